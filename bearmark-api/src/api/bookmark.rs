@@ -1,10 +1,24 @@
+use super::conditional::{Preconditions, Tagged, etag_of_versions};
+use super::configs;
 use super::errors::Error;
 use super::fairings::db::Db;
 use super::guards;
+use crate::archive::Archiver;
+use crate::db::archive::{self, BookmarkArchive};
+use crate::db::link_check::{self, LinkCheck};
+use crate::db::metadata::{self, BookmarkMetadata};
+use crate::db::reading::{self, ReadingState};
+use crate::db::reminder;
+use crate::db::revision::{self, BookmarkRevision};
+use crate::db::shortcut;
 use crate::db::{self, bookmark, folder, tag};
+use crate::utils::icalendar::{self, Event};
+use crate::utils::nullable;
 
-use diesel_async::AsyncConnection;
 use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection};
+use rocket::State;
+use rocket::http::ContentType;
 use rocket::serde::json::Json;
 use rocket::serde::{Deserialize, Serialize};
 use rocket_db_pools::Connection;
@@ -13,10 +27,24 @@ use utoipa::ToSchema;
 
 #[derive(Serialize, Deserialize, ToSchema, Debug)]
 pub struct CreateBookmark {
+    /// Filled in from the page in the background if empty, when the fetcher is enabled
+    #[serde(default)]
     pub title: String,
     pub url: String,
+    /// Free-form notes in markdown
+    pub notes: Option<String>,
+    /// A short description
+    pub description: Option<String>,
     pub folder_id: Option<i32>,
+    /// The path of folder, missing folders are created
+    pub folder: Option<String>,
     pub tags: Vec<String>,
+    /// Custom key/value metadata
+    #[schema(value_type = Option<Object>)]
+    pub attributes: Option<serde_json::Map<String, serde_json::Value>>,
+    /// A unique keyword to open the bookmark by `/api/shortcut`, the URL may be a template
+    /// with `%s` for the arguments
+    pub shortcut: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Debug)]
@@ -24,6 +52,11 @@ pub struct Bookmark {
     pub id: i32,
     pub title: String,
     pub url: String,
+    pub canonical_url: String,
+    /// Free-form notes in markdown
+    pub notes: Option<String>,
+    /// A short description
+    pub description: Option<String>,
     pub folder: Option<String>,
     pub tags: Vec<String>,
     #[schema(format = DateTime, value_type=String)]
@@ -35,6 +68,138 @@ pub struct Bookmark {
     #[schema(format = DateTime, value_type=String)]
     #[serde(with = "time::serde::rfc3339")]
     pub updated_at: time::OffsetDateTime,
+    /// The number of visits through `/go/{id}`
+    pub visit_count: i32,
+    #[schema(format = DateTime, value_type=String, nullable)]
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_visited_at: Option<time::OffsetDateTime>,
+    pub pinned: bool,
+    /// The manual order of pinned bookmarks, ascending
+    pub pin_order: Option<i32>,
+    /// The state in the read-later workflow, or null if not to be read later
+    #[schema(nullable)]
+    pub reading_state: Option<ReadingState>,
+    #[schema(format = DateTime, value_type=String, nullable)]
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub reading_state_changed_at: Option<time::OffsetDateTime>,
+    /// When it was marked unread last time
+    #[schema(format = DateTime, value_type=String, nullable)]
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub queued_at: Option<time::OffsetDateTime>,
+    /// When it was marked done
+    #[schema(format = DateTime, value_type=String, nullable)]
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub read_at: Option<time::OffsetDateTime>,
+    /// When to be reminded of the bookmark
+    #[schema(format = DateTime, value_type=String, nullable)]
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub remind_at: Option<time::OffsetDateTime>,
+    /// Custom key/value metadata
+    #[schema(value_type = Object)]
+    pub attributes: serde_json::Value,
+    /// A unique keyword to open the bookmark by `/api/shortcut`
+    pub shortcut: Option<String>,
+}
+
+type BookmarkDetails = (bookmark::Bookmark, Option<folder::Folder>, Vec<tag::Tag>);
+
+/// Set or clear the shortcut, which must be a single word.
+async fn set_shortcut(
+    db: &mut AsyncPgConnection,
+    id: i32,
+    shortcut: Option<&str>,
+) -> Result<bookmark::Bookmark, Error> {
+    let shortcut = shortcut.map(str::trim);
+    if shortcut.is_some_and(|s| s.is_empty() || s.contains(char::is_whitespace)) {
+        return Err(Error::BadRequest(
+            "Shortcut should be a single word".to_string(),
+        ));
+    }
+    shortcut::set_shortcut(db, id, shortcut)
+        .await
+        .map_err(|_| {
+            Error::BadRequest(format!(
+                "Shortcut already exists: {}",
+                shortcut.unwrap_or_default()
+            ))
+        })?
+        .ok_or_else(|| Error::NotFound("Bookmark not found".to_string()))
+}
+
+impl From<BookmarkDetails> for Bookmark {
+    fn from((m, folder, tags): BookmarkDetails) -> Self {
+        Self {
+            id: m.id,
+            title: m.title,
+            url: m.url,
+            canonical_url: m.canonical_url,
+            notes: m.notes,
+            description: m.description,
+            folder: folder.map(|f| f.path),
+            tags: tags.into_iter().map(|t| t.name).collect(),
+            created_at: m.created_at,
+            updated_at: m.updated_at,
+            deleted_at: m.deleted_at,
+            visit_count: m.visit_count,
+            last_visited_at: m.last_visited_at,
+            pinned: m.pinned,
+            pin_order: m.pin_order,
+            reading_state: m.reading_state.as_deref().and_then(ReadingState::parse),
+            reading_state_changed_at: m.reading_state_changed_at,
+            queued_at: m.queued_at,
+            read_at: m.read_at,
+            remind_at: m.remind_at,
+            attributes: m.attributes,
+            shortcut: m.shortcut,
+        }
+    }
+}
+
+/// Check the URL is HTTP(S), others like `javascript:` are not saved.
+fn check_url(url: &str) -> Result<(), Error> {
+    match url::Url::parse(url.trim()) {
+        Ok(u) if matches!(u.scheme(), "http" | "https") => Ok(()),
+        _ => Err(Error::BadRequest(format!("Not an HTTP(S) URL: {url}"))),
+    }
+}
+
+pub(super) async fn create(
+    db: &mut AsyncPgConnection,
+    payload: CreateBookmark,
+) -> Result<BookmarkDetails, Error> {
+    check_url(&payload.url)?;
+    let new = bookmark::NewBookmark {
+        title: payload.title,
+        url: payload.url,
+        notes: payload.notes,
+        description: payload.description,
+        attributes: payload.attributes.map(serde_json::Value::Object),
+    };
+    let folder_id = match (payload.folder_id, payload.folder) {
+        (Some(_), Some(_)) => {
+            return Err(Error::BadRequest(
+                "Only one of folder_id and folder is allowed".to_string(),
+            ));
+        }
+        (None, Some(path)) => folder::get_or_create_folder(db, &path).await?.map(|f| f.id),
+        (folder_id, _) => folder_id,
+    };
+    let mut m = bookmark::create_bookmark(db, &new).await;
+    if configs::is_fetcher_enabled() {
+        metadata::enqueue_bookmarks(db, &[m.id]).await;
+    }
+    if let Some(shortcut) = payload.shortcut {
+        m = set_shortcut(db, m.id, Some(&shortcut)).await?;
+    }
+
+    tag::update_bookmark_tags(db, &m, &payload.tags).await;
+
+    if let Some(folder_id) = folder_id {
+        folder::move_bookmarks(db, folder_id, &vec![m.id]).await?;
+        m.folder_id = Some(folder_id);
+    }
+
+    Ok(db::get_bookmark_details(db, vec![m]).await.remove(0))
 }
 
 /// Create a new bookmark
@@ -43,7 +208,8 @@ pub struct Bookmark {
     path = "/",
     request_body = CreateBookmark,
     responses(
-        (status = 200, description = "Bookmark created success", body = Bookmark)
+        (status = 200, description = "Bookmark created success", body = Bookmark,
+            headers(("ETag" = String, description = "The entity tag of the bookmark")))
     ),
     security(
         ("api_key" = [])
@@ -54,42 +220,79 @@ pub async fn create_bookmark(
     mut db: Connection<Db>,
     _required: guards::Auth,
     payload: Json<CreateBookmark>,
-) -> Result<Json<Bookmark>, Error> {
+) -> Result<Tagged<Json<Bookmark>>, Error> {
     let payload = payload.into_inner();
-    let (new, tags) = (
-        bookmark::NewBookmark {
-            title: payload.title,
-            url: payload.url,
-        },
-        payload.tags,
-    );
-    let (m, f, ts) = db
-        .transaction::<_, Error, _>(|db| {
-            async move {
-                let m = bookmark::create_bookmark(db, &new).await;
+    let rv = db
+        .transaction::<_, Error, _>(|db| create(db, payload).scope_boxed())
+        .await?;
 
-                tag::update_bookmark_tags(db, &m, &tags).await;
+    Ok(tagged(rv))
+}
 
-                if let Some(folder_id) = payload.folder_id {
-                    folder::move_bookmarks(db, folder_id, &vec![m.id]).await?;
-                }
+/// The version of a bookmark, which is changed by visits as well as updates.
+fn version(m: &bookmark::Bookmark) -> String {
+    format!(
+        "{}:{}:{}",
+        m.updated_at.unix_timestamp_nanos(),
+        m.visit_count,
+        m.last_visited_at
+            .map_or(0, |visited_at| visited_at.unix_timestamp_nanos())
+    )
+}
 
-                Ok(db::get_bookmark_details(db, vec![m]).await.remove(0))
-            }
-            .scope_boxed()
-        })
-        .await?;
+fn etag(m: &bookmark::Bookmark) -> String {
+    etag_of_versions([(m.id, version(m))])
+}
+
+fn tagged(details: BookmarkDetails) -> Tagged<Json<Bookmark>> {
+    let etag = etag(&details.0);
+    Tagged::new(Json(details.into()), etag)
+}
+
+/// Respond with bookmarks, or 304 if they are not modified since the client got them.
+fn respond_many(
+    conditions: &Preconditions,
+    details: Vec<BookmarkDetails>,
+) -> Tagged<Json<Vec<Bookmark>>> {
+    let etag = etag_of_versions(details.iter().map(|(m, _, _)| (m.id, version(m))));
+    conditions.respond(
+        Json(details.into_iter().map(Bookmark::from).collect()),
+        etag,
+    )
+}
 
-    Ok(Json(Bookmark {
-        id: m.id,
-        title: m.title,
-        url: m.url,
-        folder: f.map(|f| f.path),
-        tags: ts.into_iter().map(|t| t.name).collect(),
-        created_at: m.created_at,
-        updated_at: m.updated_at,
-        deleted_at: m.deleted_at,
-    }))
+/// Get a bookmark
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    params(
+        ("id" = inline(i32), Path, description = "The bookmark id")
+    ),
+    responses(
+        (status = 200, description = "Bookmark found success", body = Bookmark,
+            headers(("ETag" = String, description = "The entity tag of the bookmark"))),
+        (status = 304, description = "Bookmark not modified since `If-None-Match`"),
+        (status = 404, description = "Bookmark not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/<id>")]
+pub async fn get_bookmark(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    conditions: Preconditions,
+    id: i32,
+) -> Result<Tagged<Json<Bookmark>>, Error> {
+    let m = bookmark::Bookmark::get(&mut db, id)
+        .await
+        .filter(|m| m.deleted_at.is_none())
+        .ok_or_else(|| Error::NotFound("Bookmark not found".to_string()))?;
+    let etag = etag(&m);
+    let rv = db::get_bookmark_details(&mut db, vec![m]).await.remove(0);
+
+    Ok(conditions.respond(Json(rv.into()), etag))
 }
 
 /// Search bookmarks
@@ -99,50 +302,58 @@ pub async fn create_bookmark(
     params(
         ("q" = inline(Option<&str>), Query, description = "Search query language"),
         ("cwd" = inline(Option<&str>), Query, description = "The path of folder to search in"),
-        ("before" = inline(Option<i32>), Query, description = "The bookmark id to search before"),
-        ("limit" = inline(Option<i64>), Query, description = "The limit of search results")
+        ("before" = inline(Option<i32>), Query, description = "The bookmark id to search before, only for the `newest` sort"),
+        ("limit" = inline(Option<i64>), Query, description = "The limit of search results"),
+        ("sort" = inline(Option<&str>), Query, description = "`newest` (default), or `frecency` for the most frequently and recently visited first"),
+        ("pinned_first" = inline(Option<bool>), Query, description = "Float pinned bookmarks to the top in their manual order")
     ),
     responses(
         (status = 200, description = "Bookmarks searched success", body = Vec<Bookmark>),
+        (status = 304, description = "Bookmarks not modified since `If-None-Match`"),
         (status = 400, description = "Bad query request")
     ),
     security(
         ("api_key" = [])
     )
 )]
-#[get("/?<q>&<cwd>&<before>&<limit>")]
+#[get("/?<q>&<cwd>&<before>&<limit>&<sort>&<pinned_first>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn search_bookmarks(
     mut db: Connection<Db>,
     _required: guards::Auth,
+    conditions: Preconditions,
     q: Option<&str>,
     cwd: Option<&str>,
     before: Option<i32>,
     limit: Option<i64>,
-) -> Result<Json<Vec<Bookmark>>, Error> {
+    sort: Option<&str>,
+    pinned_first: Option<bool>,
+) -> Result<Tagged<Json<Vec<Bookmark>>>, Error> {
+    let sort = match sort {
+        None | Some("newest") => db::Sort::Newest,
+        Some("frecency") => db::Sort::Frecency,
+        Some(sort) => return Err(Error::BadRequest(format!("Unknown sort {sort}"))),
+    };
+    let pinned_first = pinned_first.unwrap_or_default();
+    if (sort != db::Sort::Newest || pinned_first) && before.is_some() {
+        return Err(Error::BadRequest(
+            "Paging with before is only supported by the newest sort without pinned_first"
+                .to_string(),
+        ));
+    }
     let rv = crate::db::search_bookmarks(
         &mut db,
         q,
         cwd,
+        sort,
+        pinned_first,
         before.unwrap_or_default(),
         limit.unwrap_or(10),
     )
     .await?;
     debug!(?rv, "search results");
 
-    Ok(Json(
-        rv.into_iter()
-            .map(|(m, folder, tags)| Bookmark {
-                id: m.id,
-                title: m.title,
-                url: m.url,
-                folder: folder.map(|f| f.path),
-                tags: tags.into_iter().map(|t| t.name).collect(),
-                created_at: m.created_at,
-                updated_at: m.updated_at,
-                deleted_at: m.deleted_at,
-            })
-            .collect(),
-    ))
+    Ok(respond_many(&conditions, rv))
 }
 
 /// Delete a bookmark
@@ -154,7 +365,8 @@ pub async fn search_bookmarks(
     ),
     responses(
         (status = 200, description = "Bookmark deleted success"),
-        (status = 404, description = "Bookmark not found")
+        (status = 404, description = "Bookmark not found"),
+        (status = 412, description = "Bookmark modified since `If-Match`")
     ),
     security(
         ("api_key" = [])
@@ -164,21 +376,193 @@ pub async fn search_bookmarks(
 pub async fn delete_bookmark(
     mut db: Connection<Db>,
     _required: guards::Auth,
+    conditions: Preconditions,
     id: i32,
 ) -> Result<&'static str, Error> {
-    let effected = bookmark::delete_bookmarks(&mut db, vec![id]).await == 1;
-    if effected {
-        Ok("Deleted")
-    } else {
-        Err(Error::NotFound("Bookmark not found".to_string()))
-    }
+    db.transaction::<_, Error, _>(|db| {
+        async move {
+            let m = bookmark::Bookmark::get_for_update(db, id)
+                .await
+                .filter(|m| m.deleted_at.is_none())
+                .ok_or_else(|| Error::NotFound("Bookmark not found".to_string()))?;
+            conditions.check(&etag(&m))?;
+            bookmark::delete_bookmarks(db, vec![id]).await;
+            Ok("Deleted")
+        }
+        .scope_boxed()
+    })
+    .await
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Debug)]
 pub struct ModifyBookmark {
     pub title: Option<String>,
     pub url: Option<String>,
+    pub notes: Option<String>,
+    pub description: Option<String>,
     pub tags: Option<Vec<String>>,
+    /// Move into the folder of this id, or out of any folder if null
+    #[serde(
+        default,
+        deserialize_with = "nullable::deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<i32>, nullable)]
+    pub folder_id: Option<Option<i32>>,
+    /// Move into the folder of this path, or out of any folder if null
+    #[serde(
+        default,
+        deserialize_with = "nullable::deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>, nullable)]
+    pub folder: Option<Option<String>>,
+    /// Pin or unpin the bookmark, unpinning clears the order
+    pub pinned: Option<bool>,
+    /// The manual order of the pinned bookmark, or null to order by default
+    #[serde(
+        default,
+        deserialize_with = "nullable::deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<i32>, nullable)]
+    pub pin_order: Option<Option<i32>>,
+    /// Change the state in the read-later workflow, or null to take it out of the read-later workflow
+    #[serde(
+        default,
+        deserialize_with = "nullable::deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<ReadingState>, nullable)]
+    pub reading_state: Option<Option<ReadingState>>,
+    /// When to be reminded of the bookmark, or null to clear the reminder
+    #[serde(
+        default,
+        deserialize_with = "nullable::deserialize_some_rfc3339",
+        serialize_with = "nullable::serialize_some_rfc3339",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(format = DateTime, value_type = Option<String>, nullable)]
+    pub remind_at: Option<Option<time::OffsetDateTime>>,
+    /// Replace all the custom key/value metadata
+    #[schema(value_type = Option<Object>)]
+    pub attributes: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Set the unique shortcut, or null to clear it
+    #[serde(
+        default,
+        deserialize_with = "nullable::deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>, nullable)]
+    pub shortcut: Option<Option<String>>,
+}
+
+async fn update(
+    db: &mut AsyncPgConnection,
+    id: i32,
+    payload: ModifyBookmark,
+) -> Result<BookmarkDetails, Error> {
+    let (modify_bookmark, modify_tags) = (
+        if payload.title.is_some()
+            || payload.url.is_some()
+            || payload.notes.is_some()
+            || payload.description.is_some()
+            || payload.pinned.is_some()
+            || payload.pin_order.is_some()
+            || payload.remind_at.is_some()
+            || payload.attributes.is_some()
+        {
+            Some(bookmark::ModifyBookmark {
+                title: payload.title,
+                url: payload.url,
+                notes: payload.notes,
+                description: payload.description,
+                pinned: payload.pinned,
+                pin_order: payload
+                    .pin_order
+                    .or((payload.pinned == Some(false)).then_some(None)),
+                remind_at: payload.remind_at,
+                attributes: payload.attributes.map(serde_json::Value::Object),
+            })
+        } else {
+            None
+        },
+        payload.tags,
+    );
+    let modify_folder = match (payload.folder_id, payload.folder) {
+        (Some(_), Some(_)) => {
+            return Err(Error::BadRequest(
+                "Only one of folder_id and folder is allowed".to_string(),
+            ));
+        }
+        (Some(Some(folder_id)), None) => Some(Some(
+            folder::Folder::get(db, folder_id)
+                .await
+                .ok_or_else(|| Error::NotFound("Folder not found".to_string()))?
+                .id,
+        )),
+        (None, Some(Some(path))) => Some(Some(
+            folder::Folder::get_by_path(db, &format!("/{}", path.trim_matches('/')))
+                .await
+                .ok_or_else(|| Error::NotFound("Folder not found".to_string()))?
+                .id,
+        )),
+        (Some(None), None) | (None, Some(None)) => Some(None),
+        (None, None) => None,
+    };
+    if let Some(url) = modify_bookmark.as_ref().and_then(|m| m.url.as_deref()) {
+        check_url(url)?;
+    }
+    if modify_bookmark.is_none()
+        && modify_tags.is_none()
+        && modify_folder.is_none()
+        && payload.reading_state.is_none()
+        && payload.shortcut.is_none()
+    {
+        return Err(Error::BadRequest("No changes".to_string()));
+    }
+    let before = revision::take_snapshots(db, &[id]).await;
+
+    let m = if let Some(payload) = modify_bookmark {
+        let url_changed = payload.url.is_some();
+        let rv = bookmark::update_bookmark(db, id, payload).await;
+        if url_changed && rv.is_some() && configs::is_fetcher_enabled() {
+            metadata::enqueue_bookmarks(db, &[id]).await;
+        }
+        rv
+    } else {
+        bookmark::Bookmark::get(db, id).await
+    }
+    .ok_or_else(|| Error::NotFound("Bookmark not found".to_string()))?;
+
+    if let Some(payload) = modify_tags {
+        tag::update_bookmark_tags(db, &m, &payload).await;
+    }
+
+    if let Some(folder_id) = modify_folder {
+        if let Some(folder_id) = folder_id {
+            folder::move_bookmarks(db, folder_id, &vec![m.id]).await?;
+        } else {
+            folder::move_out_bookmarks(db, &vec![m.id]).await;
+        }
+    }
+
+    if let Some(shortcut) = payload.shortcut {
+        set_shortcut(db, m.id, shortcut.as_deref()).await?;
+    }
+
+    if let Some(state) = payload.reading_state {
+        reading::set_reading_state(db, m.id, state)
+            .await?
+            .ok_or_else(|| Error::NotFound("Bookmark not found".to_string()))?;
+    }
+    revision::record_revisions(db, before).await;
+
+    // read again for the changes of tags, folder and reading state
+    let m = bookmark::Bookmark::get(db, id)
+        .await
+        .ok_or_else(|| Error::NotFound("Bookmark not found".to_string()))?;
+    Ok(db::get_bookmark_details(db, vec![m]).await.remove(0))
 }
 
 /// Update a bookmark
@@ -190,9 +574,11 @@ pub struct ModifyBookmark {
     ),
     request_body = ModifyBookmark,
     responses(
-        (status = 200, description = "Bookmark updated success", body = Bookmark),
+        (status = 200, description = "Bookmark updated success", body = Bookmark,
+            headers(("ETag" = String, description = "The entity tag of the bookmark"))),
         (status = 400, description = "No changes"),
-        (status = 404, description = "Bookmark not found")
+        (status = 404, description = "Bookmark not found"),
+        (status = 412, description = "Bookmark modified since `If-Match`")
     ),
     security(
         ("api_key" = [])
@@ -202,175 +588,1700 @@ pub struct ModifyBookmark {
 pub async fn update_bookmark(
     mut db: Connection<Db>,
     _required: guards::Auth,
+    conditions: Preconditions,
     id: i32,
     payload: Json<ModifyBookmark>,
-) -> Result<Json<Bookmark>, Error> {
+) -> Result<Tagged<Json<Bookmark>>, Error> {
     let payload = payload.into_inner();
-    let (modify_bookmark, modify_tags) = (
-        if payload.title.is_some() || payload.url.is_some() {
-            Some(bookmark::ModifyBookmark {
-                title: payload.title,
-                url: payload.url,
-            })
-        } else {
-            None
-        },
-        payload.tags,
-    );
-    if modify_bookmark.is_none() && modify_tags.is_none() {
-        return Err(Error::BadRequest("No changes".to_string()));
-    }
-
-    let (m, folder, tags) = db
+    let rv = db
         .transaction::<_, Error, _>(|db| {
             async move {
-                let m = if let Some(payload) = modify_bookmark {
-                    bookmark::update_bookmark(db, id, payload).await
-                } else {
-                    bookmark::Bookmark::get(db, id).await
-                }
-                .ok_or_else(|| Error::NotFound("Bookmark not found".to_string()))?;
-
-                if let Some(payload) = modify_tags {
-                    tag::update_bookmark_tags(db, &m, &payload).await;
-                }
-
-                Ok(db::get_bookmark_details(db, vec![m.clone()])
+                let m = bookmark::Bookmark::get_for_update(db, id)
                     .await
-                    .remove(0))
+                    .filter(|m| m.deleted_at.is_none())
+                    .ok_or_else(|| Error::NotFound("Bookmark not found".to_string()))?;
+                conditions.check(&etag(&m))?;
+                update(db, id, payload).await
             }
             .scope_boxed()
         })
         .await?;
 
-    Ok(Json(Bookmark {
-        id: m.id,
-        title: m.title.clone(),
-        url: m.url.clone(),
-        folder: folder.clone().map(|f| f.path),
-        tags: tags.iter().map(|t| t.name.clone()).collect(),
-        created_at: m.created_at,
-        updated_at: m.updated_at,
-        deleted_at: m.deleted_at,
-    }))
+    Ok(tagged(rv))
 }
 
-pub fn routes() -> Vec<rocket::Route> {
-    routes![
-        create_bookmark,
-        search_bookmarks,
-        delete_bookmark,
-        update_bookmark
-    ]
+/// The state of a bookmark in a revision
+#[derive(Serialize, Deserialize, ToSchema, Debug, PartialEq, Eq)]
+pub struct RevisionState {
+    pub title: String,
+    pub url: String,
+    pub folder: Option<String>,
+    pub tags: Vec<String>,
 }
 
-#[cfg(not(tarpaulin_include))]
-pub(crate) mod misc {
-    use super::*;
-
-    use utoipa::{OpenApi, Path};
-
-    pub struct ApiDoc;
-
-    impl OpenApi for ApiDoc {
-        fn openapi() -> utoipa::openapi::OpenApi {
-            use utoipa::openapi::{
-                InfoBuilder, OpenApiBuilder,
-                security::{ApiKey, ApiKeyValue, SecurityScheme},
-            };
-
-            let mut api = OpenApiBuilder::new()
-                .info(
-                    InfoBuilder::new()
-                        .title("Bookmarks API")
-                        .description(Some("Bookmarks API"))
-                        .version("1.0")
-                        .build(),
-                )
-                .paths(bearmark_macro::utoipa_paths!(
-                    "/api/bookmarks",
-                    create_bookmark,
-                    search_bookmarks,
-                    delete_bookmark,
-                    update_bookmark
-                ))
-                .components(Some(bearmark_macro::utoipa_components![
-                    CreateBookmark,
-                    ModifyBookmark,
-                    Bookmark
-                ]))
-                .build();
+impl From<revision::Snapshot> for RevisionState {
+    fn from(s: revision::Snapshot) -> Self {
+        Self {
+            title: s.title,
+            url: s.url,
+            folder: s.folder,
+            tags: s.tags,
+        }
+    }
+}
 
-            api.components.as_mut().unwrap().add_security_scheme(
-                "api_key",
-                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Authorization"))),
-            );
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct Revision {
+    pub id: i32,
+    pub bookmark_id: i32,
+    pub before: RevisionState,
+    pub after: RevisionState,
+    /// Names of the changed fields
+    pub changed: Vec<String>,
+    #[schema(format = DateTime, value_type=String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: time::OffsetDateTime,
+}
 
-            api
+impl From<BookmarkRevision> for Revision {
+    fn from(r: BookmarkRevision) -> Self {
+        let (before, after) = (r.before(), r.after());
+        let changed = [
+            ("title", before.title != after.title),
+            ("url", before.url != after.url),
+            ("folder", before.folder != after.folder),
+            ("tags", before.tags != after.tags),
+        ]
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(name, _)| name.to_string())
+        .collect();
+        Self {
+            id: r.id,
+            bookmark_id: r.bookmark_id,
+            before: before.into(),
+            after: after.into(),
+            changed,
+            created_at: r.created_at,
         }
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use crate::api::configs::{self, Config};
-    use crate::db::bookmark::test::rand_bookmark;
-    use crate::utils::rand::rand_str;
+/// List the revisions of a bookmark, the latest first
+#[utoipa::path(
+    get,
+    path = "/{id}/history",
+    params(
+        ("id" = inline(i32), Path, description = "The bookmark id")
+    ),
+    responses(
+        (status = 200, description = "Bookmark revisions listed success", body = Vec<Revision>)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/<id>/history")]
+pub async fn get_bookmark_history(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    id: i32,
+) -> Json<Vec<Revision>> {
+    let revisions = revision::get_revisions(&mut db, id).await;
 
-    use itertools::Itertools;
-    use rocket::fairing::AdHoc;
-    use rocket::http::Status;
-    use rocket::local::{asynchronous, blocking};
-    use rocket_db_pools::Database;
-    use tracing::info;
+    Json(revisions.into_iter().map(Revision::from).collect())
+}
 
-    fn test_app() -> rocket::Rocket<rocket::Build> {
-        rocket::custom(configs::config_provider())
-            .attach(Db::init())
-            .mount("/", routes())
-            .attach(AdHoc::config::<Config>())
+async fn revert(
+    db: &mut AsyncPgConnection,
+    id: i32,
+    revision_id: i32,
+) -> Result<BookmarkDetails, Error> {
+    let state = BookmarkRevision::get(db, revision_id)
+        .await
+        .filter(|r| r.bookmark_id == id)
+        .ok_or_else(|| Error::NotFound("Revision not found".to_string()))?
+        .before();
+    let before = revision::take_snapshots(db, &[id]).await;
+    let Some((_, current)) = before.first() else {
+        return Err(Error::NotFound("Bookmark not found".to_string()));
+    };
+    let url_changed = current.url != state.url;
+
+    let m = bookmark::update_bookmark(
+        db,
+        id,
+        bookmark::ModifyBookmark {
+            title: Some(state.title),
+            url: url_changed.then_some(state.url),
+            notes: None,
+            description: None,
+            pinned: None,
+            pin_order: None,
+            remind_at: None,
+            attributes: None,
+        },
+    )
+    .await
+    .ok_or_else(|| Error::NotFound("Bookmark not found".to_string()))?;
+    if url_changed && configs::is_fetcher_enabled() {
+        metadata::enqueue_bookmarks(db, &[id]).await;
     }
-
-    fn test_client() -> blocking::Client {
-        blocking::Client::tracked(test_app()).expect("valid rocket instance")
+    tag::update_bookmark_tags(db, &m, &state.tags).await;
+    // the folder may be deleted since then
+    let f = match state.folder {
+        Some(path) => folder::get_or_create_folder(db, &path).await?,
+        None => None,
+    };
+    if let Some(f) = f {
+        folder::move_bookmarks(db, f.id, &vec![id]).await?;
+    } else {
+        folder::move_out_bookmarks(db, &vec![id]).await;
     }
+    revision::record_revisions(db, before).await;
 
-    async fn test_async_client() -> asynchronous::Client {
-        asynchronous::Client::tracked(test_app())
-            .await
-            .expect("valid rocket instance")
-    }
+    let m = bookmark::Bookmark::get(db, id)
+        .await
+        .ok_or_else(|| Error::NotFound("Bookmark not found".to_string()))?;
+    Ok(db::get_bookmark_details(db, vec![m]).await.remove(0))
+}
 
-    #[test]
-    fn create_bookmark() {
-        let client = test_client();
-        let payload = CreateBookmark {
-            url: "https://www.rust-lang.org".to_string(),
-            title: "Rust".to_string(),
-            folder_id: None,
-            tags: vec![rand_str(4), rand_str(4)],
-        };
-        let response = client
-            .post(uri!(super::create_bookmark))
-            .json(&payload)
-            .dispatch();
+/// Revert a bookmark to the state before a revision
+#[utoipa::path(
+    post,
+    path = "/{id}/history/{revision_id}/revert",
+    params(
+        ("id" = inline(i32), Path, description = "The bookmark id"),
+        ("revision_id" = inline(i32), Path, description = "The revision id to revert")
+    ),
+    responses(
+        (status = 200, description = "Bookmark reverted success", body = Bookmark,
+            headers(("ETag" = String, description = "The entity tag of the bookmark"))),
+        (status = 404, description = "Bookmark or revision not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/<id>/history/<revision_id>/revert")]
+pub async fn revert_bookmark(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    id: i32,
+    revision_id: i32,
+) -> Result<Tagged<Json<Bookmark>>, Error> {
+    let rv = db
+        .transaction::<_, Error, _>(|db| revert(db, id, revision_id).scope_boxed())
+        .await?;
+
+    Ok(tagged(rv))
+}
+
+/// List bookmarks due to be reminded of, the earliest first
+#[utoipa::path(
+    get,
+    path = "/reminders",
+    params(
+        ("limit" = inline(Option<i64>), Query, description = "The limit of results")
+    ),
+    responses(
+        (status = 200, description = "Due reminders listed success", body = Vec<Bookmark>)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/reminders?<limit>")]
+pub async fn list_due_reminders(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    limit: Option<i64>,
+) -> Json<Vec<Bookmark>> {
+    let due = reminder::get_due_reminders(&mut db, limit.unwrap_or(10)).await;
+    let rv = db::get_bookmark_details(&mut db, due).await;
+
+    Json(rv.into_iter().map(Bookmark::from).collect())
+}
+
+/// Subscribe reminders of bookmarks in calendar apps
+#[utoipa::path(
+    get,
+    path = "/reminders.ics",
+    params(
+        ("key" = inline(Option<&str>), Query, description = "The API key, for calendar apps which can not set the `Authorization` header")
+    ),
+    responses(
+        (status = 200, description = "The iCalendar feed of reminders", content_type = "text/calendar", body = String)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/reminders.ics")]
+pub async fn get_reminders_feed(
+    mut db: Connection<Db>,
+    _required: guards::QueryAuth,
+) -> (ContentType, String) {
+    let events = reminder::get_reminders(&mut db)
+        .await
+        .into_iter()
+        .filter_map(|m| {
+            let description = [m.description.as_deref(), Some(m.url.as_str())]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join("\n");
+            Some(Event {
+                uid: format!("bookmark-{}@bearmark", m.id),
+                stamp: m.updated_at,
+                start: m.remind_at?,
+                summary: if m.title.is_empty() {
+                    m.url.clone()
+                } else {
+                    m.title
+                },
+                description: Some(description),
+                url: Some(m.url),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    (
+        ContentType::Calendar,
+        icalendar::render_calendar("Bearmark reminders", &events),
+    )
+}
+
+/// List unread bookmarks to read later, the earliest queued first
+#[utoipa::path(
+    get,
+    path = "/queue",
+    params(
+        ("limit" = inline(Option<i64>), Query, description = "The limit of results")
+    ),
+    responses(
+        (status = 200, description = "Reading queue listed success", body = Vec<Bookmark>),
+        (status = 304, description = "Reading queue not modified since `If-None-Match`")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/queue?<limit>")]
+pub async fn list_reading_queue(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    conditions: Preconditions,
+    limit: Option<i64>,
+) -> Tagged<Json<Vec<Bookmark>>> {
+    let queue = reading::get_reading_queue(&mut db, limit.unwrap_or(10)).await;
+    let rv = db::get_bookmark_details(&mut db, queue).await;
+
+    respond_many(&conditions, rv)
+}
+
+/// List pinned bookmarks in their manual order
+#[utoipa::path(
+    get,
+    path = "/pinned",
+    responses(
+        (status = 200, description = "Pinned bookmarks listed success", body = Vec<Bookmark>),
+        (status = 304, description = "Pinned bookmarks not modified since `If-None-Match`")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/pinned")]
+pub async fn list_pinned_bookmarks(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    conditions: Preconditions,
+) -> Tagged<Json<Vec<Bookmark>>> {
+    let pinned = bookmark::get_pinned_bookmarks(&mut db).await;
+    let rv = db::get_bookmark_details(&mut db, pinned).await;
+
+    respond_many(&conditions, rv)
+}
+
+/// Get the metadata fetched from the page of a bookmark
+#[utoipa::path(
+    get,
+    path = "/{id}/metadata",
+    params(
+        ("id" = inline(i32), Path, description = "The bookmark id")
+    ),
+    responses(
+        (status = 200, description = "Bookmark metadata found success", body = BookmarkMetadata),
+        (status = 404, description = "Bookmark metadata not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/<id>/metadata")]
+pub async fn get_bookmark_metadata(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    id: i32,
+) -> Result<Json<BookmarkMetadata>, Error> {
+    BookmarkMetadata::get(&mut db, id)
+        .await
+        .map(Json)
+        .ok_or_else(|| Error::NotFound("Bookmark metadata not found".to_string()))
+}
+
+/// Archive the current page of a bookmark for reading offline
+#[utoipa::path(
+    post,
+    path = "/{id}/archive",
+    params(
+        ("id" = inline(i32), Path, description = "The bookmark id"),
+        ("inline_assets" = Option<bool>, Query, description = "Inline images, scripts and stylesheets of the page")
+    ),
+    responses(
+        (status = 200, description = "Bookmark archived success", body = BookmarkArchive),
+        (status = 400, description = "Archiving is disabled, or failed to fetch the page"),
+        (status = 404, description = "Bookmark not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/<id>/archive?<inline_assets>")]
+pub async fn archive_bookmark(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    archiver: &State<Archiver>,
+    id: i32,
+    inline_assets: Option<bool>,
+) -> Result<Json<BookmarkArchive>, Error> {
+    let m = bookmark::Bookmark::get(&mut db, id)
+        .await
+        .filter(|m| m.deleted_at.is_none())
+        .ok_or_else(|| Error::NotFound("Bookmark not found".to_string()))?;
+    let inline_assets = inline_assets.unwrap_or(archiver.inline_assets());
+
+    Ok(Json(archiver.snapshot(&mut db, &m, inline_assets).await?))
+}
+
+#[derive(Responder)]
+pub struct ArchivedPage {
+    content: Vec<u8>,
+    content_type: ContentType,
+    // Archived pages are untrusted, never run their scripts within the origin of the API
+    csp: rocket::http::Header<'static>,
+}
+
+/// Get the latest archived page of a bookmark
+#[utoipa::path(
+    get,
+    path = "/{id}/archive",
+    params(
+        ("id" = inline(i32), Path, description = "The bookmark id")
+    ),
+    responses(
+        (status = 200, description = "The archived page"),
+        (status = 404, description = "Bookmark archive not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/<id>/archive")]
+pub async fn get_bookmark_archive(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    archiver: &State<Archiver>,
+    id: i32,
+) -> Result<ArchivedPage, Error> {
+    let archived = archive::get_latest_archive(&mut db, id)
+        .await
+        .ok_or_else(|| Error::NotFound("Bookmark archive not found".to_string()))?;
+    let content = archiver.load(&mut db, &archived).await?;
+
+    Ok(ArchivedPage {
+        content,
+        content_type: ContentType::parse_flexible(&archived.content_type)
+            .unwrap_or(ContentType::Binary),
+        csp: rocket::http::Header::new("Content-Security-Policy", "sandbox"),
+    })
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct BrokenLink {
+    pub bookmark: Bookmark,
+    pub check: LinkCheck,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct LinkReport {
+    /// The number of checked links
+    pub checked: i64,
+    /// Links failed in consecutive checks, the most failed first
+    pub broken: Vec<BrokenLink>,
+}
+
+/// Report the broken links of bookmarks
+#[utoipa::path(
+    get,
+    path = "/links/report",
+    responses(
+        (status = 200, description = "Link report generated success", body = LinkReport)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/links/report")]
+pub async fn get_link_report(mut db: Connection<Db>, _required: guards::Auth) -> Json<LinkReport> {
+    let checked = link_check::count_checked_links(&mut db).await;
+    let (checks, bookmarks): (Vec<_>, Vec<_>) = link_check::get_broken_links(&mut db)
+        .await
+        .into_iter()
+        .unzip();
+    let broken = db::get_bookmark_details(&mut db, bookmarks)
+        .await
+        .into_iter()
+        .zip(checks)
+        .map(|(details, check)| BrokenLink {
+            bookmark: details.into(),
+            check,
+        })
+        .collect();
+
+    Json(LinkReport { checked, broken })
+}
+
+/// List groups of duplicate bookmarks
+#[utoipa::path(
+    get,
+    path = "/duplicates",
+    responses(
+        (status = 200, description = "Duplicate bookmarks found success", body = Vec<Vec<Bookmark>>)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/duplicates")]
+pub async fn list_duplicate_bookmarks(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+) -> Json<Vec<Vec<Bookmark>>> {
+    let groups = bookmark::find_duplicate_bookmarks(&mut db).await;
+    let sizes = groups.iter().map(|g| g.len()).collect::<Vec<_>>();
+    let mut details = db::get_bookmark_details(&mut db, groups.into_iter().flatten().collect())
+        .await
+        .into_iter()
+        .map(Bookmark::from);
+
+    Json(
+        sizes
+            .into_iter()
+            .map(|size| details.by_ref().take(size).collect())
+            .collect(),
+    )
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct MergeBookmarks {
+    /// The bookmark to keep
+    pub keep: i32,
+    /// The bookmarks to be merged into the kept one, and then deleted
+    pub duplicates: Vec<i32>,
+    /// The folder of merged bookmark, defaults to the first folder found starting from the kept one
+    pub folder_id: Option<i32>,
+}
+
+/// Merge duplicate bookmarks into one
+#[utoipa::path(
+    post,
+    path = "/merge",
+    request_body = MergeBookmarks,
+    responses(
+        (status = 200, description = "Bookmarks merged success", body = Bookmark),
+        (status = 400, description = "Nothing to merge"),
+        (status = 404, description = "Bookmark not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/merge", format = "application/json", data = "<payload>")]
+pub async fn merge_bookmarks(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    payload: Json<MergeBookmarks>,
+) -> Result<Json<Bookmark>, Error> {
+    use itertools::Itertools;
+
+    let MergeBookmarks {
+        keep,
+        duplicates,
+        folder_id,
+    } = payload.into_inner();
+    let duplicates = duplicates
+        .into_iter()
+        .filter(|id| *id != keep)
+        .unique()
+        .collect_vec();
+    if duplicates.is_empty() {
+        return Err(Error::BadRequest("Nothing to merge".to_string()));
+    }
+
+    let rv = db
+        .transaction::<_, Error, _>(|db| {
+            async move {
+                let ids = std::iter::once(keep)
+                    .chain(duplicates.clone())
+                    .collect_vec();
+                let bookmarks = bookmark::Bookmark::get_many(db, &ids).await;
+                if bookmarks.len() != ids.len() {
+                    return Err(Error::NotFound("Bookmark not found".to_string()));
+                }
+                let details = db::get_bookmark_details(db, bookmarks).await;
+                let before = revision::take_snapshots(db, &[keep]).await;
+
+                let tags = details
+                    .iter()
+                    .flat_map(|(_, _, tags)| tags.iter().map(|t| t.name.clone()))
+                    .unique()
+                    .collect_vec();
+                let folder_id =
+                    folder_id.or_else(|| details.iter().find_map(|(m, _, _)| m.folder_id));
+                let (m, _, _) = details.into_iter().next().unwrap();
+
+                tag::update_bookmark_tags(db, &m, &tags).await;
+                if let Some(folder_id) = folder_id {
+                    folder::move_bookmarks(db, folder_id, &vec![m.id]).await?;
+                }
+                bookmark::delete_bookmarks(db, duplicates).await;
+                revision::record_revisions(db, before).await;
+
+                let m = bookmark::Bookmark::get(db, m.id).await.unwrap();
+                Ok(db::get_bookmark_details(db, vec![m]).await.remove(0))
+            }
+            .scope_boxed()
+        })
+        .await?;
+
+    Ok(Json(rv.into()))
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlMatching {
+    /// Match the URL as it is
+    Exact,
+    /// Match the canonical URL
+    #[default]
+    Normalized,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct LookupBookmarks {
+    pub urls: Vec<String>,
+    #[serde(default)]
+    pub matching: UrlMatching,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct LookupMatch {
+    pub id: i32,
+    pub folder: Option<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct LookupResult {
+    pub url: String,
+    pub bookmarks: Vec<LookupMatch>,
+}
+
+/// Look up bookmarks by URLs
+#[utoipa::path(
+    post,
+    path = "/lookup",
+    request_body = LookupBookmarks,
+    responses(
+        (status = 200, description = "Bookmarks looked up success", body = Vec<LookupResult>)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/lookup", format = "application/json", data = "<payload>")]
+pub async fn lookup_bookmarks(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    payload: Json<LookupBookmarks>,
+) -> Json<Vec<LookupResult>> {
+    use crate::utils::canonical_url::canonicalize;
+
+    let LookupBookmarks { urls, matching } = payload.into_inner();
+    let canonical = matching == UrlMatching::Normalized;
+    let found = bookmark::find_bookmarks_by_urls(&mut db, &urls, canonical).await;
+    let found = db::get_bookmark_details(&mut db, found)
+        .await
+        .into_iter()
+        .map(|(m, folder, tags)| {
+            let key = if canonical { m.canonical_url } else { m.url };
+            let matched = LookupMatch {
+                id: m.id,
+                folder: folder.map(|f| f.path),
+                tags: tags.into_iter().map(|t| t.name).collect(),
+            };
+            (key, matched)
+        })
+        .collect::<Vec<_>>();
+
+    Json(
+        urls.into_iter()
+            .map(|url| {
+                let key = if canonical {
+                    canonicalize(&url)
+                } else {
+                    url.clone()
+                };
+                let bookmarks = found
+                    .iter()
+                    .filter(|(k, _)| *k == key)
+                    .map(|(_, m)| m.clone())
+                    .collect();
+                LookupResult { url, bookmarks }
+            })
+            .collect(),
+    )
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BatchOperation {
+    /// Create a new bookmark
+    Create(CreateBookmark),
+    /// Update a bookmark
+    Update { id: i32, changes: ModifyBookmark },
+    /// Delete bookmarks
+    Delete { ids: Vec<i32> },
+    /// Move bookmarks into a folder, or out of any folder if `folder_id` is null
+    Move {
+        ids: Vec<i32>,
+        folder_id: Option<i32>,
+    },
+    /// Add tags to and remove tags from bookmarks
+    Tag {
+        ids: Vec<i32>,
+        #[serde(default)]
+        add: Vec<String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct Batch {
+    pub operations: Vec<BatchOperation>,
+    /// Roll back all operations if any of them fails
+    #[serde(default)]
+    pub atomic: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Default)]
+pub struct BatchResult {
+    /// The number of affected bookmarks
+    pub affected: usize,
+    /// The created or updated bookmark
+    pub bookmark: Option<Bookmark>,
+    pub error: Option<String>,
+}
+
+/// Returns the deduplicated ids, if all bookmarks exist and are not deleted.
+async fn get_existing_ids(db: &mut AsyncPgConnection, ids: &[i32]) -> Result<Vec<i32>, Error> {
+    use itertools::Itertools;
+
+    let ids = ids.iter().copied().unique().collect_vec();
+    if bookmark::Bookmark::get_many(db, &ids).await.len() != ids.len() {
+        return Err(Error::NotFound("Bookmark not found".to_string()));
+    }
+    Ok(ids)
+}
+
+async fn apply(db: &mut AsyncPgConnection, op: BatchOperation) -> Result<BatchResult, Error> {
+    Ok(match op {
+        BatchOperation::Create(payload) => BatchResult {
+            affected: 1,
+            bookmark: Some(create(db, payload).await?.into()),
+            ..Default::default()
+        },
+        BatchOperation::Update { id, changes } => BatchResult {
+            affected: 1,
+            bookmark: Some(update(db, id, changes).await?.into()),
+            ..Default::default()
+        },
+        BatchOperation::Delete { ids } => {
+            let ids = get_existing_ids(db, &ids).await?;
+            BatchResult {
+                affected: bookmark::delete_bookmarks(db, ids).await,
+                ..Default::default()
+            }
+        }
+        BatchOperation::Move { ids, folder_id } => {
+            let ids = get_existing_ids(db, &ids).await?;
+            let before = revision::take_snapshots(db, &ids).await;
+            let affected = if let Some(folder_id) = folder_id {
+                folder::move_bookmarks(db, folder_id, &ids).await?
+            } else {
+                folder::move_out_bookmarks(db, &ids).await
+            };
+            revision::record_revisions(db, before).await;
+            BatchResult {
+                affected,
+                ..Default::default()
+            }
+        }
+        BatchOperation::Tag { ids, add, remove } => {
+            if add.is_empty() && remove.is_empty() {
+                return Err(Error::BadRequest("No changes".to_string()));
+            }
+            let ids = get_existing_ids(db, &ids).await?;
+            let before = revision::take_snapshots(db, &ids).await;
+            tag::remove_bookmarks_tags(db, &ids, &remove).await;
+            tag::add_bookmarks_tags(db, &ids, &add).await;
+            revision::record_revisions(db, before).await;
+            BatchResult {
+                affected: ids.len(),
+                ..Default::default()
+            }
+        }
+    })
+}
+
+/// Apply a batch of operations on bookmarks
+#[utoipa::path(
+    post,
+    path = "/batch",
+    request_body = Batch,
+    responses(
+        (status = 200, description = "Batch applied, with the result of each operation", body = Vec<BatchResult>),
+        (status = 400, description = "Operation failed in the atomic mode"),
+        (status = 404, description = "Bookmark not found in the atomic mode")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/batch", format = "application/json", data = "<payload>")]
+pub async fn batch_bookmarks(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    payload: Json<Batch>,
+) -> Result<Json<Vec<BatchResult>>, Error> {
+    let Batch { operations, atomic } = payload.into_inner();
+    let rv = db
+        .transaction::<_, Error, _>(|db| {
+            async move {
+                let mut results = vec![];
+                for (idx, op) in operations.into_iter().enumerate() {
+                    // each operation runs in a savepoint, so a failed one leaves no changes
+                    let rv = db
+                        .transaction::<_, Error, _>(|db| apply(db, op).scope_boxed())
+                        .await;
+                    match rv {
+                        Ok(rv) => results.push(rv),
+                        Err(e) if atomic => return Err(e.context(format!("Operation #{idx}"))),
+                        Err(e) => results.push(BatchResult {
+                            error: Some(e.to_string()),
+                            ..Default::default()
+                        }),
+                    }
+                }
+                Ok(results)
+            }
+            .scope_boxed()
+        })
+        .await?;
+
+    Ok(Json(rv))
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BulkAction {
+    /// Add tags to the matched bookmarks
+    AddTags { tags: Vec<String> },
+    /// Remove tags from the matched bookmarks
+    RemoveTags { tags: Vec<String> },
+    /// Move the matched bookmarks into a folder, or out of any folder if `folder_id` is null
+    Move { folder_id: Option<i32> },
+    /// Delete the matched bookmarks
+    Delete,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct Bulk {
+    /// Search query language, selecting the bookmarks to act on
+    pub q: String,
+    /// The path of folder to search in
+    pub cwd: Option<String>,
+    pub action: BulkAction,
+    /// Only count the matched bookmarks, without applying the action
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct BulkResult {
+    /// The number of matched bookmarks
+    pub affected: usize,
+    /// A sample of the matched bookmarks, before the action is applied
+    pub sample: Vec<Bookmark>,
+}
+
+const BULK_SAMPLE_SIZE: usize = 10;
+
+/// Apply an action on all bookmarks matching a query
+///
+/// Archived bookmarks are excluded as in searching, unless `is:archived` is queried.
+#[utoipa::path(
+    post,
+    path = "/bulk",
+    request_body = Bulk,
+    responses(
+        (status = 200, description = "Action applied, or counted only in the dry-run", body = BulkResult),
+        (status = 400, description = "Bad query request")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/bulk", format = "application/json", data = "<payload>")]
+pub async fn bulk_bookmarks(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    payload: Json<Bulk>,
+) -> Result<Json<BulkResult>, Error> {
+    let Bulk {
+        q,
+        cwd,
+        action,
+        dry_run,
+    } = payload.into_inner();
+    if q.trim().is_empty() {
+        return Err(Error::BadRequest("Empty query".to_string()));
+    }
+
+    let rv = db
+        .transaction::<_, Error, _>(|db| {
+            async move {
+                let ids = crate::db::find_bookmark_ids(db, Some(&q), cwd.as_deref()).await?;
+                let sample =
+                    bookmark::Bookmark::get_many(db, &ids[..ids.len().min(BULK_SAMPLE_SIZE)]).await;
+                let sample = crate::db::get_bookmark_details(db, sample)
+                    .await
+                    .into_iter()
+                    .map(Bookmark::from)
+                    .collect();
+                if dry_run || ids.is_empty() {
+                    return Ok(BulkResult {
+                        affected: ids.len(),
+                        sample,
+                    });
+                }
+
+                let op = match action {
+                    BulkAction::AddTags { tags } => BatchOperation::Tag {
+                        ids,
+                        add: tags,
+                        remove: vec![],
+                    },
+                    BulkAction::RemoveTags { tags } => BatchOperation::Tag {
+                        ids,
+                        add: vec![],
+                        remove: tags,
+                    },
+                    BulkAction::Move { folder_id } => BatchOperation::Move { ids, folder_id },
+                    BulkAction::Delete => BatchOperation::Delete { ids },
+                };
+                let affected = apply(db, op).await?.affected;
+                Ok(BulkResult { affected, sample })
+            }
+            .scope_boxed()
+        })
+        .await?;
+
+    Ok(Json(rv))
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![
+        create_bookmark,
+        search_bookmarks,
+        get_bookmark,
+        delete_bookmark,
+        update_bookmark,
+        list_pinned_bookmarks,
+        list_reading_queue,
+        list_due_reminders,
+        get_reminders_feed,
+        get_bookmark_history,
+        revert_bookmark,
+        get_bookmark_metadata,
+        archive_bookmark,
+        get_bookmark_archive,
+        get_link_report,
+        list_duplicate_bookmarks,
+        merge_bookmarks,
+        lookup_bookmarks,
+        batch_bookmarks,
+        bulk_bookmarks
+    ]
+}
+
+#[cfg(not(tarpaulin_include))]
+pub(crate) mod misc {
+    use super::*;
+
+    use utoipa::{OpenApi, Path};
+
+    pub struct ApiDoc;
+
+    impl OpenApi for ApiDoc {
+        fn openapi() -> utoipa::openapi::OpenApi {
+            use utoipa::openapi::{
+                InfoBuilder, OpenApiBuilder,
+                security::{ApiKey, ApiKeyValue, SecurityScheme},
+            };
+
+            let mut api = OpenApiBuilder::new()
+                .info(
+                    InfoBuilder::new()
+                        .title("Bookmarks API")
+                        .description(Some("Bookmarks API"))
+                        .version("1.0")
+                        .build(),
+                )
+                .paths(bearmark_macro::utoipa_paths!(
+                    "/api/bookmarks",
+                    create_bookmark,
+                    search_bookmarks,
+                    get_bookmark,
+                    delete_bookmark,
+                    update_bookmark,
+                    list_pinned_bookmarks,
+                    list_reading_queue,
+                    list_due_reminders,
+                    get_reminders_feed,
+                    get_bookmark_history,
+                    revert_bookmark,
+                    get_bookmark_metadata,
+                    archive_bookmark,
+                    get_bookmark_archive,
+                    get_link_report,
+                    list_duplicate_bookmarks,
+                    merge_bookmarks,
+                    lookup_bookmarks,
+                    batch_bookmarks,
+                    bulk_bookmarks
+                ))
+                .components(Some(bearmark_macro::utoipa_components![
+                    CreateBookmark,
+                    ModifyBookmark,
+                    BookmarkMetadata,
+                    BookmarkArchive,
+                    ReadingState,
+                    RevisionState,
+                    Revision,
+                    LinkCheck,
+                    BrokenLink,
+                    LinkReport,
+                    MergeBookmarks,
+                    UrlMatching,
+                    LookupBookmarks,
+                    LookupMatch,
+                    LookupResult,
+                    BatchOperation,
+                    Batch,
+                    BatchResult,
+                    BulkAction,
+                    Bulk,
+                    BulkResult,
+                    Bookmark
+                ]))
+                .build();
+
+            api.components.as_mut().unwrap().add_security_scheme(
+                "api_key",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Authorization"))),
+            );
+
+            api
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::configs::{self, ArchiveStorageKind, Config};
+    use crate::archive::test::test_archiver;
+    use crate::db::bookmark::test::rand_bookmark;
+    use crate::utils::rand::rand_str;
+
+    use itertools::Itertools;
+    use rocket::fairing::AdHoc;
+    use rocket::http::Status;
+    use rocket::local::{asynchronous, blocking};
+    use rocket_db_pools::Database;
+    use tracing::info;
+
+    fn test_app() -> rocket::Rocket<rocket::Build> {
+        rocket::custom(configs::config_provider())
+            .attach(Db::init())
+            .mount("/", routes())
+            .manage(test_archiver(ArchiveStorageKind::Local, 1024))
+            .attach(AdHoc::config::<Config>())
+    }
+
+    fn test_client() -> blocking::Client {
+        blocking::Client::tracked(test_app()).expect("valid rocket instance")
+    }
+
+    async fn test_async_client() -> asynchronous::Client {
+        asynchronous::Client::tracked(test_app())
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[test]
+    fn create_bookmark() {
+        let client = test_client();
+        let payload = CreateBookmark {
+            url: "https://www.rust-lang.org".to_string(),
+            notes: None,
+            description: None,
+            title: "Rust".to_string(),
+            folder_id: None,
+            folder: None,
+            tags: vec![rand_str(4), rand_str(4)],
+            attributes: None,
+            shortcut: None,
+        };
+        let response = client
+            .post(uri!(super::create_bookmark))
+            .json(&payload)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let added: Bookmark = response.into_json().unwrap();
+
+        assert!(added.id > 0);
+        assert_eq!(added.title, payload.title);
+        assert_eq!(added.url, payload.url);
+    }
+
+    #[test]
+    fn delete_bookmark() {
+        let client = test_client();
+        let payload = CreateBookmark {
+            url: "https://www.rust-lang.org".to_string(),
+            notes: None,
+            description: None,
+            title: "Rust".to_string(),
+            folder_id: None,
+            folder: None,
+            tags: vec![rand_str(4), rand_str(4)],
+            attributes: None,
+            shortcut: None,
+        };
+        let response = client
+            .post(uri!(super::create_bookmark))
+            .json(&payload)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let added: Bookmark = response.into_json().unwrap();
+
+        let response = client
+            .delete(uri!(super::delete_bookmark(added.id)))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .delete(uri!(super::delete_bookmark(added.id)))
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn search_bookmarks() {
+        // Create some bookmarks
+        let mut conn = crate::db::connection::establish().await;
+        crate::db::search::test::setup_searchable_bookmarks(&mut conn).await;
+
+        let client = test_async_client().await;
+        let mut results: Vec<Bookmark>;
+
+        macro_rules! assert_get_bookmarks {
+            ($uri:expr, $($assert_args:expr),*) => {
+                let response = client.get($uri).dispatch().await;
+                assert_eq!(response.status(), Status::Ok);
+                results = response.into_json().await.unwrap();
+                assert!(
+                    $($assert_args,)*
+                );
+            };
+        }
+
+        assert_get_bookmarks!(
+            uri!(super::search_bookmarks(
+                q = _,
+                cwd = _,
+                before = _,
+                limit = _,
+                sort = _,
+                pinned_first = _
+            )),
+            results.len() >= 5,
+            "Expected more than 5 bookmarks, got {}",
+            results.len()
+        );
+
+        assert_get_bookmarks!(
+            uri!(super::search_bookmarks(
+                q = Some("Weather"),
+                cwd = _,
+                before = _,
+                limit = _,
+                sort = _,
+                pinned_first = _
+            )),
+            results.len() == 3,
+            "Expected 3 bookmarks, got {}",
+            results.len()
+        );
+
+        assert_get_bookmarks!(
+            uri!(super::search_bookmarks(
+                q = Some("Weather"),
+                cwd = _,
+                before = _,
+                limit = Some(2),
+                sort = _,
+                pinned_first = _
+            )),
+            results.len() == 2,
+            "Expected 2 bookmarks, got {}",
+            results.len()
+        );
+
+        assert_get_bookmarks!(
+            uri!(super::search_bookmarks(
+                q = Some("Weather"),
+                cwd = _,
+                before = Some(results[1].id),
+                limit = Some(2),
+                sort = _,
+                pinned_first = _
+            )),
+            results.len() == 1,
+            "Expected 1 bookmark, got {}",
+            results.len()
+        );
+
+        assert_get_bookmarks!(
+            uri!(super::search_bookmarks(
+                q = Some("#global weather"),
+                cwd = _,
+                before = _,
+                limit = _,
+                sort = _,
+                pinned_first = _
+            )),
+            results.len() == 1,
+            "Expected 1 bookmark, got {}",
+            results.len()
+        );
+
+        assert_get_bookmarks!(
+            uri!(super::search_bookmarks(
+                q = Some("#west weather"),
+                cwd = _,
+                before = _,
+                limit = _,
+                sort = _,
+                pinned_first = _
+            )),
+            results.len() == 1,
+            "Expected 1 bookmark, got {}",
+            results.len()
+        );
+
+        assert_get_bookmarks!(
+            uri!(super::search_bookmarks(
+                q = Some("#global #west weather"),
+                cwd = _,
+                before = _,
+                limit = _,
+                sort = _,
+                pinned_first = _
+            )),
+            results.is_empty(),
+            "Expected 0 bookmark, got {}",
+            results.len()
+        );
+
+        assert_get_bookmarks!(
+            uri!(super::search_bookmarks(
+                q = Some("#weather"),
+                cwd = _,
+                before = _,
+                limit = _,
+                sort = _,
+                pinned_first = _
+            )),
+            results.len() == 3,
+            "Expected 3 bookmarks, got {}",
+            results.len()
+        );
+        assert_get_bookmarks!(
+            uri!(super::search_bookmarks(
+                q = Some("#weather"),
+                cwd = _,
+                before = _,
+                limit = Some(1),
+                sort = _,
+                pinned_first = _
+            )),
+            results.len() == 1,
+            "Expected 1 bookmark, got {}",
+            results.len()
+        );
+        assert_get_bookmarks!(
+            uri!(super::search_bookmarks(
+                q = Some("#weather"),
+                cwd = _,
+                before = Some(results[0].id),
+                limit = Some(3),
+                sort = _,
+                pinned_first = _
+            )),
+            results.len() == 2,
+            "Expected 2 bookmarks, got {}",
+            results.len()
+        );
+    }
+
+    #[test]
+    fn unsearchable_deleted_bookmark() {
+        let payload = rand_bookmark();
+        let payload = CreateBookmark {
+            url: payload.url,
+            notes: None,
+            description: None,
+            title: payload.title,
+            folder_id: None,
+            folder: None,
+            tags: vec![rand_str(4), rand_str(4)],
+            attributes: None,
+            shortcut: None,
+        };
+        info!(?payload, "creating");
+        let title = payload.title.clone();
+        let client = test_client();
+        let response = client
+            .post(uri!(super::create_bookmark))
+            .json(&payload)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let added: Bookmark = response.into_json().unwrap();
+        info!(?added, "created");
+
+        let mut results: Vec<Bookmark>;
+
+        macro_rules! assert_get_bookmark {
+            ($($assert_args:expr),*) => {
+                let response = client.get(
+                    uri!(super::search_bookmarks(
+                        q = Some(&title),
+                        cwd = _,
+                        before = _,
+                        limit = _,
+                        sort = _,
+                        pinned_first = _
+                    ))
+                ).dispatch();
+                assert_eq!(response.status(), Status::Ok);
+                results = response.into_json().unwrap();
+                assert!(
+                    $($assert_args,)*
+                );
+            };
+        }
+
+        assert_get_bookmark!(
+            results.len() == 1,
+            "Expected 1 bookmarks, got {}",
+            results.len()
+        );
+
+        let response = client
+            .delete(uri!(super::delete_bookmark(id = added.id)))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        assert_get_bookmark!(
+            results.is_empty(),
+            "Expected 0 bookmarks, got {}",
+            results.len()
+        );
+    }
+
+    #[test]
+    fn update_exist_bookmark() {
+        let client = test_client();
+        let m = rand_bookmark();
+        let payload = CreateBookmark {
+            url: m.url,
+            notes: None,
+            description: None,
+            title: m.title,
+            folder_id: None,
+            folder: None,
+            tags: vec![rand_str(4), rand_str(4)],
+            attributes: None,
+            shortcut: None,
+        };
+        let response = client
+            .post(uri!(super::create_bookmark))
+            .json(&payload)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let added: Bookmark = response.into_json().unwrap();
+
+        let payload = ModifyBookmark {
+            url: Some("https://www.rust-lang.org".to_string()),
+            notes: None,
+            description: None,
+            title: Some("Rust Programming Language".to_string()),
+            tags: None,
+            folder_id: None,
+            folder: None,
+            pinned: None,
+            pin_order: None,
+            remind_at: None,
+            attributes: None,
+            reading_state: None,
+            shortcut: None,
+        };
+        assert_ne!(Some(added.title), payload.title);
+        assert_ne!(Some(added.url), payload.url);
+
+        let response = client
+            .patch(uri!(super::update_bookmark(added.id)))
+            .json(&payload)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let updated: Bookmark = response.into_json().unwrap();
+
+        assert_eq!(updated.id, added.id);
+        assert_eq!(updated.title, payload.title.unwrap());
+        assert_eq!(updated.url, payload.url.unwrap());
+    }
+
+    #[test]
+    fn update_missing_bookmark() {
+        let client = test_client();
+        let payload = ModifyBookmark {
+            url: Some("https://www.rust-lang.org".to_string()),
+            notes: None,
+            description: None,
+            title: Some("Rust Programming Language".to_string()),
+            tags: None,
+            folder_id: None,
+            folder: None,
+            pinned: None,
+            pin_order: None,
+            remind_at: None,
+            attributes: None,
+            reading_state: None,
+            shortcut: None,
+        };
+
+        let response = client
+            .patch(uri!(super::update_bookmark(99999999)))
+            .json(&payload)
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[test]
+    fn update_trashed_bookmark() {
+        let client = test_client();
+        let m = rand_bookmark();
+        let payload = CreateBookmark {
+            url: m.url,
+            notes: None,
+            description: None,
+            title: m.title,
+            folder_id: None,
+            folder: None,
+            tags: vec![],
+            attributes: None,
+            shortcut: None,
+        };
+        let response = client
+            .post(uri!(super::create_bookmark))
+            .json(&payload)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let added: Bookmark = response.into_json().unwrap();
+        let response = client
+            .delete(uri!(super::delete_bookmark(added.id)))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let payload = ModifyBookmark {
+            url: None,
+            notes: None,
+            description: None,
+            title: Some(rand_str(10)),
+            tags: None,
+            folder_id: None,
+            folder: None,
+            pinned: None,
+            pin_order: None,
+            remind_at: None,
+            attributes: None,
+            reading_state: None,
+            shortcut: None,
+        };
+        let response = client
+            .patch(uri!(super::update_bookmark(added.id)))
+            .json(&payload)
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[test]
+    fn update_bookmark_no_change() {
+        let client = test_client();
+        let m = rand_bookmark();
+        let payload = CreateBookmark {
+            url: m.url,
+            notes: None,
+            description: None,
+            title: m.title,
+            folder_id: None,
+            folder: None,
+            tags: vec!["rust".to_string(), "programming".to_string()],
+            attributes: None,
+            shortcut: None,
+        };
+        let response = client
+            .post(uri!(super::create_bookmark))
+            .json(&payload)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let added: Bookmark = response.into_json().unwrap();
+
+        let payload = ModifyBookmark {
+            url: None,
+            notes: None,
+            description: None,
+            title: None,
+            tags: None,
+            folder_id: None,
+            folder: None,
+            pinned: None,
+            pin_order: None,
+            remind_at: None,
+            attributes: None,
+            reading_state: None,
+            shortcut: None,
+        };
+        let response = client
+            .patch(uri!(super::update_bookmark(added.id)))
+            .json(&payload)
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[test]
+    fn update_bookmark_tags() {
+        let client = test_client();
+        let m = rand_bookmark();
+        let payload = CreateBookmark {
+            url: m.url,
+            notes: None,
+            description: None,
+            title: m.title,
+            folder_id: None,
+            folder: None,
+            tags: vec!["rust", "programming"]
+                .into_iter()
+                .map(|s| s.to_string())
+                .collect(),
+            attributes: None,
+            shortcut: None,
+        };
+        let response = client
+            .post(uri!(super::create_bookmark))
+            .json(&payload)
+            .dispatch();
         assert_eq!(response.status(), Status::Ok);
         let added: Bookmark = response.into_json().unwrap();
 
-        assert!(added.id > 0);
-        assert_eq!(added.title, payload.title);
-        assert_eq!(added.url, payload.url);
+        let modify_tags = vec!["doc", "programming", "rust"]
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect_vec();
+        let payload = ModifyBookmark {
+            url: None,
+            notes: None,
+            description: None,
+            title: None,
+            tags: Some(modify_tags.clone()),
+            folder_id: None,
+            folder: None,
+            pinned: None,
+            pin_order: None,
+            remind_at: None,
+            attributes: None,
+            reading_state: None,
+            shortcut: None,
+        };
+
+        let response = client
+            .patch(uri!(super::update_bookmark(added.id)))
+            .json(&payload)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let updated: Bookmark = response.into_json().unwrap();
+
+        assert_eq!(updated.id, added.id);
+        assert_eq!(updated.title, added.title);
+        assert_eq!(updated.url, added.url);
+        assert_eq!(updated.tags, modify_tags);
+    }
+
+    #[rocket::async_test]
+    async fn merge_duplicate_bookmarks() {
+        use crate::db::folder::test::create_rand_folder;
+
+        let mut conn = crate::db::connection::establish().await;
+        let f = create_rand_folder(&mut conn).await;
+        let client = test_async_client().await;
+
+        let host = rand_str(10).to_lowercase();
+        let mut added = vec![];
+        for (url, folder_id, tags) in [
+            (format!("https://{host}.com/"), None, vec!["rust"]),
+            (
+                format!("http://www.{host}.com/?utm_source=x"),
+                Some(f.id),
+                vec!["doc"],
+            ),
+            (format!("https://{host}.com"), None, vec!["rust", "lang"]),
+        ] {
+            let payload = CreateBookmark {
+                url,
+                notes: None,
+                description: None,
+                title: rand_str(10),
+                folder_id,
+                folder: None,
+                tags: tags.into_iter().map(|t| t.to_string()).collect(),
+                attributes: None,
+                shortcut: None,
+            };
+            let response = client
+                .post(uri!(super::create_bookmark))
+                .json(&payload)
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+            added.push(response.into_json::<Bookmark>().await.unwrap());
+        }
+
+        let response = client
+            .get(uri!(super::list_duplicate_bookmarks))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let groups: Vec<Vec<Bookmark>> = response.into_json().await.unwrap();
+        let group = groups
+            .iter()
+            .find(|g| g.iter().any(|b| b.id == added[0].id))
+            .unwrap();
+        assert_eq!(
+            group.iter().map(|b| b.id).collect_vec(),
+            added.iter().map(|b| b.id).collect_vec()
+        );
+
+        let response = client
+            .post(uri!(super::merge_bookmarks))
+            .json(&MergeBookmarks {
+                keep: added[0].id,
+                duplicates: vec![added[0].id],
+                folder_id: None,
+            })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let response = client
+            .post(uri!(super::merge_bookmarks))
+            .json(&MergeBookmarks {
+                keep: added[0].id,
+                duplicates: vec![added[1].id, 99999999],
+                folder_id: None,
+            })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        let response = client
+            .post(uri!(super::merge_bookmarks))
+            .json(&MergeBookmarks {
+                keep: added[0].id,
+                duplicates: vec![added[1].id, added[2].id],
+                folder_id: None,
+            })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let merged: Bookmark = response.into_json().await.unwrap();
+        assert_eq!(merged.id, added[0].id);
+        assert_eq!(merged.folder, Some(f.path.clone()));
+        assert_eq!(merged.tags, vec!["doc", "lang", "rust"]);
+
+        let revisions = revision::get_revisions(&mut conn, added[0].id).await;
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].old_folder, None);
+        assert_eq!(revisions[0].new_folder, Some(f.path));
+
+        for m in &added[1..] {
+            let m = crate::db::bookmark::Bookmark::get(&mut conn, m.id)
+                .await
+                .unwrap();
+            assert!(m.deleted_at.is_some());
+        }
     }
 
     #[test]
-    fn delete_bookmark() {
+    fn lookup_bookmarks() {
         let client = test_client();
+        let host = rand_str(10).to_lowercase();
+        let url = format!("https://{host}.com/docs/?utm_source=x");
         let payload = CreateBookmark {
-            url: "https://www.rust-lang.org".to_string(),
-            title: "Rust".to_string(),
+            url: url.clone(),
+            notes: None,
+            description: None,
+            title: rand_str(10),
             folder_id: None,
-            tags: vec![rand_str(4), rand_str(4)],
+            folder: None,
+            tags: vec!["rust".to_string()],
+            attributes: None,
+            shortcut: None,
         };
         let response = client
             .post(uri!(super::create_bookmark))
@@ -379,336 +2290,1011 @@ mod test {
         assert_eq!(response.status(), Status::Ok);
         let added: Bookmark = response.into_json().unwrap();
 
+        let urls = vec![
+            url.clone(),
+            format!("https://{host}.com/docs"),
+            format!("https://{host}.com/docs/more"),
+        ];
         let response = client
-            .delete(uri!(super::delete_bookmark(added.id)))
+            .post(uri!(super::lookup_bookmarks))
+            .json(&LookupBookmarks {
+                urls: urls.clone(),
+                matching: UrlMatching::Normalized,
+            })
             .dispatch();
         assert_eq!(response.status(), Status::Ok);
+        let results: Vec<LookupResult> = response.into_json().unwrap();
+        assert_eq!(
+            results.iter().map(|r| &r.url).collect_vec(),
+            urls.iter().collect_vec()
+        );
+        assert_eq!(results[0].bookmarks.len(), 1);
+        assert_eq!(results[0].bookmarks[0].id, added.id);
+        assert_eq!(results[0].bookmarks[0].tags, vec!["rust"]);
+        assert_eq!(results[1].bookmarks.len(), 1);
+        assert!(results[2].bookmarks.is_empty());
 
         let response = client
-            .delete(uri!(super::delete_bookmark(added.id)))
+            .post(uri!(super::lookup_bookmarks))
+            .json(&LookupBookmarks {
+                urls: urls.clone(),
+                matching: UrlMatching::Exact,
+            })
             .dispatch();
-        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.status(), Status::Ok);
+        let results: Vec<LookupResult> = response.into_json().unwrap();
+        assert_eq!(results[0].bookmarks.len(), 1);
+        assert!(results[1].bookmarks.is_empty());
+        assert!(results[2].bookmarks.is_empty());
     }
 
     #[rocket::async_test]
-    async fn search_bookmarks() {
-        // Create some bookmarks
+    async fn batch_bookmarks() {
+        use crate::db::folder::test::create_rand_folder;
+
         let mut conn = crate::db::connection::establish().await;
-        crate::db::search::test::setup_searchable_bookmarks(&mut conn).await;
+        let f = create_rand_folder(&mut conn).await;
+        let client = test_async_client().await;
+
+        let m = rand_bookmark();
+        let tag = rand_str(10);
+        let response = client
+            .post(uri!(super::batch_bookmarks))
+            .json(&Batch {
+                operations: vec![
+                    BatchOperation::Create(CreateBookmark {
+                        url: m.url.clone(),
+                        notes: None,
+                        description: None,
+                        title: m.title.clone(),
+                        folder_id: None,
+                        folder: None,
+                        tags: vec!["old".to_string()],
+                        attributes: None,
+                        shortcut: None,
+                    }),
+                    BatchOperation::Delete {
+                        ids: vec![99999999],
+                    },
+                ],
+                atomic: false,
+            })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let results: Vec<BatchResult> = response.into_json().await.unwrap();
+        assert_eq!(results.len(), 2);
+        let created = results[0].bookmark.as_ref().unwrap();
+        assert_eq!(created.title, m.title);
+        assert!(results[0].error.is_none());
+        assert_eq!(results[1].affected, 0);
+        assert!(results[1].error.is_some());
+
+        let response = client
+            .post(uri!(super::batch_bookmarks))
+            .json(&Batch {
+                operations: vec![
+                    BatchOperation::Move {
+                        ids: vec![created.id],
+                        folder_id: Some(f.id),
+                    },
+                    BatchOperation::Tag {
+                        ids: vec![created.id, created.id],
+                        add: vec![tag.clone()],
+                        remove: vec!["old".to_string()],
+                    },
+                    BatchOperation::Update {
+                        id: created.id,
+                        changes: ModifyBookmark {
+                            title: Some("renamed".to_string()),
+                            url: None,
+                            notes: None,
+                            description: None,
+                            tags: None,
+                            folder_id: None,
+                            folder: None,
+                            pinned: None,
+                            pin_order: None,
+                            remind_at: None,
+                            attributes: None,
+                            reading_state: None,
+                            shortcut: None,
+                        },
+                    },
+                ],
+                atomic: true,
+            })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let results: Vec<BatchResult> = response.into_json().await.unwrap();
+        assert_eq!(
+            results.iter().map(|r| r.affected).collect_vec(),
+            vec![1, 1, 1]
+        );
+        let updated = results[2].bookmark.as_ref().unwrap();
+        assert_eq!(updated.title, "renamed");
+        assert_eq!(updated.folder, Some(f.path.clone()));
+        assert_eq!(updated.tags, vec![tag.clone()]);
+
+        // the whole batch is rolled back if any operation fails in the atomic mode
+        let response = client
+            .post(uri!(super::batch_bookmarks))
+            .json(&Batch {
+                operations: vec![
+                    BatchOperation::Delete {
+                        ids: vec![created.id],
+                    },
+                    BatchOperation::Move {
+                        ids: vec![created.id],
+                        folder_id: None,
+                    },
+                ],
+                atomic: true,
+            })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+        let m = crate::db::bookmark::Bookmark::get(&mut conn, created.id)
+            .await
+            .unwrap();
+        assert!(m.deleted_at.is_none());
+        assert_eq!(m.folder_id, Some(f.id));
+    }
+
+    #[rocket::async_test]
+    async fn bulk_bookmarks() {
+        use crate::db::folder::test::create_rand_folder;
 
+        let mut conn = crate::db::connection::establish().await;
+        let f = create_rand_folder(&mut conn).await;
         let client = test_async_client().await;
-        let mut results: Vec<Bookmark>;
 
-        macro_rules! assert_get_bookmarks {
-            ($uri:expr, $($assert_args:expr),*) => {
-                let response = client.get($uri).dispatch().await;
+        let (tag, added_tag) = (rand_str(10), rand_str(10));
+        let mut added = vec![];
+        for _ in 0..3 {
+            let m = rand_bookmark();
+            let response = client
+                .post(uri!(super::create_bookmark))
+                .json(&CreateBookmark {
+                    url: m.url,
+                    notes: None,
+                    description: None,
+                    title: m.title,
+                    folder_id: None,
+                    folder: None,
+                    tags: vec![tag.clone()],
+                    attributes: None,
+                    shortcut: None,
+                })
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+            added.push(response.into_json::<Bookmark>().await.unwrap());
+        }
+
+        macro_rules! bulk {
+            ($q:expr, $action:expr, $dry_run:expr) => {{
+                let response = client
+                    .post(uri!(super::bulk_bookmarks))
+                    .json(&Bulk {
+                        q: $q,
+                        cwd: None,
+                        action: $action,
+                        dry_run: $dry_run,
+                    })
+                    .dispatch()
+                    .await;
                 assert_eq!(response.status(), Status::Ok);
-                results = response.into_json().await.unwrap();
-                assert!(
-                    $($assert_args,)*
-                );
-            };
+                response.into_json::<BulkResult>().await.unwrap()
+            }};
         }
 
-        assert_get_bookmarks!(
-            uri!(super::search_bookmarks(
-                q = _,
-                cwd = _,
-                before = _,
-                limit = _
-            )),
-            results.len() >= 5,
-            "Expected more than 5 bookmarks, got {}",
-            results.len()
+        let add_tags = || BulkAction::AddTags {
+            tags: vec![added_tag.clone()],
+        };
+        let rv = bulk!(format!("#{tag}"), add_tags(), true);
+        assert_eq!(rv.affected, 3);
+        assert_eq!(
+            rv.sample.iter().map(|b| b.id).collect_vec(),
+            added.iter().rev().map(|b| b.id).collect_vec()
         );
-
-        assert_get_bookmarks!(
-            uri!(super::search_bookmarks(
-                q = Some("Weather"),
-                cwd = _,
-                before = _,
-                limit = _
-            )),
-            results.len() == 3,
-            "Expected 3 bookmarks, got {}",
-            results.len()
+        assert_eq!(bulk!(format!("#{added_tag}"), add_tags(), true).affected, 0);
+
+        let rv = bulk!(format!("#{tag}"), add_tags(), false);
+        assert_eq!(rv.affected, 3);
+        assert_eq!(bulk!(format!("#{added_tag}"), add_tags(), true).affected, 3);
+
+        let host = added[1].url.trim_start_matches("https://").to_uppercase();
+        let rv = bulk!(format!("#{tag} site:{host}"), add_tags(), true);
+        assert_eq!(rv.affected, 1);
+        assert_eq!(rv.sample[0].id, added[1].id);
+        let rv = bulk!(format!("#{tag} site:www.{host}"), add_tags(), true);
+        assert_eq!(rv.affected, 0);
+
+        let rv = bulk!(
+            format!("#{tag} {}", added[0].title),
+            BulkAction::Move {
+                folder_id: Some(f.id)
+            },
+            false
         );
+        assert_eq!(rv.affected, 1);
+        let rv = bulk!(format!("#{tag} {}//", f.path), BulkAction::Delete, true);
+        assert_eq!(rv.affected, 1);
+        assert_eq!(rv.sample[0].folder, Some(f.path.clone()));
 
-        assert_get_bookmarks!(
-            uri!(super::search_bookmarks(
-                q = Some("Weather"),
-                cwd = _,
-                before = _,
-                limit = Some(2)
-            )),
-            results.len() == 2,
-            "Expected 2 bookmarks, got {}",
-            results.len()
-        );
+        let rv = bulk!(format!("#{added_tag}"), BulkAction::Delete, false);
+        assert_eq!(rv.affected, 3);
+        assert_eq!(bulk!(format!("#{tag}"), add_tags(), true).affected, 0);
 
-        assert_get_bookmarks!(
-            uri!(super::search_bookmarks(
-                q = Some("Weather"),
-                cwd = _,
-                before = Some(results[1].id),
-                limit = Some(2)
-            )),
-            results.len() == 1,
-            "Expected 1 bookmark, got {}",
-            results.len()
-        );
+        let response = client
+            .post(uri!(super::bulk_bookmarks))
+            .json(&Bulk {
+                q: " ".to_string(),
+                cwd: None,
+                action: BulkAction::Delete,
+                dry_run: false,
+            })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+    }
 
-        assert_get_bookmarks!(
-            uri!(super::search_bookmarks(
-                q = Some("#global weather"),
-                cwd = _,
-                before = _,
-                limit = _
-            )),
-            results.len() == 1,
-            "Expected 1 bookmark, got {}",
-            results.len()
-        );
+    #[rocket::async_test]
+    async fn update_bookmark_folder() {
+        use crate::db::folder::test::create_rand_folder;
+        use rocket::http::ContentType;
 
-        assert_get_bookmarks!(
-            uri!(super::search_bookmarks(
-                q = Some("#west weather"),
-                cwd = _,
-                before = _,
-                limit = _
-            )),
-            results.len() == 1,
-            "Expected 1 bookmark, got {}",
-            results.len()
+        let mut conn = crate::db::connection::establish().await;
+        let (f1, f2) = (
+            create_rand_folder(&mut conn).await,
+            create_rand_folder(&mut conn).await,
         );
+        let client = test_async_client().await;
 
-        assert_get_bookmarks!(
-            uri!(super::search_bookmarks(
-                q = Some("#global #west weather"),
-                cwd = _,
-                before = _,
-                limit = _
-            )),
-            results.is_empty(),
-            "Expected 0 bookmark, got {}",
-            results.len()
-        );
+        let m = rand_bookmark();
+        let response = client
+            .post(uri!(super::create_bookmark))
+            .json(&CreateBookmark {
+                url: m.url,
+                title: m.title,
+                notes: None,
+                description: None,
+                folder_id: None,
+                folder: None,
+                tags: vec![],
+                attributes: None,
+                shortcut: None,
+            })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let added: Bookmark = response.into_json().await.unwrap();
+
+        for (body, status, folder) in [
+            (
+                format!(r#"{{"folder": "{}/"}}"#, f1.path),
+                Status::Ok,
+                Some(f1.path.clone()),
+            ),
+            (
+                format!(r#"{{"folder_id": {}, "title": "moved"}}"#, f2.id),
+                Status::Ok,
+                Some(f2.path.clone()),
+            ),
+            (
+                format!(r#"{{"folder_id": {}, "folder": null}}"#, f1.id),
+                Status::BadRequest,
+                Some(f2.path.clone()),
+            ),
+            (
+                r#"{"folder": "/not/exists"}"#.to_string(),
+                Status::NotFound,
+                Some(f2.path.clone()),
+            ),
+            (r#"{"folder_id": null}"#.to_string(), Status::Ok, None),
+        ] {
+            let response = client
+                .patch(uri!(super::update_bookmark(added.id)))
+                .header(ContentType::JSON)
+                .body(&body)
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), status, "{body}");
+            if status == Status::Ok {
+                let updated: Bookmark = response.into_json().await.unwrap();
+                assert_eq!(updated.folder, folder, "{body}");
+            }
+            let m = crate::db::bookmark::Bookmark::get(&mut conn, added.id)
+                .await
+                .unwrap();
+            let path = match m.folder_id {
+                Some(id) => Some(
+                    crate::db::folder::Folder::get(&mut conn, id)
+                        .await
+                        .unwrap()
+                        .path,
+                ),
+                None => None,
+            };
+            assert_eq!(path, folder, "{body}");
+        }
+    }
 
-        assert_get_bookmarks!(
-            uri!(super::search_bookmarks(
-                q = Some("#weather"),
-                cwd = _,
-                before = _,
-                limit = _
-            )),
-            results.len() == 3,
-            "Expected 3 bookmarks, got {}",
-            results.len()
-        );
-        assert_get_bookmarks!(
-            uri!(super::search_bookmarks(
-                q = Some("#weather"),
+    #[test]
+    fn create_bookmark_into_folder_path() {
+        let client = test_client();
+        let path = format!("/{}/{}/{}", rand_str(10), rand_str(10), rand_str(10));
+
+        for (folder_id, folder, status, expected) in [
+            (
+                None,
+                Some(format!("{path}/")),
+                Status::Ok,
+                Some(path.clone()),
+            ),
+            (None, Some(path.clone()), Status::Ok, Some(path.clone())),
+            (None, Some("/".to_string()), Status::Ok, None),
+            (Some(1), Some(path.clone()), Status::BadRequest, None),
+        ] {
+            let m = rand_bookmark();
+            let response = client
+                .post(uri!(super::create_bookmark))
+                .json(&CreateBookmark {
+                    url: m.url,
+                    title: m.title,
+                    notes: None,
+                    description: None,
+                    folder_id,
+                    folder: folder.clone(),
+                    tags: vec![],
+                    attributes: None,
+                    shortcut: None,
+                })
+                .dispatch();
+            assert_eq!(response.status(), status, "{folder:?}");
+            if status == Status::Ok {
+                let added: Bookmark = response.into_json().unwrap();
+                assert_eq!(added.folder, expected, "{folder:?}");
+            }
+        }
+    }
+
+    #[rocket::async_test]
+    async fn get_pending_bookmark_metadata() {
+        let mut conn = crate::db::connection::establish().await;
+        let client = test_async_client().await;
+        let m = rand_bookmark();
+        let response = client
+            .post(uri!(super::create_bookmark))
+            .json(&CreateBookmark {
+                url: m.url,
+                title: "".to_string(),
+                notes: None,
+                description: None,
+                folder_id: None,
+                folder: None,
+                tags: vec![],
+                attributes: None,
+                shortcut: None,
+            })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let added: Bookmark = response.into_json().await.unwrap();
+
+        // not queued unless the fetcher is enabled
+        if !configs::is_fetcher_enabled() {
+            let response = client
+                .get(uri!(super::get_bookmark_metadata(added.id)))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::NotFound);
+            metadata::enqueue_bookmarks(&mut conn, &[added.id]).await;
+        }
+
+        let response = client
+            .get(uri!(super::get_bookmark_metadata(added.id)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let meta: BookmarkMetadata = response.into_json().await.unwrap();
+        assert_eq!(meta.bookmark_id, added.id);
+        assert_eq!(meta.status, metadata::status::PENDING);
+
+        let response = client
+            .get(uri!(super::get_bookmark_metadata(99999999)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn report_broken_links() {
+        use crate::db::bookmark::test::create_rand_bookmark;
+        use crate::db::link_check::{NewLinkCheck, save_link_check};
+
+        let mut conn = crate::db::connection::establish().await;
+        let m = create_rand_bookmark(&mut conn).await;
+        let client = test_async_client().await;
+
+        macro_rules! is_reported {
+            () => {{
+                let response = client.get(uri!(super::get_link_report)).dispatch().await;
+                assert_eq!(response.status(), Status::Ok);
+                let report: LinkReport = response.into_json().await.unwrap();
+                report.broken.iter().any(|b| b.bookmark.id == m.id)
+            }};
+        }
+        macro_rules! is_searched {
+            () => {{
+                let q = format!("is:broken {}", m.title);
+                let response = client
+                    .get(uri!(super::search_bookmarks(
+                        q = Some(&q),
+                        cwd = _,
+                        before = _,
+                        limit = _,
+                        sort = _,
+                        pinned_first = _
+                    )))
+                    .dispatch()
+                    .await;
+                assert_eq!(response.status(), Status::Ok);
+                !response
+                    .into_json::<Vec<Bookmark>>()
+                    .await
+                    .unwrap()
+                    .is_empty()
+            }};
+        }
+
+        let failed = NewLinkCheck {
+            bookmark_id: m.id,
+            error: Some("timed out".to_string()),
+            ..Default::default()
+        };
+        save_link_check(&mut conn, &failed).await;
+        assert!(!is_reported!());
+        assert!(!is_searched!());
+
+        save_link_check(&mut conn, &failed).await;
+        assert!(is_reported!());
+        assert!(is_searched!());
+
+        let response = client
+            .get(uri!(super::search_bookmarks(
+                q = Some("is:unknown"),
                 cwd = _,
                 before = _,
-                limit = Some(1)
-            )),
-            results.len() == 1,
-            "Expected 1 bookmark, got {}",
-            results.len()
+                limit = _,
+                sort = _,
+                pinned_first = _
+            )))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[rocket::async_test]
+    async fn archive_and_get_bookmark_page() {
+        use crate::db::bookmark::{NewBookmark, create_bookmark};
+        use crate::fetcher::test::{Fixture, serve};
+
+        let base = serve(vec![("/page", Fixture::html("<p>Offline</p>"))]).await;
+        let mut conn = crate::db::connection::establish().await;
+        let m = create_bookmark(
+            &mut conn,
+            &NewBookmark {
+                title: "archived".to_string(),
+                url: format!("{base}/page"),
+                notes: None,
+                description: None,
+                attributes: None,
+            },
+        )
+        .await;
+        let client = test_async_client().await;
+
+        let response = client
+            .get(uri!(super::get_bookmark_archive(m.id)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        let response = client
+            .post(uri!(super::archive_bookmark(m.id, Some(true))))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let archived: BookmarkArchive = response.into_json().await.unwrap();
+        assert_eq!(archived.bookmark_id, m.id);
+
+        let response = client
+            .get(uri!(super::get_bookmark_archive(m.id)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::HTML));
+        assert_eq!(
+            response.headers().get_one("Content-Security-Policy"),
+            Some("sandbox")
         );
-        assert_get_bookmarks!(
-            uri!(super::search_bookmarks(
-                q = Some("#weather"),
-                cwd = _,
-                before = Some(results[0].id),
-                limit = Some(3)
-            )),
-            results.len() == 2,
-            "Expected 2 bookmarks, got {}",
-            results.len()
+        assert_eq!(
+            response.into_string().await.unwrap(),
+            "<p>Offline</p>".to_string()
         );
+
+        let response = client
+            .post(uri!(super::archive_bookmark(99999999, None::<bool>)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
     }
 
     #[test]
-    fn unsearchable_deleted_bookmark() {
-        let payload = rand_bookmark();
-        let payload = CreateBookmark {
-            url: payload.url,
-            title: payload.title,
-            folder_id: None,
-            tags: vec![rand_str(4), rand_str(4)],
-        };
-        info!(?payload, "creating");
-        let title = payload.title.clone();
+    fn search_bookmarks_by_frecency() {
         let client = test_client();
-        let response = client
-            .post(uri!(super::create_bookmark))
-            .json(&payload)
-            .dispatch();
+        let response = client.get("/?sort=frecency&limit=3").dispatch();
         assert_eq!(response.status(), Status::Ok);
-        let added: Bookmark = response.into_json().unwrap();
-        info!(?added, "created");
+        let results: Vec<Bookmark> = response.into_json().unwrap();
+        assert!(
+            results
+                .windows(2)
+                .all(|w| w[0].visit_count > 0 || w[1].visit_count == 0)
+        );
 
-        let mut results: Vec<Bookmark>;
+        let response = client.get("/?sort=frecency&before=10").dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+        let response = client.get("/?sort=unknown").dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
 
-        macro_rules! assert_get_bookmark {
-            ($($assert_args:expr),*) => {
-                let response = client.get(
-                    uri!(super::search_bookmarks(
-                        q = Some(&title),
-                        cwd = _,
-                        before = _,
-                        limit = _
-                    ))
-                ).dispatch();
-                assert_eq!(response.status(), Status::Ok);
-                results = response.into_json().unwrap();
-                assert!(
-                    $($assert_args,)*
-                );
-            };
-        }
+    #[rocket::async_test]
+    async fn pin_bookmarks() {
+        use crate::db::bookmark::test::create_rand_bookmark;
+        use rocket::http::ContentType;
 
-        assert_get_bookmark!(
-            results.len() == 1,
-            "Expected 1 bookmarks, got {}",
-            results.len()
+        let mut conn = crate::db::connection::establish().await;
+        let (m1, m2) = (
+            create_rand_bookmark(&mut conn).await,
+            create_rand_bookmark(&mut conn).await,
         );
+        let client = test_async_client().await;
+
+        for (id, body) in [
+            (m1.id, r#"{"pinned": true, "pin_order": 2}"#),
+            (m2.id, r#"{"pinned": true, "pin_order": 1}"#),
+        ] {
+            let response = client
+                .patch(uri!(super::update_bookmark(id)))
+                .header(ContentType::JSON)
+                .body(body)
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+            let updated: Bookmark = response.into_json().await.unwrap();
+            assert!(updated.pinned);
+        }
 
         let response = client
-            .delete(uri!(super::delete_bookmark(id = added.id)))
-            .dispatch();
+            .get(uri!(super::list_pinned_bookmarks))
+            .dispatch()
+            .await;
         assert_eq!(response.status(), Status::Ok);
+        let pinned: Vec<Bookmark> = response.into_json().await.unwrap();
+        let ids = pinned
+            .iter()
+            .map(|m| m.id)
+            .filter(|id| [m1.id, m2.id].contains(id))
+            .collect_vec();
+        assert_eq!(ids, vec![m2.id, m1.id]);
 
-        assert_get_bookmark!(
-            results.is_empty(),
-            "Expected 0 bookmarks, got {}",
-            results.len()
+        let q = format!("{} | {} | {}", m1.title, m2.title, rand_bookmark().title);
+        let response = client
+            .get(uri!(super::search_bookmarks(
+                q = Some(&q),
+                cwd = _,
+                before = _,
+                limit = _,
+                sort = _,
+                pinned_first = Some(true)
+            )))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let results: Vec<Bookmark> = response.into_json().await.unwrap();
+        assert_eq!(
+            results.iter().map(|m| m.id).collect_vec(),
+            vec![m2.id, m1.id]
         );
+
+        // unpinning clears the order
+        let response = client
+            .patch(uri!(super::update_bookmark(m2.id)))
+            .header(ContentType::JSON)
+            .body(r#"{"pinned": false}"#)
+            .dispatch()
+            .await;
+        let updated: Bookmark = response.into_json().await.unwrap();
+        assert!(!updated.pinned);
+        assert_eq!(updated.pin_order, None);
+
+        let response = client
+            .get(uri!(super::search_bookmarks(
+                q = Some(&format!("is:pinned ({} | {})", m1.title, m2.title)),
+                cwd = _,
+                before = _,
+                limit = _,
+                sort = _,
+                pinned_first = _
+            )))
+            .dispatch()
+            .await;
+        let results: Vec<Bookmark> = response.into_json().await.unwrap();
+        assert_eq!(results.iter().map(|m| m.id).collect_vec(), vec![m1.id]);
     }
 
-    #[test]
-    fn update_exist_bookmark() {
-        let client = test_client();
-        let m = rand_bookmark();
-        let payload = CreateBookmark {
-            url: m.url,
-            title: m.title,
-            folder_id: None,
-            tags: vec![rand_str(4), rand_str(4)],
-        };
+    #[rocket::async_test]
+    async fn read_later_workflow() {
+        use crate::db::bookmark::test::create_rand_bookmark;
+        use rocket::http::ContentType;
+
+        let mut conn = crate::db::connection::establish().await;
+        let m = create_rand_bookmark(&mut conn).await;
+        let client = test_async_client().await;
+
+        for (body, status, state) in [
+            (
+                r#"{"reading_state": "unread"}"#,
+                Status::Ok,
+                Some(ReadingState::Unread),
+            ),
+            (
+                r#"{"reading_state": "done"}"#,
+                Status::Ok,
+                Some(ReadingState::Done),
+            ),
+            (r#"{"reading_state": "reading"}"#, Status::BadRequest, None),
+            (
+                r#"{"reading_state": "unknown"}"#,
+                Status::UnprocessableEntity,
+                None,
+            ),
+            (r#"{"reading_state": null}"#, Status::Ok, None),
+        ] {
+            let response = client
+                .patch(uri!(super::update_bookmark(m.id)))
+                .header(ContentType::JSON)
+                .body(body)
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), status, "{body}");
+            if status == Status::Ok {
+                let updated: Bookmark = response.into_json().await.unwrap();
+                assert_eq!(updated.reading_state, state, "{body}");
+            }
+        }
+
         let response = client
-            .post(uri!(super::create_bookmark))
-            .json(&payload)
-            .dispatch();
+            .patch(uri!(super::update_bookmark(m.id)))
+            .header(ContentType::JSON)
+            .body(r#"{"reading_state": "unread"}"#)
+            .dispatch()
+            .await;
         assert_eq!(response.status(), Status::Ok);
-        let added: Bookmark = response.into_json().unwrap();
+        let response = client
+            .get(uri!(super::list_reading_queue(Some(i64::MAX))))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let queue: Vec<Bookmark> = response.into_json().await.unwrap();
+        assert!(queue.iter().any(|b| b.id == m.id));
+        assert!(queue.windows(2).all(|w| w[0].queued_at <= w[1].queued_at));
+    }
 
-        let payload = ModifyBookmark {
-            url: Some("https://www.rust-lang.org".to_string()),
-            title: Some("Rust Programming Language".to_string()),
-            tags: None,
-        };
-        assert_ne!(Some(added.title), payload.title);
-        assert_ne!(Some(added.url), payload.url);
+    #[rocket::async_test]
+    async fn history_and_revert() {
+        use crate::db::bookmark::test::create_rand_bookmark;
+        use rocket::http::ContentType;
+
+        let mut conn = crate::db::connection::establish().await;
+        let m = create_rand_bookmark(&mut conn).await;
+        let client = test_async_client().await;
 
         let response = client
-            .patch(uri!(super::update_bookmark(added.id)))
-            .json(&payload)
-            .dispatch();
+            .patch(uri!(super::update_bookmark(m.id)))
+            .header(ContentType::JSON)
+            .body(r#"{"title": "revised", "tags": ["history"]}"#)
+            .dispatch()
+            .await;
         assert_eq!(response.status(), Status::Ok);
-        let updated: Bookmark = response.into_json().unwrap();
 
-        assert_eq!(updated.id, added.id);
-        assert_eq!(updated.title, payload.title.unwrap());
-        assert_eq!(updated.url, payload.url.unwrap());
+        let response = client
+            .get(uri!(super::get_bookmark_history(m.id)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let revisions: Vec<Revision> = response.into_json().await.unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].changed, vec!["title", "tags"]);
+        assert_eq!(revisions[0].before.title, m.title);
+        assert_eq!(revisions[0].after.tags, vec!["history".to_string()]);
+
+        let response = client
+            .post(uri!(super::revert_bookmark(m.id, 99999999)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+        let response = client
+            .post(uri!(super::revert_bookmark(m.id, revisions[0].id)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let reverted: Bookmark = response.into_json().await.unwrap();
+        assert_eq!(reverted.title, m.title);
+        assert_eq!(reverted.url, m.url);
+        assert!(reverted.tags.is_empty());
+
+        let response = client
+            .get(uri!(super::get_bookmark_history(m.id)))
+            .dispatch()
+            .await;
+        let revisions: Vec<Revision> = response.into_json().await.unwrap();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].after.title, m.title);
     }
 
-    #[test]
-    fn update_missing_bookmark() {
-        let client = test_client();
-        let payload = ModifyBookmark {
-            url: Some("https://www.rust-lang.org".to_string()),
-            title: Some("Rust Programming Language".to_string()),
-            tags: None,
-        };
+    #[rocket::async_test]
+    async fn conditional_requests() {
+        use crate::db::bookmark::test::create_rand_bookmark;
+        use rocket::http::{ContentType, Header};
+
+        let mut conn = crate::db::connection::establish().await;
+        let m = create_rand_bookmark(&mut conn).await;
+        let client = test_async_client().await;
 
+        let response = client.get(uri!(super::get_bookmark(m.id))).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let etag = response.headers().get_one("ETag").unwrap().to_string();
         let response = client
-            .patch(uri!(super::update_bookmark(99999999)))
-            .json(&payload)
-            .dispatch();
+            .get(uri!(super::get_bookmark(m.id)))
+            .header(Header::new("If-None-Match", etag.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotModified);
+
+        // visiting changes the visit count
+        crate::db::bookmark::visit_bookmark(&mut conn, m.id).await;
+        let response = client
+            .get(uri!(super::get_bookmark(m.id)))
+            .header(Header::new("If-None-Match", etag.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let etag = response.headers().get_one("ETag").unwrap().to_string();
+
+        let response = client
+            .patch(uri!(super::update_bookmark(m.id)))
+            .header(ContentType::JSON)
+            .header(Header::new("If-Match", "\"stale\""))
+            .body(r#"{"title": "conditional"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::PreconditionFailed);
+
+        // changing tags only also changes the ETag
+        let response = client
+            .patch(uri!(super::update_bookmark(m.id)))
+            .header(ContentType::JSON)
+            .header(Header::new("If-Match", etag.clone()))
+            .body(r#"{"tags": ["conditional"]}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let updated = response.headers().get_one("ETag").unwrap().to_string();
+        assert_ne!(updated, etag);
+        let response = client
+            .get(uri!(super::get_bookmark(m.id)))
+            .header(Header::new("If-None-Match", etag.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.headers().get_one("ETag"), Some(updated.as_str()));
+
+        let response = client
+            .delete(uri!(super::delete_bookmark(m.id)))
+            .header(Header::new("If-Match", etag))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::PreconditionFailed);
+        let response = client
+            .delete(uri!(super::delete_bookmark(m.id)))
+            .header(Header::new("If-Match", updated))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let response = client.get(uri!(super::get_bookmark(m.id))).dispatch().await;
         assert_eq!(response.status(), Status::NotFound);
     }
 
-    #[test]
-    fn update_bookmark_no_change() {
-        let client = test_client();
-        let m = rand_bookmark();
-        let payload = CreateBookmark {
-            url: m.url,
-            title: m.title,
-            folder_id: None,
-            tags: vec!["rust".to_string(), "programming".to_string()],
-        };
+    #[rocket::async_test]
+    async fn remind_bookmarks() {
+        use crate::db::bookmark::test::create_rand_bookmark;
+        use rocket::http::ContentType;
+
+        let mut conn = crate::db::connection::establish().await;
+        let m = create_rand_bookmark(&mut conn).await;
+        let client = test_async_client().await;
+
         let response = client
-            .post(uri!(super::create_bookmark))
-            .json(&payload)
-            .dispatch();
+            .patch(uri!(super::update_bookmark(m.id)))
+            .header(ContentType::JSON)
+            .body(r#"{"remind_at": "2000-01-01T09:30:00Z"}"#)
+            .dispatch()
+            .await;
         assert_eq!(response.status(), Status::Ok);
-        let added: Bookmark = response.into_json().unwrap();
+        let updated: Bookmark = response.into_json().await.unwrap();
+        assert_eq!(
+            updated.remind_at,
+            Some(time::macros::datetime!(2000-01-01 09:30:00 UTC))
+        );
 
-        let payload = ModifyBookmark {
-            url: None,
-            title: None,
-            tags: None,
-        };
         let response = client
-            .patch(uri!(super::update_bookmark(added.id)))
-            .json(&payload)
-            .dispatch();
-        assert_eq!(response.status(), Status::BadRequest);
+            .get(uri!(super::list_due_reminders(Some(i64::MAX))))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let due: Vec<Bookmark> = response.into_json().await.unwrap();
+        assert!(due.iter().any(|b| b.id == m.id));
+
+        let response = client.get(uri!(super::get_reminders_feed)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::Calendar));
+        let feed = response.into_string().await.unwrap();
+        assert!(feed.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(feed.contains(&format!("UID:bookmark-{}@bearmark\r\n", m.id)));
+        assert!(feed.contains("DTSTART:20000101T093000Z\r\n"));
+
+        let response = client
+            .patch(uri!(super::update_bookmark(m.id)))
+            .header(ContentType::JSON)
+            .body(r#"{"remind_at": null}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let updated: Bookmark = response.into_json().await.unwrap();
+        assert!(updated.remind_at.is_none());
+        let response = client
+            .get(uri!(super::list_due_reminders(Some(i64::MAX))))
+            .dispatch()
+            .await;
+        let due: Vec<Bookmark> = response.into_json().await.unwrap();
+        assert!(!due.iter().any(|b| b.id == m.id));
     }
 
-    #[test]
-    fn update_bookmark_tags() {
-        let client = test_client();
-        let m = rand_bookmark();
-        let payload = CreateBookmark {
-            url: m.url,
-            title: m.title,
-            folder_id: None,
-            tags: vec!["rust", "programming"]
-                .into_iter()
-                .map(|s| s.to_string())
-                .collect(),
-        };
+    #[rocket::async_test]
+    async fn bookmark_attributes() {
+        use rocket::http::ContentType;
+        use serde_json::json;
+
+        let client = test_async_client().await;
+        let keyword = crate::utils::rand::rand_str(10);
         let response = client
             .post(uri!(super::create_bookmark))
-            .json(&payload)
-            .dispatch();
+            .header(ContentType::JSON)
+            .body(
+                json!({
+                    "title": keyword,
+                    "url": "https://example.com/attributes",
+                    "tags": [],
+                    "attributes": {"ticket": "BM-1", "owner": "alice"}
+                })
+                .to_string(),
+            )
+            .dispatch()
+            .await;
         assert_eq!(response.status(), Status::Ok);
-        let added: Bookmark = response.into_json().unwrap();
+        let created: Bookmark = response.into_json().await.unwrap();
+        assert_eq!(
+            created.attributes,
+            json!({"ticket": "BM-1", "owner": "alice"})
+        );
 
-        let modify_tags = vec!["doc", "programming", "rust"]
-            .into_iter()
-            .map(|s| s.to_string())
-            .collect_vec();
-        let payload = ModifyBookmark {
-            url: None,
-            title: None,
-            tags: Some(modify_tags.clone()),
-        };
+        let q = format!("{keyword} attr.owner:alice");
+        let response = client
+            .get(uri!(super::search_bookmarks(
+                q = Some(&q),
+                cwd = None::<&str>,
+                before = None::<i32>,
+                limit = None::<i64>,
+                sort = None::<&str>,
+                pinned_first = None::<bool>
+            )))
+            .dispatch()
+            .await;
+        let found: Vec<Bookmark> = response.into_json().await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, created.id);
 
         let response = client
-            .patch(uri!(super::update_bookmark(added.id)))
-            .json(&payload)
-            .dispatch();
+            .patch(uri!(super::update_bookmark(created.id)))
+            .header(ContentType::JSON)
+            .body(r#"{"attributes": {"owner": "bob"}}"#)
+            .dispatch()
+            .await;
         assert_eq!(response.status(), Status::Ok);
-        let updated: Bookmark = response.into_json().unwrap();
+        let updated: Bookmark = response.into_json().await.unwrap();
+        assert_eq!(updated.attributes, json!({"owner": "bob"}));
 
-        assert_eq!(updated.id, added.id);
-        assert_eq!(updated.title, added.title);
-        assert_eq!(updated.url, added.url);
-        assert_eq!(updated.tags, modify_tags);
+        let response = client
+            .patch(uri!(super::update_bookmark(created.id)))
+            .header(ContentType::JSON)
+            .body(r#"{"attributes": ["owner"]}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[rocket::async_test]
+    async fn bookmark_shortcuts() {
+        use crate::db::bookmark::test::create_rand_bookmark;
+        use rocket::http::ContentType;
+        use serde_json::json;
+
+        let mut conn = crate::db::connection::establish().await;
+        let other = create_rand_bookmark(&mut conn).await;
+        let client = test_async_client().await;
+        let keyword = crate::utils::rand::rand_str(8);
+
+        let response = client
+            .post(uri!(super::create_bookmark))
+            .header(ContentType::JSON)
+            .body(
+                json!({
+                    "url": "https://docs.rs/%s",
+                    "tags": [],
+                    "shortcut": keyword
+                })
+                .to_string(),
+            )
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let created: Bookmark = response.into_json().await.unwrap();
+        assert_eq!(created.shortcut.as_deref(), Some(keyword.as_str()));
+
+        for (body, status) in [
+            (json!({"shortcut": keyword}), Status::BadRequest),
+            (json!({"shortcut": "two words"}), Status::BadRequest),
+            (json!({"shortcut": " "}), Status::BadRequest),
+        ] {
+            let response = client
+                .patch(uri!(super::update_bookmark(other.id)))
+                .header(ContentType::JSON)
+                .body(body.to_string())
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), status, "{body}");
+        }
+
+        let response = client
+            .patch(uri!(super::update_bookmark(created.id)))
+            .header(ContentType::JSON)
+            .body(r#"{"shortcut": null}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let updated: Bookmark = response.into_json().await.unwrap();
+        assert!(updated.shortcut.is_none());
+
+        let response = client
+            .patch(uri!(super::update_bookmark(other.id)))
+            .header(ContentType::JSON)
+            .body(json!({"shortcut": keyword}).to_string())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
     }
 }
@@ -0,0 +1,148 @@
+use rocket::http::Header;
+use rocket::request::{FromRequest, Outcome};
+use sha2::{Digest, Sha256};
+
+use super::errors::Error;
+
+/// The entity tag of a resource, derived from when it was updated.
+pub fn etag(updated_at: time::OffsetDateTime) -> String {
+    format!("\"{:x}\"", updated_at.unix_timestamp_nanos())
+}
+
+/// The entity tag of a list of resources, derived from their ids and when they were updated.
+pub fn etag_of_many(items: impl IntoIterator<Item = (i32, time::OffsetDateTime)>) -> String {
+    etag_of_versions(
+        items
+            .into_iter()
+            .map(|(id, updated_at)| (id, updated_at.unix_timestamp_nanos().to_string())),
+    )
+}
+
+/// The entity tag of a list of resources, derived from their ids and versions, for resources
+/// changed in other ways than being updated.
+pub fn etag_of_versions(items: impl IntoIterator<Item = (i32, String)>) -> String {
+    let mut hasher = Sha256::new();
+    for (id, version) in items {
+        hasher.update(format!("{id}:{version};"));
+    }
+    format!("\"{:x}\"", hasher.finalize())
+}
+
+/// Whether any of the comma separated entity tags matches, `*` matches anything.
+fn matches(header: &str, etag: &str, weak: bool) -> bool {
+    header.split(',').map(str::trim).any(|tag| {
+        let tag = if weak {
+            tag.strip_prefix("W/").unwrap_or(tag)
+        } else {
+            tag
+        };
+        tag == "*" || tag == etag
+    })
+}
+
+/// The `If-Match` and `If-None-Match` headers of a conditional request.
+#[derive(Debug, Default)]
+pub struct Preconditions {
+    if_match: Option<String>,
+    if_none_match: Option<String>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Preconditions {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r rocket::Request<'_>) -> Outcome<Self, Self::Error> {
+        let headers = request.headers();
+        Outcome::Success(Preconditions {
+            if_match: headers.get_one("If-Match").map(str::to_string),
+            if_none_match: headers.get_one("If-None-Match").map(str::to_string),
+        })
+    }
+}
+
+impl Preconditions {
+    /// Check `If-Match` against the entity tag of the resource before changing it.
+    pub fn check(&self, etag: &str) -> Result<(), Error> {
+        match self.if_match.as_deref() {
+            Some(header) if !matches(header, etag, false) => Err(Error::PreconditionFailed(
+                format!("Resource has been modified, the current ETag is {etag}"),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Respond with the entity tag, or 304 if the client has it by `If-None-Match` already.
+    pub fn respond<R>(&self, body: R, etag: String) -> Tagged<R> {
+        match self.if_none_match.as_deref() {
+            Some(header) if matches(header, &etag, true) => {
+                Tagged::NotModified((), Header::new("ETag", etag))
+            }
+            _ => Tagged::Fresh(body, Header::new("ETag", etag)),
+        }
+    }
+}
+
+/// A response carrying the `ETag` header.
+#[derive(Responder)]
+pub enum Tagged<R> {
+    Fresh(R, Header<'static>),
+    #[response(status = 304)]
+    NotModified((), Header<'static>),
+}
+
+impl<R> Tagged<R> {
+    pub fn new(body: R, etag: String) -> Self {
+        Tagged::Fresh(body, Header::new("ETag", etag))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_preconditions() {
+        let updated_at = time::macros::datetime!(2024-01-01 00:00:00 UTC);
+        let tag = etag(updated_at);
+        assert_ne!(tag, etag(updated_at + time::Duration::microseconds(1)));
+
+        let conditions = |if_match: Option<&str>, if_none_match: Option<&str>| Preconditions {
+            if_match: if_match.map(str::to_string),
+            if_none_match: if_none_match.map(str::to_string),
+        };
+        assert!(conditions(None, None).check(&tag).is_ok());
+        assert!(conditions(Some("*"), None).check(&tag).is_ok());
+        assert!(
+            conditions(Some(&format!("\"x\", {tag}")), None)
+                .check(&tag)
+                .is_ok()
+        );
+        assert!(matches!(
+            conditions(Some("\"x\""), None).check(&tag),
+            Err(Error::PreconditionFailed(_))
+        ));
+        assert!(matches!(
+            conditions(Some(&format!("W/{tag}")), None).check(&tag),
+            Err(Error::PreconditionFailed(_))
+        ));
+
+        for (if_none_match, modified) in [
+            (None, true),
+            (Some("\"x\"".to_string()), true),
+            (Some(tag.clone()), false),
+            (Some(format!("W/{tag}")), false),
+            (Some("*".to_string()), false),
+        ] {
+            let rv = conditions(None, if_none_match.as_deref()).respond((), tag.clone());
+            assert_eq!(
+                matches!(rv, Tagged::Fresh(..)),
+                modified,
+                "{if_none_match:?}"
+            );
+        }
+
+        let many = etag_of_many([(1, updated_at), (2, updated_at)]);
+        assert_ne!(many, etag_of_many([(1, updated_at)]));
+        assert_eq!(many, etag_of_many([(1, updated_at), (2, updated_at)]));
+    }
+}
@@ -8,6 +8,119 @@ use rocket::{
 pub struct Config {
     pub ui_path: Option<String>,
     pub api_key: Option<String>,
+    /// The URL where the service is served to the public, such as `https://bm.example.com`, used
+    /// in documents for other apps. Derived from the `Host` header if not set.
+    pub public_url: Option<String>,
+    /// Days to keep deleted bookmarks in the trash before purging them automatically.
+    pub trash_retention_days: Option<u32>,
+    /// Query parameters stripped from URLs on canonicalization, a trailing `*` matches any suffix.
+    pub tracking_params: Option<Vec<String>>,
+    /// Settings of fetching web pages of bookmarks in the background.
+    #[serde(default)]
+    pub fetcher: FetcherConfig,
+    /// Settings of checking links of bookmarks periodically, sharing the fetcher's client.
+    #[serde(default)]
+    pub link_checker: LinkCheckerConfig,
+    /// Settings of archiving pages of bookmarks.
+    #[serde(default)]
+    pub archive: ArchiveConfig,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum ArchiveStorageKind {
+    Local,
+    Postgres,
+}
+
+impl ArchiveStorageKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArchiveStorageKind::Local => "local",
+            ArchiveStorageKind::Postgres => "postgres",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct ArchiveConfig {
+    /// Storage of new archives.
+    pub storage: ArchiveStorageKind,
+    /// Directory of the local storage.
+    pub path: String,
+    /// Archive pages of new bookmarks in the background, along with fetching their metadata.
+    pub on_create: bool,
+    /// Inline images, stylesheets and scripts into archived pages.
+    pub inline_assets: bool,
+    /// Bytes of a page with its assets to archive at most.
+    pub max_bytes: usize,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            storage: ArchiveStorageKind::Local,
+            path: "archives".to_string(),
+            on_create: false,
+            inline_assets: false,
+            max_bytes: 10 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct FetcherConfig {
+    /// Off by default, as the server would request whatever URL is saved.
+    pub enabled: bool,
+    /// Seconds between polls for bookmarks to fetch.
+    pub interval_secs: u64,
+    /// Seconds before a request is aborted.
+    pub timeout_secs: u64,
+    /// Bytes of a page to read at most, the rest is ignored.
+    pub max_bytes: usize,
+    pub user_agent: String,
+    /// Allow requesting loopback, private and link-local addresses, e.g. of the local network.
+    pub allow_private_addresses: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct LinkCheckerConfig {
+    /// Check the links of bookmarks for broken ones. Off by default, as every saved URL is
+    /// requested again each `recheck_after_hours`.
+    pub enabled: bool,
+    /// Seconds between polls for links to check.
+    pub interval_secs: u64,
+    /// Hours before a checked link is checked again.
+    pub recheck_after_hours: u32,
+    /// Milliseconds to wait between requests to the same host.
+    pub per_host_delay_ms: u64,
+}
+
+impl Default for LinkCheckerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 60 * 60,
+            recheck_after_hours: 7 * 24,
+            per_host_delay_ms: 1000,
+        }
+    }
+}
+
+impl Default for FetcherConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 10,
+            timeout_secs: 10,
+            max_bytes: 1024 * 1024,
+            user_agent: concat!("bearmark/", env!("CARGO_PKG_VERSION")).to_string(),
+            allow_private_addresses: false,
+        }
+    }
 }
 
 pub fn config_provider() -> Figment {
@@ -19,8 +132,39 @@ pub fn config_provider() -> Figment {
         .merge(Env::prefixed("BM_").global())
 }
 
+/// Whether saved bookmarks are queued for fetching their metadata.
+pub fn is_fetcher_enabled() -> bool {
+    use std::sync::OnceLock;
+
+    static FETCHER_ENABLED: OnceLock<bool> = OnceLock::new();
+    *FETCHER_ENABLED.get_or_init(|| {
+        config_provider()
+            .extract_inner::<bool>("fetcher.enabled")
+            .unwrap()
+    })
+}
+
 pub fn get_database_url() -> String {
     config_provider()
         .extract_inner("databases.main.url")
         .unwrap()
 }
+
+pub fn get_tracking_params() -> &'static [String] {
+    use std::sync::OnceLock;
+
+    use crate::utils::canonical_url::DEFAULT_TRACKING_PARAMS;
+
+    static TRACKING_PARAMS: OnceLock<Vec<String>> = OnceLock::new();
+    TRACKING_PARAMS.get_or_init(|| {
+        config_provider()
+            .extract_inner::<Option<Vec<String>>>("tracking_params")
+            .unwrap()
+            .unwrap_or_else(|| {
+                DEFAULT_TRACKING_PARAMS
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect()
+            })
+    })
+}
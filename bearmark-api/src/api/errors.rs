@@ -1,7 +1,7 @@
 use serde::Serialize;
 use utoipa::ToSchema;
 
-use crate::utils::{BearQLError, CommonError, DatabaseError};
+use crate::utils::{ArchiveError, BearQLError, CommonError, DatabaseError};
 
 #[derive(Responder, Debug, Serialize, ToSchema)]
 pub enum Error {
@@ -9,6 +9,8 @@ pub enum Error {
     NotFound(String),
     #[response(status = 400)]
     BadRequest(String),
+    #[response(status = 412)]
+    PreconditionFailed(String),
     #[response(status = 500)]
     InternalServer(String),
 
@@ -18,6 +20,33 @@ pub enum Error {
     InvalidAPIKey(String),
 }
 
+impl Error {
+    /// Prefix the error message with the given context.
+    pub fn context(self, ctx: impl std::fmt::Display) -> Self {
+        match self {
+            Error::NotFound(msg) => Error::NotFound(format!("{ctx}: {msg}")),
+            Error::BadRequest(msg) => Error::BadRequest(format!("{ctx}: {msg}")),
+            Error::PreconditionFailed(msg) => Error::PreconditionFailed(format!("{ctx}: {msg}")),
+            Error::InternalServer(msg) => Error::InternalServer(format!("{ctx}: {msg}")),
+            Error::MissingAPIKey(msg) => Error::MissingAPIKey(format!("{ctx}: {msg}")),
+            Error::InvalidAPIKey(msg) => Error::InvalidAPIKey(format!("{ctx}: {msg}")),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(msg)
+            | Error::BadRequest(msg)
+            | Error::PreconditionFailed(msg)
+            | Error::InternalServer(msg)
+            | Error::MissingAPIKey(msg)
+            | Error::InvalidAPIKey(msg) => f.write_str(msg),
+        }
+    }
+}
+
 impl From<DatabaseError> for Error {
     fn from(e: DatabaseError) -> Self {
         match e {
@@ -37,6 +66,12 @@ impl From<BearQLError> for Error {
             } => Error::BadRequest(format!("Syntax Error: {msg}")),
             BearQLError::EmptyKeyword => Error::BadRequest("Empty keyword error".to_string()),
             BearQLError::EmptyTag => Error::BadRequest("Empty tag name error".to_string()),
+            BearQLError::EmptyQualifier(k) => {
+                Error::BadRequest(format!("Empty value of qualifier {k} error"))
+            }
+            BearQLError::UnknownQualifier(k) => {
+                Error::BadRequest(format!("Unknown qualifier {k} error"))
+            }
         }
     }
 }
@@ -52,6 +87,20 @@ impl From<CommonError> for Error {
         match e {
             CommonError::InvalidCWD => Error::BadRequest("Invalid CWD".to_string()),
             CommonError::BearQL(e) => Error::from(e),
+            CommonError::InvalidTransition { .. } => Error::BadRequest(e.to_string()),
+        }
+    }
+}
+
+impl From<ArchiveError> for Error {
+    fn from(e: ArchiveError) -> Self {
+        match e {
+            ArchiveError::Disabled
+            | ArchiveError::Fetch(_)
+            | ArchiveError::Status(_)
+            | ArchiveError::TooLarge(_) => Error::BadRequest(e.to_string()),
+            ArchiveError::Missing(_) => Error::NotFound(e.to_string()),
+            ArchiveError::Storage(_) => Error::InternalServer(e.to_string()),
         }
     }
 }
@@ -1,11 +1,15 @@
+use super::conditional::{Preconditions, Tagged, etag, etag_of_many};
 use super::errors::Error;
 use super::fairings::db::Db;
 use crate::api::guards;
 use crate::db::{
     bookmark::Bookmark,
     folder::{self, Folder},
+    revision,
 };
 
+use diesel_async::AsyncConnection;
+use diesel_async::scoped_futures::ScopedFutureExt;
 use rocket::serde::json::Json;
 use rocket::serde::{Deserialize, Serialize};
 use rocket_db_pools::Connection;
@@ -24,7 +28,8 @@ pub struct CreateFolder {
     path = "/",
     request_body = CreateFolder,
     responses(
-        (status = 200, description = "Folder created success", body = Folder),
+        (status = 200, description = "Folder created success", body = Folder,
+            headers(("ETag" = String, description = "The entity tag of the folder"))),
         (status = 400, description = "Folder already exists"),
         (status = 404, description = "Parent folder does not exist")
     ),
@@ -37,7 +42,7 @@ pub async fn create_folder(
     mut db: Connection<Db>,
     _required: guards::Auth,
     payload: Json<CreateFolder>,
-) -> Result<Json<Folder>, Error> {
+) -> Result<Tagged<Json<Folder>>, Error> {
     let path = payload.into_inner().path;
 
     let mut self_and_ancestors = Folder::get_with_ancestors(&mut db, &path).await.into_iter();
@@ -55,7 +60,9 @@ pub async fn create_folder(
         return Err(Error::NotFound("Parent folder does not exist".to_string()));
     }
 
-    Ok(Json(folder::create_folder(&mut db, &path).await?))
+    let folder = folder::create_folder(&mut db, &path).await?;
+    let etag = etag(folder.updated_at);
+    Ok(Tagged::new(Json(folder), etag))
 }
 
 /// List folders
@@ -66,7 +73,8 @@ pub async fn create_folder(
         ("cwd" = inline(Option<&str>), Query, description = "The path of folder to search in"),
     ),
     responses(
-        (status = 200, description = "Folders searched success", body = Vec<Folder>)
+        (status = 200, description = "Folders searched success", body = Vec<Folder>),
+        (status = 304, description = "Folders not modified since `If-None-Match`")
     ),
     security(
         ("api_key" = [])
@@ -76,9 +84,12 @@ pub async fn create_folder(
 pub async fn list_folders(
     mut db: Connection<Db>,
     _required: guards::Auth,
+    conditions: Preconditions,
     cwd: Option<&str>,
-) -> Json<Vec<Folder>> {
-    Json(folder::list_folders(&mut db, cwd.unwrap_or_default()).await)
+) -> Tagged<Json<Vec<Folder>>> {
+    let folders = folder::list_folders(&mut db, cwd.unwrap_or_default()).await;
+    let etag = etag_of_many(folders.iter().map(|f| (f.id, f.updated_at)));
+    conditions.respond(Json(folders), etag)
 }
 
 /// Move a bookmark into a folder
@@ -119,7 +130,16 @@ pub async fn move_bookmark(
     let _ = Folder::get(&mut db, id)
         .await
         .ok_or(Error::NotFound("Folder not found".to_string()))?;
-    Ok(folder::move_bookmarks(&mut db, id, &vec![bookmark_id]).await?)
+    db.transaction::<_, Error, _>(|db| {
+        async move {
+            let before = revision::take_snapshots(db, &[bookmark_id]).await;
+            folder::move_bookmarks(db, id, &vec![bookmark_id]).await?;
+            revision::record_revisions(db, before).await;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await
 }
 
 /// Move a bookmark out of a folder
@@ -148,8 +168,16 @@ pub async fn move_out_bookmark(
         .folder_id
         .ok_or(Error::BadRequest("Bookmark is not in a folder".to_string()))?;
     info!(?bookmark_id, ?folder_id, "Moving out bookmark");
-    folder::move_out_bookmarks(&mut db, &vec![bookmark_id]).await;
-    Ok(())
+    db.transaction::<_, Error, _>(|db| {
+        async move {
+            let before = revision::take_snapshots(db, &[bookmark_id]).await;
+            folder::move_out_bookmarks(db, &vec![bookmark_id]).await;
+            revision::record_revisions(db, before).await;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await
 }
 
 pub fn routes() -> Vec<rocket::Route> {
@@ -372,5 +400,20 @@ pub(crate) mod test {
             .dispatch()
             .await;
         assert_eq!(res.status(), Status::BadRequest);
+
+        // each movement is recorded, the latest first
+        let folders = revision::get_revisions(&mut conn, bm.id)
+            .await
+            .into_iter()
+            .map(|r| (r.old_folder, r.new_folder))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            folders,
+            vec![
+                (Some(f02.path.clone()), None),
+                (Some(f01.path.clone()), Some(f02.path)),
+                (None, Some(f01.path)),
+            ]
+        );
     }
 }
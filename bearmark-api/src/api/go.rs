@@ -0,0 +1,141 @@
+use super::errors::Error;
+use super::fairings::db::Db;
+use super::guards;
+use crate::db::bookmark;
+
+use rocket::response::Redirect;
+use rocket_db_pools::Connection;
+
+/// Visit a bookmark, recording the visit and redirecting to its URL
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    params(
+        ("id" = inline(i32), Path, description = "The bookmark id to visit"),
+        ("key" = inline(Option<&str>), Query, description = "The API key, for browsers which can not set the `Authorization` header")
+    ),
+    responses(
+        (status = 302, description = "Redirect to the URL of the bookmark"),
+        (status = 404, description = "Bookmark not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/<id>")]
+pub async fn visit_bookmark(
+    mut db: Connection<Db>,
+    _required: guards::QueryAuth,
+    id: i32,
+) -> Result<Redirect, Error> {
+    let m = bookmark::visit_bookmark(&mut db, id)
+        .await
+        .ok_or_else(|| Error::NotFound("Bookmark not found".to_string()))?;
+
+    Ok(Redirect::found(m.url))
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![visit_bookmark]
+}
+
+#[cfg(not(tarpaulin_include))]
+pub(crate) mod misc {
+    use super::*;
+
+    use utoipa::{OpenApi, Path};
+
+    pub struct ApiDoc;
+
+    impl OpenApi for ApiDoc {
+        fn openapi() -> utoipa::openapi::OpenApi {
+            use utoipa::openapi::{
+                InfoBuilder, OpenApiBuilder,
+                security::{ApiKey, ApiKeyValue, SecurityScheme},
+            };
+
+            let mut api = OpenApiBuilder::new()
+                .info(
+                    InfoBuilder::new()
+                        .title("Go API")
+                        .description(Some("Visit bookmarks by redirecting"))
+                        .version("1.0")
+                        .build(),
+                )
+                .paths(bearmark_macro::utoipa_paths!("/go", visit_bookmark))
+                .build();
+
+            api.components
+                .get_or_insert_with(Default::default)
+                .add_security_scheme(
+                    "api_key",
+                    SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Authorization"))),
+                );
+
+            api
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::configs::{self, Config};
+    use crate::db::bookmark::test::create_rand_bookmark;
+    use crate::db::connection;
+
+    use crate::utils::rand::rand_str;
+
+    use rocket::fairing::AdHoc;
+    use rocket::figment::Figment;
+    use rocket::http::Status;
+    use rocket::local::asynchronous;
+    use rocket_db_pools::Database;
+
+    fn test_app(figment: Figment) -> rocket::Rocket<rocket::Build> {
+        rocket::custom(figment)
+            .attach(Db::init())
+            .mount("/", routes())
+            .attach(AdHoc::config::<Config>())
+    }
+
+    async fn test_async_client(figment: Figment) -> asynchronous::Client {
+        asynchronous::Client::tracked(test_app(figment))
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[rocket::async_test]
+    async fn visit_and_redirect() {
+        let mut conn = connection::establish().await;
+        let m = create_rand_bookmark(&mut conn).await;
+        let client = test_async_client(configs::config_provider()).await;
+
+        for _ in 0..2 {
+            let response = client.get(uri!(visit_bookmark(m.id))).dispatch().await;
+            assert_eq!(response.status(), Status::Found);
+            assert_eq!(response.headers().get_one("Location"), Some(m.url.as_str()));
+        }
+        let visited = bookmark::Bookmark::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(visited.visit_count, 2);
+
+        let response = client.get(uri!(visit_bookmark(99999999))).dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn visit_with_key_in_query() {
+        let mut conn = connection::establish().await;
+        let m = create_rand_bookmark(&mut conn).await;
+        let key = rand_str(32);
+        let client =
+            test_async_client(configs::config_provider().merge(("api_key", key.clone()))).await;
+
+        let response = client.get(uri!(visit_bookmark(m.id))).dispatch().await;
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        let response = client.get(format!("/{}?key={key}", m.id)).dispatch().await;
+        assert_eq!(response.status(), Status::Found);
+        assert_eq!(response.headers().get_one("Location"), Some(m.url.as_str()));
+    }
+}
@@ -6,35 +6,61 @@ use crate::api::errors::Error;
 
 pub struct Auth;
 
+/// Check the token against the API key if it is configured.
+fn authenticate<T>(
+    request: &rocket::Request<'_>,
+    token: Option<&str>,
+    guard: T,
+) -> Outcome<T, Error> {
+    let Some(config) = request.rocket().state::<Config>() else {
+        return Outcome::Error((
+            Status::InternalServerError,
+            Error::InternalServer("Missing Config".to_string()),
+        ));
+    };
+    match (config.api_key.as_ref(), token) {
+        (None, _) => Outcome::Success(guard),
+        (Some(key), Some(token)) if token == key => Outcome::Success(guard),
+        (Some(_), Some(_)) => Outcome::Error((
+            Status::Forbidden,
+            Error::InvalidAPIKey("Invalid API Key".to_string()),
+        )),
+        (Some(_), None) => Outcome::Error((
+            Status::Unauthorized,
+            Error::MissingAPIKey("Missing API Key".to_string()),
+        )),
+    }
+}
+
 #[rocket::async_trait]
 impl<'r> FromRequest<'r> for Auth {
     type Error = Error;
 
     async fn from_request(request: &'r rocket::Request<'_>) -> Outcome<Self, Self::Error> {
-        // Check if the user is authenticated
-        if let Some(config) = request.rocket().state::<Config>() {
-            if let Some(key) = config.api_key.as_ref() {
-                let token = request.headers().get_one("Authorization");
-                if let Some(token) = token {
-                    if token != key {
-                        return Outcome::Error((
-                            Status::Forbidden,
-                            Error::InvalidAPIKey("Invalid API Key".to_string()),
-                        ));
-                    }
-                } else {
-                    return Outcome::Error((
-                        Status::Unauthorized,
-                        Error::MissingAPIKey("Missing API Key".to_string()),
-                    ));
-                }
-            }
-            return Outcome::Success(Auth);
-        };
-        return Outcome::Error((
-            Status::InternalServerError,
-            Error::InternalServer("Missing Config".to_string()),
-        ));
+        let token = request.headers().get_one("Authorization");
+        authenticate(request, token, Auth)
+    }
+}
+
+/// The cookie keeping the API key for browsers, such as bookmarklets saving pages.
+pub const KEY_COOKIE: &str = "bearmark_key";
+
+/// Authenticated by the `Authorization` header, or the `key` query parameter for clients which
+/// can not set headers, such as calendar apps subscribing a feed or browsers opening a shortcut,
+/// or the [`KEY_COOKIE`] cookie.
+pub struct QueryAuth;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for QueryAuth {
+    type Error = Error;
+
+    async fn from_request(request: &'r rocket::Request<'_>) -> Outcome<Self, Self::Error> {
+        let token = request
+            .headers()
+            .get_one("Authorization")
+            .or_else(|| request.query_value::<&str>("key").and_then(Result::ok))
+            .or_else(|| request.cookies().get(KEY_COOKIE).map(|c| c.value()));
+        authenticate(request, token, QueryAuth)
     }
 }
 
@@ -53,6 +79,11 @@ mod test {
         "Hello, World!"
     }
 
+    #[get("/feed")]
+    fn feed(_required: QueryAuth) -> &'static str {
+        "Hello, World!"
+    }
+
     #[test]
     fn test_without_config() {
         let app = rocket::build().mount("/", routes![required_auth]);
@@ -103,4 +134,37 @@ mod test {
             .dispatch();
         assert_eq!(response.status(), Status::Forbidden);
     }
+
+    #[test]
+    fn test_feed_auth() {
+        use rocket::figment::{Figment, providers::Serialized};
+
+        let key = rand_str(32);
+        let figment =
+            Figment::from(rocket::Config::default()).merge(Serialized::defaults(Config {
+                api_key: Some(key.clone()),
+                ..Default::default()
+            }));
+        let app = rocket::custom(figment)
+            .mount("/", routes![feed])
+            .attach(AdHoc::config::<Config>());
+        let client = blocking::Client::tracked(app).expect("valid rocket instance");
+
+        let response = client.get("/feed").dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+        let response = client.get(format!("/feed?key={key}")).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let response = client
+            .get("/feed")
+            .cookie(rocket::http::Cookie::new(KEY_COOKIE, key.clone()))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let response = client
+            .get("/feed")
+            .header(Header::new("Authorization", key))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let response = client.get(format!("/feed?key={}", rand_str(32))).dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+    }
 }
@@ -1,3 +1,4 @@
+mod conditional;
 pub mod configs;
 mod errors;
 pub mod fairings;
@@ -5,4 +6,10 @@ mod guards;
 
 pub mod bookmark;
 pub mod folder;
+pub mod go;
+pub mod opensearch;
+pub mod save;
+pub mod shortcut;
 pub mod tag;
+pub mod transfer;
+pub mod trash;
@@ -0,0 +1,461 @@
+use super::configs::Config;
+use super::errors::Error;
+use super::fairings::db::Db;
+use super::guards;
+use super::shortcut::expand_template;
+use crate::db::{self, bookmark, shortcut};
+use crate::utils::html::{content_security_policy, escape_html, render_link};
+
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use rocket::http::{ContentType, Header};
+use rocket::request::{FromRequest, Outcome};
+use rocket::response::Redirect;
+use rocket::response::content::RawHtml;
+use rocket::serde::json::Json;
+use rocket_db_pools::Connection;
+
+/// Bookmarks listed on the results page at most.
+const MAX_RESULTS: i64 = 50;
+/// Suggestions listed in the address bar at most.
+const MAX_SUGGESTIONS: i64 = 10;
+
+/// The URL where the service is served, from the config, or the `Host` header of the request.
+pub struct BaseUrl(pub String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for BaseUrl {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r rocket::Request<'_>) -> Outcome<Self, Self::Error> {
+        let configured = request
+            .rocket()
+            .state::<Config>()
+            .and_then(|c| c.public_url.as_deref());
+        let url = match configured {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => {
+                let headers = request.headers();
+                let scheme = headers.get_one("X-Forwarded-Proto").unwrap_or("http");
+                let host = headers.get_one("Host").unwrap_or("localhost");
+                format!("{scheme}://{host}")
+            }
+        };
+        Outcome::Success(BaseUrl(url))
+    }
+}
+
+/// The `key` query parameter to be appended to URLs, if the API key is given by it.
+fn key_param(key: Option<&str>) -> String {
+    key.map(|k| format!("&key={}", utf8_percent_encode(k, NON_ALPHANUMERIC)))
+        .unwrap_or_default()
+}
+
+/// Get the OpenSearch description to add the service as a search engine of browsers
+#[utoipa::path(
+    get,
+    path = "/opensearch.xml",
+    params(
+        ("key" = inline(Option<&str>), Query, description = "The API key, which is kept in the URLs of the description for browsers")
+    ),
+    responses(
+        (status = 200, description = "The OpenSearch description", content_type = "application/opensearchdescription+xml", body = String)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/opensearch.xml?<key>")]
+pub async fn get_description(
+    _required: guards::QueryAuth,
+    base: BaseUrl,
+    key: Option<&str>,
+) -> (ContentType, String) {
+    let (base, key) = (escape_html(&base.0), escape_html(&key_param(key)));
+    let description = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<OpenSearchDescription xmlns="http://a9.com/-/spec/opensearch/1.1/" xmlns:moz="http://www.mozilla.org/2006/browser/search/">
+  <ShortName>Bearmark</ShortName>
+  <Description>Search bookmarks in Bearmark</Description>
+  <InputEncoding>UTF-8</InputEncoding>
+  <Url type="text/html" method="get" template="{base}/search?q={{searchTerms}}{key}"/>
+  <Url type="application/x-suggestions+json" method="get" template="{base}/search/suggestions?q={{searchTerms}}{key}"/>
+  <moz:SearchForm>{base}/search</moz:SearchForm>
+</OpenSearchDescription>
+"#
+    );
+
+    (
+        ContentType::new("application", "opensearchdescription+xml"),
+        description,
+    )
+}
+
+/// An HTML page with the `Content-Security-Policy` allowing nothing but its inline scripts.
+#[derive(Responder)]
+pub struct Page(RawHtml<String>, Header<'static>);
+
+impl Page {
+    pub fn new(html: String, scripts: &[&str]) -> Self {
+        Page(
+            RawHtml(html),
+            Header::new("Content-Security-Policy", content_security_policy(scripts)),
+        )
+    }
+}
+
+#[derive(Responder)]
+#[allow(clippy::large_enum_variant)]
+pub enum SearchResponse {
+    Redirect(Redirect),
+    Page(Page),
+}
+
+fn render_results(q: &str, key: Option<&str>, bookmarks: &[bookmark::Bookmark]) -> String {
+    let items = bookmarks
+        .iter()
+        .map(|m| {
+            let title = if m.title.is_empty() { &m.url } else { &m.title };
+            format!(
+                "<li>{}<br><small>{}</small></li>\n",
+                render_link(&m.url, title),
+                escape_html(&m.url)
+            )
+        })
+        .collect::<String>();
+    let results = if items.is_empty() {
+        "<p>No bookmarks found</p>".to_string()
+    } else {
+        format!("<ol>\n{items}</ol>")
+    };
+    // keep the API key for the search engine and the next search
+    let (description, key_input) = match key {
+        Some(k) => (
+            format!("/opensearch.xml?{}", key_param(key).trim_start_matches('&')),
+            format!(
+                r#"<input type="hidden" name="key" value="{}">"#,
+                escape_html(k)
+            ),
+        ),
+        None => ("/opensearch.xml".to_string(), String::new()),
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{q} - Bearmark</title>
+<link rel="search" type="application/opensearchdescription+xml" title="Bearmark" href="{description}">
+</head>
+<body>
+<form action="/search"><input name="q" value="{q}">{key_input}</form>
+{results}
+</body>
+</html>
+"#,
+        q = escape_html(q),
+        description = escape_html(&description),
+    )
+}
+
+/// Search bookmarks from the address bar of browsers
+///
+/// It redirects to the URL of a shortcut if the query starts with one, or the single matched
+/// bookmark, otherwise renders a page of the results.
+#[utoipa::path(
+    get,
+    path = "/search",
+    params(
+        ("q" = inline(Option<&str>), Query, description = "Search query language, or a shortcut followed by its arguments"),
+        ("key" = inline(Option<&str>), Query, description = "The API key, for browsers which can not set the `Authorization` header")
+    ),
+    responses(
+        (status = 302, description = "Redirect to the URL of the shortcut or the single matched bookmark"),
+        (status = 200, description = "The page of searched bookmarks", content_type = "text/html", body = String),
+        (status = 400, description = "Bad query request")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/search?<q>&<key>")]
+pub async fn search(
+    mut db: Connection<Db>,
+    _required: guards::QueryAuth,
+    q: Option<&str>,
+    key: Option<&str>,
+) -> Result<SearchResponse, Error> {
+    let q = q.unwrap_or_default().trim();
+    if q.is_empty() {
+        return Ok(SearchResponse::Page(Page::new(
+            render_results(q, key, &[]),
+            &[],
+        )));
+    }
+
+    let (keyword, args) = q.split_once(char::is_whitespace).unwrap_or((q, ""));
+    if let Some(m) = shortcut::get_by_shortcut(&mut db, keyword).await {
+        bookmark::visit_bookmark(&mut db, m.id).await;
+        return Ok(SearchResponse::Redirect(Redirect::found(expand_template(
+            &m.url,
+            args.trim(),
+        ))));
+    }
+
+    let rv = db::search_bookmarks(
+        &mut db,
+        Some(q),
+        None,
+        db::Sort::Frecency,
+        false,
+        0,
+        MAX_RESULTS,
+    )
+    .await?;
+    let bookmarks = rv.into_iter().map(|(m, _, _)| m).collect::<Vec<_>>();
+    if let [m] = bookmarks.as_slice() {
+        bookmark::visit_bookmark(&mut db, m.id).await;
+        return Ok(SearchResponse::Redirect(Redirect::found(m.url.clone())));
+    }
+
+    Ok(SearchResponse::Page(Page::new(
+        render_results(q, key, &bookmarks),
+        &[],
+    )))
+}
+
+/// Suggest bookmarks while typing in the address bar of browsers
+///
+/// The response is in the OpenSearch suggestions format, `[query, titles, descriptions, urls]`.
+/// A query which can't be parsed yet suggests nothing.
+#[utoipa::path(
+    get,
+    path = "/search/suggestions",
+    params(
+        ("q" = inline(Option<&str>), Query, description = "Search query language"),
+        ("key" = inline(Option<&str>), Query, description = "The API key, for browsers which can not set the `Authorization` header")
+    ),
+    responses(
+        (status = 200, description = "Suggestions of bookmarks", content_type = "application/x-suggestions+json", body = Vec<Object>)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/search/suggestions?<q>")]
+pub async fn suggest(
+    mut db: Connection<Db>,
+    _required: guards::QueryAuth,
+    q: Option<&str>,
+) -> (
+    ContentType,
+    Json<(String, Vec<String>, Vec<String>, Vec<String>)>,
+) {
+    let q = q.unwrap_or_default().trim();
+    let rv = if q.is_empty() {
+        vec![]
+    } else {
+        db::search_bookmarks(
+            &mut db,
+            Some(q),
+            None,
+            db::Sort::Frecency,
+            false,
+            0,
+            MAX_SUGGESTIONS,
+        )
+        .await
+        .unwrap_or_default()
+    };
+
+    let (mut titles, mut descriptions, mut urls) = (vec![], vec![], vec![]);
+    for (m, _, _) in rv {
+        titles.push(if m.title.is_empty() {
+            m.url.clone()
+        } else {
+            m.title
+        });
+        descriptions.push(m.description.unwrap_or_default());
+        urls.push(m.url);
+    }
+
+    (
+        ContentType::new("application", "x-suggestions+json"),
+        Json((q.to_string(), titles, descriptions, urls)),
+    )
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![get_description, search, suggest]
+}
+
+#[cfg(not(tarpaulin_include))]
+pub(crate) mod misc {
+    use super::*;
+
+    use utoipa::{OpenApi, Path};
+
+    pub struct ApiDoc;
+
+    impl OpenApi for ApiDoc {
+        fn openapi() -> utoipa::openapi::OpenApi {
+            use utoipa::openapi::{
+                InfoBuilder, OpenApiBuilder,
+                security::{ApiKey, ApiKeyValue, SecurityScheme},
+            };
+
+            let mut api = OpenApiBuilder::new()
+                .info(
+                    InfoBuilder::new()
+                        .title("OpenSearch API")
+                        .description(Some("Search bookmarks from the address bar of browsers"))
+                        .version("1.0")
+                        .build(),
+                )
+                .paths(bearmark_macro::utoipa_paths!(
+                    "",
+                    get_description,
+                    search,
+                    suggest
+                ))
+                .build();
+
+            api.components
+                .get_or_insert_with(Default::default)
+                .add_security_scheme(
+                    "api_key",
+                    SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Authorization"))),
+                );
+
+            api
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::configs;
+    use crate::db::bookmark::{NewBookmark, create_bookmark};
+    use crate::db::connection;
+    use crate::utils::rand::rand_str;
+
+    use rocket::fairing::AdHoc;
+    use rocket::http::{Header, Status};
+    use rocket::local::asynchronous;
+    use rocket_db_pools::Database;
+
+    fn test_app() -> rocket::Rocket<rocket::Build> {
+        rocket::custom(configs::config_provider())
+            .attach(Db::init())
+            .mount("/", routes())
+            .attach(AdHoc::config::<Config>())
+    }
+
+    async fn test_async_client() -> asynchronous::Client {
+        asynchronous::Client::tracked(test_app())
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[rocket::async_test]
+    async fn get_opensearch_description() {
+        let client = test_async_client().await;
+        let response = client
+            .get(uri!(get_description(key = Some("a&b"))))
+            .header(Header::new("Host", "bm.example.com"))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(
+            response.content_type(),
+            Some(ContentType::new("application", "opensearchdescription+xml"))
+        );
+        let body = response.into_string().await.unwrap();
+        assert!(
+            body.contains(
+                r#"template="http://bm.example.com/search?q={searchTerms}&amp;key=a%26b""#
+            )
+        );
+        assert!(body.contains(
+            r#"template="http://bm.example.com/search/suggestions?q={searchTerms}&amp;key=a%26b""#
+        ));
+    }
+
+    #[rocket::async_test]
+    async fn search_and_suggest() {
+        let mut conn = connection::establish().await;
+        let keyword = rand_str(10);
+        let mut added = vec![];
+        for (title, url) in [
+            (format!("{keyword} one"), "https://one.example.com/"),
+            (format!("{keyword} two"), "https://two.example.com/"),
+            (
+                format!("{keyword} three"),
+                "javascript:alert(document.cookie)",
+            ),
+        ] {
+            let m = create_bookmark(
+                &mut conn,
+                &NewBookmark {
+                    title,
+                    url: url.to_string(),
+                    notes: None,
+                    description: None,
+                    attributes: None,
+                },
+            )
+            .await;
+            added.push(m);
+        }
+        let client = test_async_client().await;
+
+        let q = format!("{keyword} one");
+        let response = client
+            .get(uri!(search(q = Some(&q), key = None::<&str>)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Found);
+        assert_eq!(
+            response.headers().get_one("Location"),
+            Some("https://one.example.com/")
+        );
+
+        let response = client
+            .get(uri!(search(q = Some(&keyword), key = None::<&str>)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::HTML));
+        assert_eq!(
+            response.headers().get_one("Content-Security-Policy"),
+            Some("default-src 'none'; form-action 'self'")
+        );
+        let page = response.into_string().await.unwrap();
+        assert!(page.contains(r#"<a href="https://one.example.com/">"#));
+        assert!(page.contains(r#"<a href="https://two.example.com/">"#));
+        assert!(page.contains(&format!("{keyword} three<br>")));
+        assert!(!page.contains(r#"href="javascript:"#));
+
+        let response = client
+            .get(uri!(suggest(q = Some(&keyword))))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let (query, titles, _, urls): (String, Vec<String>, Vec<String>, Vec<String>) =
+            response.into_json().await.unwrap();
+        assert_eq!(query, keyword);
+        assert_eq!(titles.len(), 3);
+        // the visited one first
+        assert_eq!(urls[0], "https://one.example.com/");
+
+        // an incomplete query suggests nothing
+        let response = client
+            .get(uri!(suggest(q = Some("(unclosed"))))
+            .dispatch()
+            .await;
+        let (_, titles, _, _): (String, Vec<String>, Vec<String>, Vec<String>) =
+            response.into_json().await.unwrap();
+        assert!(titles.is_empty());
+    }
+}
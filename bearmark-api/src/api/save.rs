@@ -0,0 +1,353 @@
+use super::bookmark::{CreateBookmark, create};
+use super::configs::Config;
+use super::errors::Error;
+use super::fairings::db::Db;
+use super::guards::{self, KEY_COOKIE};
+use super::opensearch::{BaseUrl, Page};
+use crate::db::bookmark;
+use crate::utils::html::{escape_html, render_link};
+
+use diesel_async::AsyncConnection;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use rocket::State;
+use rocket::http::{ContentType, Cookie, CookieJar, SameSite};
+use rocket_db_pools::Connection;
+
+/// Milliseconds the confirmation page stays open before closing itself.
+const CLOSE_DELAY_MS: u32 = 1500;
+
+/// The inline script of the page, which closes the page after a while.
+fn close_script() -> String {
+    format!("setTimeout(function () {{ window.close(); }}, {CLOSE_DELAY_MS});")
+}
+
+fn render_page(heading: &str, m: &bookmark::Bookmark) -> Page {
+    let title = if m.title.is_empty() { &m.url } else { &m.title };
+    let script = close_script();
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{heading} - Bearmark</title>
+</head>
+<body>
+<h1>{heading}</h1>
+<p>{link}</p>
+<script>{script}</script>
+</body>
+</html>
+"#,
+        heading = escape_html(heading),
+        link = render_link(&m.url, title),
+    );
+    Page::new(html, &[&script])
+}
+
+/// Save a bookmark from a bookmarklet, responding a page which closes itself
+///
+/// A URL already saved, compared by the canonical URL, is not saved again. The API key given by
+/// the `key` query parameter is remembered in a cookie, so the bookmarklet can go without it.
+#[utoipa::path(
+    get,
+    path = "/",
+    params(
+        ("url" = inline(Option<&str>), Query, description = "The URL to save"),
+        ("title" = inline(Option<&str>), Query, description = "The title, filled in from the page in the background if empty and the fetcher is enabled"),
+        ("tags" = inline(Option<&str>), Query, description = "Comma separated tags"),
+        ("folder" = inline(Option<&str>), Query, description = "The path of folder, missing folders are created"),
+        ("key" = inline(Option<&str>), Query, description = "The API key, for browsers which can not set the `Authorization` header")
+    ),
+    responses(
+        (status = 200, description = "The page confirming the bookmark is saved or already saved", content_type = "text/html", body = String),
+        (status = 400, description = "Missing or not HTTP(S) URL, or invalid folder")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/?<url>&<title>&<tags>&<folder>&<key>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn save_bookmark(
+    mut db: Connection<Db>,
+    _required: guards::QueryAuth,
+    config: &State<Config>,
+    cookies: &CookieJar<'_>,
+    url: Option<&str>,
+    title: Option<&str>,
+    tags: Option<&str>,
+    folder: Option<&str>,
+    key: Option<&str>,
+) -> Result<Page, Error> {
+    if let Some(key) = key.filter(|k| Some(*k) == config.api_key.as_deref()) {
+        cookies.add(
+            Cookie::build((KEY_COOKIE, key.to_string()))
+                .path("/")
+                .http_only(true)
+                .same_site(SameSite::Lax)
+                .permanent(),
+        );
+    }
+
+    let url = url.map(str::trim).unwrap_or_default();
+    if url.is_empty() {
+        return Err(Error::BadRequest("Missing URL".to_string()));
+    }
+    let existing = bookmark::find_bookmarks_by_urls(&mut db, &[url.to_string()], true).await;
+    if let Some(m) = existing.first() {
+        return Ok(render_page("Already saved", m));
+    }
+
+    let payload = CreateBookmark {
+        title: title.map(str::trim).unwrap_or_default().to_string(),
+        url: url.to_string(),
+        notes: None,
+        description: None,
+        folder_id: None,
+        folder: folder.map(str::to_string),
+        tags: tags
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect(),
+        attributes: None,
+        shortcut: None,
+    };
+    let (m, _, _) = db
+        .transaction::<_, Error, _>(|db| create(db, payload).scope_boxed())
+        .await?;
+
+    Ok(render_page("Saved", &m))
+}
+
+/// Quote a string in a JavaScript string literal.
+fn escape_js(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+/// Get the bookmarklet to save the current page of browsers
+///
+/// The API key is kept in the bookmarklet if it's given by the `key` query parameter, otherwise
+/// the bookmarklet relies on the cookie remembered by saving once with the key.
+#[utoipa::path(
+    get,
+    path = "/bookmarklet",
+    params(
+        ("key" = inline(Option<&str>), Query, description = "The API key, which is kept in the bookmarklet")
+    ),
+    responses(
+        (status = 200, description = "The `javascript:` URL of the bookmarklet", content_type = "text/plain", body = String)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/bookmarklet?<key>")]
+pub async fn get_bookmarklet(
+    _required: guards::QueryAuth,
+    base: BaseUrl,
+    key: Option<&str>,
+) -> (ContentType, String) {
+    let key = key
+        .map(|k| format!("key={}&", utf8_percent_encode(k, NON_ALPHANUMERIC)))
+        .unwrap_or_default();
+    let script = format!(
+        "javascript:(function(){{window.open('{base}/save?{key}url='+encodeURIComponent(location.href)\
+        +'&title='+encodeURIComponent(document.title),'bearmark','width=480,height=240');}})();",
+        base = escape_js(&base.0),
+    );
+
+    (ContentType::Plain, script)
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![save_bookmark, get_bookmarklet]
+}
+
+#[cfg(not(tarpaulin_include))]
+pub(crate) mod misc {
+    use super::*;
+
+    use utoipa::{OpenApi, Path};
+
+    pub struct ApiDoc;
+
+    impl OpenApi for ApiDoc {
+        fn openapi() -> utoipa::openapi::OpenApi {
+            use utoipa::openapi::{
+                InfoBuilder, OpenApiBuilder,
+                security::{ApiKey, ApiKeyValue, SecurityScheme},
+            };
+
+            let mut api = OpenApiBuilder::new()
+                .info(
+                    InfoBuilder::new()
+                        .title("Save API")
+                        .description(Some("Save bookmarks from browsers by a bookmarklet"))
+                        .version("1.0")
+                        .build(),
+                )
+                .paths(bearmark_macro::utoipa_paths!(
+                    "/save",
+                    save_bookmark,
+                    get_bookmarklet
+                ))
+                .build();
+
+            api.components
+                .get_or_insert_with(Default::default)
+                .add_security_scheme(
+                    "api_key",
+                    SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Authorization"))),
+                );
+
+            api
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::configs;
+    use crate::db::{connection, get_bookmark_details};
+    use crate::utils::rand::rand_str;
+
+    use rocket::fairing::AdHoc;
+    use rocket::figment::Figment;
+    use rocket::http::{Header, Status};
+    use rocket::local::asynchronous;
+    use rocket_db_pools::Database;
+
+    fn test_app(figment: Figment) -> rocket::Rocket<rocket::Build> {
+        rocket::custom(figment)
+            .attach(Db::init())
+            .mount("/save", routes())
+            .attach(AdHoc::config::<Config>())
+    }
+
+    async fn test_async_client(figment: Figment) -> asynchronous::Client {
+        asynchronous::Client::tracked(test_app(figment))
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[rocket::async_test]
+    async fn save_bookmarks() {
+        let mut conn = connection::establish().await;
+        let client = test_async_client(configs::config_provider()).await;
+        let url = format!("https://{}.example.com/", rand_str(10));
+        let folder = format!("/{}", rand_str(10));
+
+        let response = client
+            .get(uri!(
+                "/save",
+                save_bookmark(
+                    url = Some(&url),
+                    title = Some("Tom & Jerry"),
+                    tags = Some("rust, ,web"),
+                    folder = Some(&folder),
+                    key = None::<&str>
+                )
+            ))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::HTML));
+        assert_eq!(
+            response.headers().get_one("Content-Security-Policy"),
+            Some(crate::utils::html::content_security_policy(&[&close_script()]).as_str())
+        );
+        let page = response.into_string().await.unwrap();
+        assert!(page.contains("<h1>Saved</h1>"));
+        assert!(page.contains(&format!(r#"<a href="{url}">Tom &amp; Jerry</a>"#)));
+        assert!(page.contains("window.close()"));
+
+        let saved =
+            bookmark::find_bookmarks_by_urls(&mut conn, std::slice::from_ref(&url), false).await;
+        assert_eq!(saved.len(), 1);
+        let (m, f, tags) = get_bookmark_details(&mut conn, saved).await.remove(0);
+        assert_eq!(m.title, "Tom & Jerry");
+        assert_eq!(f.unwrap().path, folder);
+        let mut tags = tags.into_iter().map(|t| t.name).collect::<Vec<_>>();
+        tags.sort();
+        assert_eq!(tags, vec!["rust", "web"]);
+
+        // the same page with a tracking parameter is saved already
+        let response = client
+            .get(uri!(
+                "/save",
+                save_bookmark(
+                    url = Some(format!("{url}?utm_source=feed")),
+                    title = None::<&str>,
+                    tags = None::<&str>,
+                    folder = None::<&str>,
+                    key = None::<&str>
+                )
+            ))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let page = response.into_string().await.unwrap();
+        assert!(page.contains("<h1>Already saved</h1>"));
+        let saved = bookmark::find_bookmarks_by_urls(&mut conn, &[url], true).await;
+        assert_eq!(saved.len(), 1);
+
+        let response = client.get("/save").dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+        let response = client
+            .get(format!("/save?url=javascript:alert({})", rand_str(10)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[rocket::async_test]
+    async fn remember_key_in_cookie() {
+        let key = rand_str(32);
+        let client =
+            test_async_client(configs::config_provider().merge(("api_key", key.clone()))).await;
+
+        let response = client.get("/save").dispatch().await;
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        let response = client.get(format!("/save?key={key}")).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+        assert!(client.cookies().get(KEY_COOKIE).is_some());
+
+        // authenticated by the cookie
+        let url = format!("https://{}.example.com/", rand_str(10));
+        let response = client
+            .get(uri!(
+                "/save",
+                save_bookmark(
+                    url = Some(&url),
+                    title = None::<&str>,
+                    tags = None::<&str>,
+                    folder = None::<&str>,
+                    key = None::<&str>
+                )
+            ))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[rocket::async_test]
+    async fn generate_bookmarklet() {
+        let client = test_async_client(configs::config_provider()).await;
+        let response = client
+            .get(uri!("/save", get_bookmarklet(key = Some("a&b"))))
+            .header(Header::new("Host", "bm.example.com"))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let script = response.into_string().await.unwrap();
+        assert!(script.starts_with("javascript:"));
+        assert!(script.contains("'http://bm.example.com/save?key=a%26b&url='"));
+        assert!(script.contains("encodeURIComponent(location.href)"));
+    }
+}
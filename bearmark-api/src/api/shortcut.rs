@@ -0,0 +1,194 @@
+use super::errors::Error;
+use super::fairings::db::Db;
+use super::guards;
+use crate::db::{bookmark, shortcut};
+
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use rocket::response::Redirect;
+use rocket_db_pools::Connection;
+
+/// Characters to be encoded in the arguments, all but the unreserved ones of RFC 3986.
+const ARGUMENTS: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Expand `%s` in the URL template with the percent-encoded arguments.
+pub fn expand_template(template: &str, args: &str) -> String {
+    template.replace("%s", &utf8_percent_encode(args, ARGUMENTS).to_string())
+}
+
+/// Open a bookmark by its shortcut, redirecting to its URL expanded with the arguments
+#[utoipa::path(
+    get,
+    path = "/",
+    params(
+        ("q" = inline(&str), Query, description = "The shortcut followed by the arguments, e.g. `gh tokio`"),
+        ("key" = inline(Option<&str>), Query, description = "The API key, for browsers which can not set the `Authorization` header")
+    ),
+    responses(
+        (status = 302, description = "Redirect to the expanded URL of the bookmark"),
+        (status = 400, description = "Empty query"),
+        (status = 404, description = "Shortcut not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/?<q>")]
+pub async fn open_shortcut(
+    mut db: Connection<Db>,
+    _required: guards::QueryAuth,
+    q: Option<&str>,
+) -> Result<Redirect, Error> {
+    let q = q.unwrap_or_default().trim();
+    if q.is_empty() {
+        return Err(Error::BadRequest("Empty query".to_string()));
+    }
+    let (keyword, args) = q.split_once(char::is_whitespace).unwrap_or((q, ""));
+
+    let m = shortcut::get_by_shortcut(&mut db, keyword)
+        .await
+        .ok_or_else(|| Error::NotFound(format!("Shortcut not found: {keyword}")))?;
+    bookmark::visit_bookmark(&mut db, m.id).await;
+
+    Ok(Redirect::found(expand_template(&m.url, args.trim())))
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![open_shortcut]
+}
+
+#[cfg(not(tarpaulin_include))]
+pub(crate) mod misc {
+    use super::*;
+
+    use utoipa::{OpenApi, Path};
+
+    pub struct ApiDoc;
+
+    impl OpenApi for ApiDoc {
+        fn openapi() -> utoipa::openapi::OpenApi {
+            use utoipa::openapi::{
+                InfoBuilder, OpenApiBuilder,
+                security::{ApiKey, ApiKeyValue, SecurityScheme},
+            };
+
+            let mut api = OpenApiBuilder::new()
+                .info(
+                    InfoBuilder::new()
+                        .title("Shortcut API")
+                        .description(Some("Open bookmarks by keyword shortcuts"))
+                        .version("1.0")
+                        .build(),
+                )
+                .paths(bearmark_macro::utoipa_paths!(
+                    "/api/shortcut",
+                    open_shortcut
+                ))
+                .build();
+
+            api.components
+                .get_or_insert_with(Default::default)
+                .add_security_scheme(
+                    "api_key",
+                    SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Authorization"))),
+                );
+
+            api
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::configs::{self, Config};
+    use crate::db::bookmark::{NewBookmark, create_bookmark};
+    use crate::db::connection;
+    use crate::utils::rand::rand_str;
+
+    use rocket::fairing::AdHoc;
+    use rocket::http::Status;
+    use rocket::local::asynchronous;
+    use rocket_db_pools::Database;
+
+    fn test_app() -> rocket::Rocket<rocket::Build> {
+        rocket::custom(configs::config_provider())
+            .attach(Db::init())
+            .mount("/", routes())
+            .attach(AdHoc::config::<Config>())
+    }
+
+    async fn test_async_client() -> asynchronous::Client {
+        asynchronous::Client::tracked(test_app())
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[test]
+    fn test_expand_template() {
+        for (template, args, expected) in [
+            (
+                "https://github.com/search?q=%s",
+                "tokio rt",
+                "https://github.com/search?q=tokio%20rt",
+            ),
+            (
+                "https://docs.rs/%s",
+                "a&b=c/d~e",
+                "https://docs.rs/a%26b%3Dc%2Fd~e",
+            ),
+            (
+                "https://example.com/?q=%s",
+                "日本",
+                "https://example.com/?q=%E6%97%A5%E6%9C%AC",
+            ),
+            ("https://example.com/", "ignored", "https://example.com/"),
+            ("https://example.com/?q=%s", "", "https://example.com/?q="),
+        ] {
+            assert_eq!(expand_template(template, args), expected);
+        }
+    }
+
+    #[rocket::async_test]
+    async fn open_by_shortcut() {
+        let mut conn = connection::establish().await;
+        let keyword = rand_str(8);
+        let m = create_bookmark(
+            &mut conn,
+            &NewBookmark {
+                title: "GitHub search".to_string(),
+                url: "https://github.com/search?q=%s".to_string(),
+                notes: None,
+                description: None,
+                attributes: None,
+            },
+        )
+        .await;
+        shortcut::set_shortcut(&mut conn, m.id, Some(&keyword))
+            .await
+            .unwrap();
+        let client = test_async_client().await;
+
+        let q = format!("{keyword}  tokio rt");
+        let response = client.get(uri!(open_shortcut(Some(&q)))).dispatch().await;
+        assert_eq!(response.status(), Status::Found);
+        assert_eq!(
+            response.headers().get_one("Location"),
+            Some("https://github.com/search?q=tokio%20rt")
+        );
+        let visited = bookmark::Bookmark::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(visited.visit_count, 1);
+
+        let q = rand_str(8);
+        let response = client.get(uri!(open_shortcut(Some(&q)))).dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+        let response = client
+            .get(uri!(open_shortcut(None::<&str>)))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+}
@@ -1,8 +1,11 @@
+use super::conditional::{Preconditions, Tagged, etag, etag_of_many};
 use super::errors::Error;
 use super::fairings::db::Db;
 use crate::api::guards;
 use crate::db::tag::{self, Tag};
 
+use diesel_async::AsyncConnection;
+use diesel_async::scoped_futures::ScopedFutureExt;
 use rocket::serde::json::Json;
 use rocket_db_pools::Connection;
 
@@ -10,20 +13,21 @@ use rocket_db_pools::Connection;
 pub async fn search_tags(
     mut db: Connection<Db>,
     _required: guards::Auth,
+    conditions: Preconditions,
     q: Option<&str>,
     before: Option<i32>,
     limit: Option<i64>,
-) -> Json<Vec<Tag>> {
+) -> Tagged<Json<Vec<Tag>>> {
     let keywords = q.map(|q| vec![q.trim()]).unwrap_or_default();
-    Json(
-        tag::search_tags(
-            &mut db,
-            &keywords,
-            before.unwrap_or_default(),
-            limit.unwrap_or(10),
-        )
-        .await,
+    let tags = tag::search_tags(
+        &mut db,
+        &keywords,
+        before.unwrap_or_default(),
+        limit.unwrap_or(10),
     )
+    .await;
+    let etag = etag_of_many(tags.iter().map(|t| (t.id, t.updated_at)));
+    conditions.respond(Json(tags), etag)
 }
 
 #[delete("/<id>")]
@@ -44,17 +48,31 @@ pub async fn delete_tag(
 pub async fn update_tag(
     mut db: Connection<Db>,
     _required: guards::Auth,
+    conditions: Preconditions,
     id: i32,
     payload: Json<tag::ModifyTag>,
-) -> Result<Json<Tag>, Error> {
+) -> Result<Tagged<Json<Tag>>, Error> {
     let payload = payload.into_inner();
     if payload.name.is_none() {
         return Err(Error::BadRequest("No changes".to_string()));
     }
-    tag::update_tag(&mut db, id, payload)
-        .await
-        .ok_or_else(|| Error::NotFound("Tag not found".to_string()))
-        .map(Json)
+    let tag = db
+        .transaction::<_, Error, _>(|db| {
+            async move {
+                let tag = Tag::get_for_update(db, id)
+                    .await
+                    .ok_or_else(|| Error::NotFound("Tag not found".to_string()))?;
+                conditions.check(&etag(tag.updated_at))?;
+                tag::update_tag(db, id, payload)
+                    .await
+                    .ok_or_else(|| Error::NotFound("Tag not found".to_string()))
+            }
+            .scope_boxed()
+        })
+        .await?;
+    let etag = etag(tag.updated_at);
+
+    Ok(Tagged::new(Json(tag), etag))
 }
 
 pub fn routes() -> Vec<rocket::Route> {
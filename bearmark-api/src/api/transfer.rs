@@ -0,0 +1,287 @@
+use super::errors::Error;
+use super::fairings::db::Db;
+use super::guards;
+use crate::transfer::{self, Format, ImportReport, ItemError, netscape};
+
+use rocket::data::{Data, ToByteUnit};
+use rocket::http::{ContentType, Header};
+use rocket::serde::json::Json;
+use rocket_db_pools::Connection;
+
+/// Size of a file to import at most, in mebibytes.
+const MAX_IMPORT_MIB: u64 = 64;
+
+/// Import bookmarks from a file exported by browsers or other services
+///
+/// Folders are created with their ancestors, bookmarks whose canonical URLs are saved already
+/// are skipped. Entries which can't be imported are reported one by one, and the others are
+/// imported all together.
+#[utoipa::path(
+    post,
+    path = "/import",
+    params(
+        ("format" = inline(Format), Query, description = "The format of the file"),
+        ("dry_run" = inline(Option<bool>), Query, description = "Report what would be imported without writing anything")
+    ),
+    request_body(content = String, description = "The content of the file", content_type = "text/plain"),
+    responses(
+        (status = 200, description = "Bookmarks imported", body = ImportReport),
+        (status = 400, description = "The file is too large, or not in the format")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/import?<format>&<dry_run>", data = "<data>")]
+pub async fn import_bookmarks(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    format: Format,
+    dry_run: Option<bool>,
+    data: Data<'_>,
+) -> Result<Json<ImportReport>, Error> {
+    let content = data
+        .open(MAX_IMPORT_MIB.mebibytes())
+        .into_string()
+        .await
+        .map_err(|e| Error::BadRequest(format!("Invalid file: {e}")))?;
+    if !content.is_complete() {
+        return Err(Error::BadRequest(format!(
+            "File is larger than {MAX_IMPORT_MIB} MiB"
+        )));
+    }
+
+    let doc = format.read(&content).map_err(Error::BadRequest)?;
+    let report = transfer::import(&mut db, doc, dry_run.unwrap_or(false)).await;
+    Ok(Json(report))
+}
+
+/// A file to be downloaded.
+#[derive(Responder)]
+pub struct Attachment(String, ContentType, Header<'static>);
+
+/// Export all bookmarks and folders as a bookmark file, which browsers can import
+#[utoipa::path(
+    get,
+    path = "/export",
+    responses(
+        (status = 200, description = "The `NETSCAPE-Bookmark-file-1` HTML", content_type = "text/html", body = String)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/export")]
+pub async fn export_bookmarks(mut db: Connection<Db>, _required: guards::Auth) -> Attachment {
+    let doc = transfer::export(&mut db).await;
+    Attachment(
+        netscape::render(&doc),
+        ContentType::HTML,
+        Header::new(
+            "Content-Disposition",
+            "attachment; filename=\"bookmarks.html\"",
+        ),
+    )
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![import_bookmarks, export_bookmarks]
+}
+
+#[cfg(not(tarpaulin_include))]
+pub(crate) mod misc {
+    use super::*;
+
+    use utoipa::{OpenApi, Path};
+
+    pub struct ApiDoc;
+
+    impl OpenApi for ApiDoc {
+        fn openapi() -> utoipa::openapi::OpenApi {
+            use utoipa::openapi::{
+                InfoBuilder, OpenApiBuilder,
+                security::{ApiKey, ApiKeyValue, SecurityScheme},
+            };
+
+            let mut api = OpenApiBuilder::new()
+                .info(
+                    InfoBuilder::new()
+                        .title("Transfer API")
+                        .description(Some("Import and export bookmarks"))
+                        .version("1.0")
+                        .build(),
+                )
+                .paths(bearmark_macro::utoipa_paths!(
+                    "/api/transfer",
+                    import_bookmarks,
+                    export_bookmarks
+                ))
+                .components(Some(bearmark_macro::utoipa_components![
+                    Format,
+                    ImportReport,
+                    ItemError
+                ]))
+                .build();
+
+            api.components
+                .get_or_insert_with(Default::default)
+                .add_security_scheme(
+                    "api_key",
+                    SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Authorization"))),
+                );
+
+            api
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::configs::{self, Config};
+    use crate::utils::rand::rand_str;
+
+    use rocket::fairing::AdHoc;
+    use rocket::http::Status;
+    use rocket::local::asynchronous;
+    use rocket_db_pools::Database;
+
+    fn test_app() -> rocket::Rocket<rocket::Build> {
+        rocket::custom(configs::config_provider())
+            .attach(Db::init())
+            .mount("/", routes())
+            .attach(AdHoc::config::<Config>())
+    }
+
+    async fn test_async_client() -> asynchronous::Client {
+        asynchronous::Client::tracked(test_app())
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[rocket::async_test]
+    async fn import_and_export_bookmarks() {
+        let client = test_async_client().await;
+        let (folder, host) = (rand_str(10), rand_str(10));
+        let file = format!(
+            r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<DL><p>
+    <DT><H3>{folder}</H3>
+    <DL><p>
+        <DT><A HREF="https://{host}.example.com/" ADD_DATE="1577934245" TAGS="imported">Imported</A>
+        <DD>From a browser
+    </DL><p>
+    <DT><A>Broken</A>
+</DL><p>
+"#
+        );
+
+        let response = client
+            .post("/import?format=netscape&dry_run=true")
+            .body(&file)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let report = response.into_json::<ImportReport>().await.unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.total, 2);
+        assert_eq!(report.created, 1);
+        assert_eq!(report.folders_created, vec![format!("/{folder}")]);
+        assert_eq!(report.errors.len(), 1);
+
+        let response = client.get(uri!(export_bookmarks)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let exported = response.into_string().await.unwrap();
+        assert!(!exported.contains(&format!("https://{host}.example.com/")));
+
+        let response = client
+            .post("/import?format=netscape")
+            .body(&file)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let report = response.into_json::<ImportReport>().await.unwrap();
+        assert!(!report.dry_run);
+        assert_eq!(report.created, 1);
+
+        let response = client.get(uri!(export_bookmarks)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::HTML));
+        assert_eq!(
+            response.headers().get_one("Content-Disposition"),
+            Some("attachment; filename=\"bookmarks.html\"")
+        );
+        let exported = response.into_string().await.unwrap();
+        assert!(exported.starts_with("<!DOCTYPE NETSCAPE-Bookmark-file-1>"));
+        assert!(exported.contains(&format!("<DT><H3>{folder}</H3>")));
+        assert!(exported.contains(&format!(
+            r#"<DT><A HREF="https://{host}.example.com/" ADD_DATE="1577934245" TAGS="imported">Imported</A>"#
+        )));
+
+        let response = client
+            .post("/import?format=unknown")
+            .body(&file)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[rocket::async_test]
+    async fn import_from_services() {
+        let client = test_async_client().await;
+        let host = rand_str(10);
+
+        let response = client
+            .post("/import?format=pinboard&dry_run=true")
+            .body(format!(
+                r#"[{{"href":"https://{host}.example.com/","description":"Pinned","tags":"a b","time":"2020-01-02T03:04:05Z","toread":"yes"}}]"#
+            ))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let report = response.into_json::<ImportReport>().await.unwrap();
+        assert_eq!((report.total, report.created), (1, 1));
+
+        let response = client
+            .post("/import?format=raindrop")
+            .body(format!(
+                "id,title,note,excerpt,url,folder,tags,created\n\
+                1,Dropped,,,https://{host}.example.com/,{host},,2020-01-02T03:04:05.000Z\n\
+                2,Again,,,https://{host}.example.com/?utm_source=x,{host},,2020-01-02T03:04:05.000Z\n\
+                3,Broken,,,https://{host}.example.com/b,{host},,now\n"
+            ))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let report = response.into_json::<ImportReport>().await.unwrap();
+        assert_eq!(report.created, 1);
+        assert_eq!(report.duplicated, 1);
+        assert_eq!(report.folders_created, vec![format!("/{host}")]);
+        assert_eq!(
+            report.errors,
+            vec![ItemError {
+                index: 2,
+                url: None,
+                message: "Invalid timestamp: now".to_string(),
+            }]
+        );
+
+        // saved already
+        let response = client
+            .post("/import?format=pocket")
+            .body(format!(
+                "title,url,time_added,tags,status\nPocketed,https://{host}.example.com/,1577934245,,archive\n"
+            ))
+            .dispatch()
+            .await;
+        let report = response.into_json::<ImportReport>().await.unwrap();
+        assert_eq!((report.created, report.duplicated), (0, 1));
+
+        let response = client
+            .post("/import?format=pinboard")
+            .body("<html>")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+}
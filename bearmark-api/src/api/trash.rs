@@ -0,0 +1,261 @@
+use super::bookmark::Bookmark;
+use super::errors::Error;
+use super::fairings::db::Db;
+use super::guards;
+use crate::archive::Archiver;
+use crate::db::bookmark;
+
+use rocket::State;
+use rocket::serde::json::Json;
+use rocket_db_pools::Connection;
+use tracing::debug;
+
+/// Search bookmarks in the trash
+#[utoipa::path(
+    get,
+    path = "/",
+    params(
+        ("q" = inline(Option<&str>), Query, description = "Search query language"),
+        ("cwd" = inline(Option<&str>), Query, description = "The path of folder to search in"),
+        ("before" = inline(Option<i32>), Query, description = "The bookmark id to search before"),
+        ("limit" = inline(Option<i64>), Query, description = "The limit of search results")
+    ),
+    responses(
+        (status = 200, description = "Deleted bookmarks searched success", body = Vec<Bookmark>),
+        (status = 400, description = "Bad query request")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/?<q>&<cwd>&<before>&<limit>")]
+pub async fn search_deleted_bookmarks(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    q: Option<&str>,
+    cwd: Option<&str>,
+    before: Option<i32>,
+    limit: Option<i64>,
+) -> Result<Json<Vec<Bookmark>>, Error> {
+    let rv = crate::db::search_deleted_bookmarks(
+        &mut db,
+        q,
+        cwd,
+        before.unwrap_or_default(),
+        limit.unwrap_or(10),
+    )
+    .await?;
+    debug!(?rv, "search results");
+
+    Ok(Json(rv.into_iter().map(Bookmark::from).collect()))
+}
+
+/// Restore a bookmark from the trash
+#[utoipa::path(
+    put,
+    path = "/restore/{id}",
+    params(
+        ("id" = inline(i32), Path, description = "The bookmark id to be restored")
+    ),
+    responses(
+        (status = 200, description = "Bookmark restored success"),
+        (status = 400, description = "The shortcut of bookmark is taken by another one"),
+        (status = 404, description = "Bookmark not found in the trash")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[put("/restore/<id>")]
+pub async fn restore_bookmark(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    id: i32,
+) -> Result<&'static str, Error> {
+    let effected = bookmark::restore_bookmarks(&mut db, vec![id]).await? == 1;
+    if effected {
+        Ok("Restored")
+    } else {
+        Err(Error::NotFound("Bookmark not in the trash".to_string()))
+    }
+}
+
+/// Permanently delete a bookmark from the trash, along with its archived pages
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    params(
+        ("id" = inline(i32), Path, description = "The bookmark id to be purged")
+    ),
+    responses(
+        (status = 200, description = "Bookmark purged success"),
+        (status = 404, description = "Bookmark not found in the trash")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[delete("/<id>")]
+pub async fn purge_bookmark(
+    mut db: Connection<Db>,
+    _required: guards::Auth,
+    archiver: &State<Archiver>,
+    id: i32,
+) -> Result<&'static str, Error> {
+    let effected = archiver.purge_bookmarks(&mut db, vec![id]).await == 1;
+    if effected {
+        Ok("Purged")
+    } else {
+        Err(Error::NotFound("Bookmark not in the trash".to_string()))
+    }
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![search_deleted_bookmarks, restore_bookmark, purge_bookmark]
+}
+
+#[cfg(not(tarpaulin_include))]
+pub(crate) mod misc {
+    use super::*;
+
+    use utoipa::{OpenApi, Path};
+
+    pub struct ApiDoc;
+
+    impl OpenApi for ApiDoc {
+        fn openapi() -> utoipa::openapi::OpenApi {
+            use utoipa::openapi::{
+                InfoBuilder, OpenApiBuilder,
+                security::{ApiKey, ApiKeyValue, SecurityScheme},
+            };
+
+            let mut api = OpenApiBuilder::new()
+                .info(
+                    InfoBuilder::new()
+                        .title("Trash API")
+                        .description(Some("Trash API"))
+                        .version("1.0")
+                        .build(),
+                )
+                .paths(bearmark_macro::utoipa_paths!(
+                    "/api/trash",
+                    search_deleted_bookmarks,
+                    restore_bookmark,
+                    purge_bookmark
+                ))
+                .components(Some(bearmark_macro::utoipa_components![Bookmark]))
+                .build();
+
+            api.components.as_mut().unwrap().add_security_scheme(
+                "api_key",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Authorization"))),
+            );
+
+            api
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::configs::{self, ArchiveStorageKind, Config};
+    use crate::archive::test::test_archiver;
+    use crate::db::archive::{self, NewBookmarkArchive};
+    use crate::db::bookmark::test::create_rand_bookmark;
+    use crate::db::connection;
+    use crate::utils::rand::rand_str;
+
+    use rocket::fairing::AdHoc;
+    use rocket::http::Status;
+    use rocket::local::asynchronous;
+    use rocket_db_pools::Database;
+
+    fn test_app() -> rocket::Rocket<rocket::Build> {
+        rocket::custom(configs::config_provider())
+            .attach(Db::init())
+            .mount("/", routes())
+            .manage(test_archiver(ArchiveStorageKind::Postgres, 1024))
+            .attach(AdHoc::config::<Config>())
+    }
+
+    async fn test_async_client() -> asynchronous::Client {
+        asynchronous::Client::tracked(test_app())
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[rocket::async_test]
+    async fn restore_and_purge_bookmark() {
+        let mut conn = connection::establish().await;
+        let m = create_rand_bookmark(&mut conn).await;
+        let client = test_async_client().await;
+
+        macro_rules! search_trash {
+            () => {{
+                let res = client
+                    .get(uri!(super::search_deleted_bookmarks(
+                        q = Some(&m.title),
+                        cwd = _,
+                        before = _,
+                        limit = _
+                    )))
+                    .dispatch()
+                    .await;
+                assert_eq!(res.status(), Status::Ok);
+                res.into_json::<Vec<Bookmark>>().await.unwrap()
+            }};
+        }
+
+        assert!(search_trash!().is_empty());
+        let res = client
+            .put(uri!(super::restore_bookmark(m.id)))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::NotFound);
+
+        bookmark::delete_bookmarks(&mut conn, vec![m.id]).await;
+        let results = search_trash!();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].deleted_at.is_some());
+
+        let res = client
+            .put(uri!(super::restore_bookmark(m.id)))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
+        assert!(search_trash!().is_empty());
+
+        // purge a bookmark not in the trash
+        let res = client
+            .delete(uri!(super::purge_bookmark(m.id)))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::NotFound);
+
+        // the archived content goes along with the bookmark
+        let hash = format!("{:0>64}", rand_str(10).to_lowercase());
+        archive::put_blob(&mut conn, &hash, b"content").await;
+        archive::create_archive(
+            &mut conn,
+            &NewBookmarkArchive {
+                bookmark_id: m.id,
+                content_hash: &hash,
+                content_type: "text/html",
+                size: 7,
+                storage: ArchiveStorageKind::Postgres.as_str(),
+            },
+        )
+        .await;
+
+        bookmark::delete_bookmarks(&mut conn, vec![m.id]).await;
+        let res = client
+            .delete(uri!(super::purge_bookmark(m.id)))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
+        assert!(search_trash!().is_empty());
+        assert!(bookmark::Bookmark::get(&mut conn, m.id).await.is_none());
+        assert!(archive::get_blob(&mut conn, &hash).await.is_none());
+    }
+}
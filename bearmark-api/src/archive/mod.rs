@@ -0,0 +1,304 @@
+mod storage;
+
+pub use storage::{ArchiveStorage, Storage};
+
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use diesel_async::AsyncPgConnection as Connection;
+use itertools::Itertools;
+use scraper::{Html, Selector};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use url::Url;
+
+use crate::api::configs::{ArchiveConfig, ArchiveStorageKind, FetcherConfig};
+use crate::db::archive::{self, BookmarkArchive, NewBookmarkArchive};
+use crate::db::bookmark::{self, Bookmark};
+use crate::fetcher;
+use crate::utils::ArchiveError;
+
+/// Takes snapshots of the pages of bookmarks, and loads them back.
+pub struct Archiver {
+    client: fetcher::Client,
+    enabled: bool,
+    config: ArchiveConfig,
+}
+
+impl Archiver {
+    pub fn new(fetcher: &FetcherConfig, config: &ArchiveConfig) -> Self {
+        Self {
+            client: fetcher::client(fetcher),
+            enabled: fetcher.enabled,
+            config: config.clone(),
+        }
+    }
+
+    /// Whether to inline assets by default.
+    pub fn inline_assets(&self) -> bool {
+        self.config.inline_assets
+    }
+
+    /// Fetch the page of the bookmark, and store it into the configured storage.
+    pub async fn snapshot(
+        &self,
+        conn: &mut Connection,
+        bookmark: &Bookmark,
+        inline_assets: bool,
+    ) -> Result<BookmarkArchive, ArchiveError> {
+        if !self.enabled {
+            return Err(ArchiveError::Disabled);
+        }
+        let max_bytes = self.config.max_bytes;
+        let page = fetcher::fetch_page(&self.client, &bookmark.url, max_bytes + 1).await?;
+        if !page.is_success() {
+            return Err(ArchiveError::Status(page.status));
+        }
+        if page.body.len() > max_bytes {
+            return Err(ArchiveError::TooLarge(max_bytes));
+        }
+
+        let content_type = page
+            .content_type
+            .clone()
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        let content = if inline_assets && page.is_html() {
+            let budget = max_bytes - page.body.len();
+            self.inline(&page.text(), &page.url, budget)
+                .await
+                .into_bytes()
+        } else {
+            page.body
+        };
+        let hash = format!("{:x}", Sha256::digest(&content));
+
+        let kind = self.config.storage;
+        Storage::new(kind, &self.config)
+            .put(conn, &hash, &content)
+            .await?;
+        Ok(archive::create_archive(
+            conn,
+            &NewBookmarkArchive {
+                bookmark_id: bookmark.id,
+                content_hash: &hash,
+                content_type: &content_type,
+                size: content.len() as i32,
+                storage: kind.as_str(),
+            },
+        )
+        .await)
+    }
+
+    /// The storage the archive is saved in.
+    fn storage_of(&self, archive: &BookmarkArchive) -> Storage {
+        let kind = if archive.storage == ArchiveStorageKind::Postgres.as_str() {
+            ArchiveStorageKind::Postgres
+        } else {
+            ArchiveStorageKind::Local
+        };
+        Storage::new(kind, &self.config)
+    }
+
+    /// Load the content of the archive from the storage it is saved in.
+    pub async fn load(
+        &self,
+        conn: &mut Connection,
+        archive: &BookmarkArchive,
+    ) -> Result<Vec<u8>, ArchiveError> {
+        self.storage_of(archive)
+            .get(conn, &archive.content_hash)
+            .await?
+            .ok_or_else(|| ArchiveError::Missing(archive.storage.clone()))
+    }
+
+    /// Permanently delete bookmarks from the trash, along with the contents of their archives
+    /// which no other archives refer to. Returns the number of purged bookmarks.
+    pub async fn purge_bookmarks(&self, conn: &mut Connection, ids: Vec<i32>) -> usize {
+        let archives = archive::get_archives(conn, &ids).await;
+        let count = bookmark::purge_bookmarks(conn, ids).await;
+
+        for archive in archives
+            .into_iter()
+            .unique_by(|a| (a.storage.clone(), a.content_hash.clone()))
+        {
+            if archive::is_content_referenced(conn, &archive.content_hash, &archive.storage).await {
+                continue;
+            }
+            if let Err(e) = self
+                .storage_of(&archive)
+                .delete(conn, &archive.content_hash)
+                .await
+            {
+                warn!(hash = archive.content_hash, %e, "Failed to delete archive content");
+            }
+        }
+        count
+    }
+
+    /// Replace URLs of images, scripts and stylesheets in the HTML with data URLs,
+    /// as many as the budget of bytes allows.
+    async fn inline(&self, html: &str, base: &Url, mut budget: usize) -> String {
+        let sources = {
+            let doc = Html::parse_document(html);
+            let selector = Selector::parse(
+                "img[src], script[src], link[rel~=stylesheet][href], link[rel~=icon][href]",
+            )
+            .unwrap();
+            doc.select(&selector)
+                .filter_map(|el| el.value().attr("src").or(el.value().attr("href")))
+                .filter(|src| !src.starts_with("data:"))
+                .map(|src| src.to_string())
+                .unique()
+                .collect_vec()
+        };
+
+        let mut html = html.to_string();
+        for src in sources {
+            let Ok(url) = base.join(&src) else {
+                continue;
+            };
+            let Ok(asset) = fetcher::fetch_page(&self.client, url.as_str(), budget + 1).await
+            else {
+                continue;
+            };
+            if !asset.is_success() || asset.body.len() > budget {
+                continue;
+            }
+            let data = format!(
+                "data:{};base64,{}",
+                asset
+                    .content_type
+                    .as_deref()
+                    .unwrap_or("application/octet-stream"),
+                BASE64_STANDARD.encode(&asset.body)
+            );
+            if data.len() > budget {
+                continue;
+            }
+            budget -= data.len();
+            for quote in ['"', '\''] {
+                html = html.replace(
+                    &format!("{quote}{src}{quote}"),
+                    &format!("{quote}{data}{quote}"),
+                );
+            }
+        }
+        html
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::db::bookmark::{NewBookmark, create_bookmark};
+    use crate::db::connection;
+    use crate::fetcher::test::{Fixture, serve};
+    use crate::utils::FetchError;
+    use crate::utils::rand::rand_str;
+
+    pub fn test_archiver(storage: ArchiveStorageKind, max_bytes: usize) -> Archiver {
+        Archiver::new(
+            &FetcherConfig {
+                enabled: true,
+                allow_private_addresses: true,
+                ..Default::default()
+            },
+            &ArchiveConfig {
+                storage,
+                path: std::env::temp_dir()
+                    .join(format!("bearmark-{}", rand_str(10)))
+                    .to_string_lossy()
+                    .into_owned(),
+                max_bytes,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn snapshot_pages() {
+        let mut conn = connection::establish().await;
+        let base = serve(vec![
+            (
+                "/page",
+                Fixture::html(r#"<link rel="stylesheet" href="/style.css"><img src='logo.png'>"#),
+            ),
+            (
+                "/style.css",
+                Fixture {
+                    headers: vec![("Content-Type", "text/css".to_string())],
+                    ..Fixture::html("body{}")
+                },
+            ),
+            ("/gone", Fixture::status(410)),
+        ])
+        .await;
+
+        let mut added = vec![];
+        for path in ["/page", "/gone"] {
+            added.push(
+                create_bookmark(
+                    &mut conn,
+                    &NewBookmark {
+                        title: "archived".to_string(),
+                        url: format!("{base}{path}"),
+                        notes: None,
+                        description: None,
+                        attributes: None,
+                    },
+                )
+                .await,
+            );
+        }
+
+        for storage in [ArchiveStorageKind::Local, ArchiveStorageKind::Postgres] {
+            let archiver = test_archiver(storage, 1024);
+            let archived = archiver
+                .snapshot(&mut conn, &added[0], false)
+                .await
+                .unwrap();
+            assert_eq!(archived.storage, storage.as_str());
+            assert!(archived.content_type.starts_with("text/html"));
+            let content = archiver.load(&mut conn, &archived).await.unwrap();
+            assert_eq!(archived.size as usize, content.len());
+            assert_eq!(
+                archived.content_hash,
+                format!("{:x}", Sha256::digest(&content))
+            );
+            assert!(String::from_utf8(content).unwrap().contains("/style.css"));
+        }
+
+        let archiver = test_archiver(ArchiveStorageKind::Local, 1024);
+        let archived = archiver.snapshot(&mut conn, &added[0], true).await.unwrap();
+        let content =
+            String::from_utf8(archiver.load(&mut conn, &archived).await.unwrap()).unwrap();
+        assert!(content.contains(&format!(
+            r#"href="data:text/css;base64,{}""#,
+            BASE64_STANDARD.encode("body{}")
+        )));
+        // missing assets are kept as they are
+        assert!(content.contains("src='logo.png'"));
+
+        let rv = archiver.snapshot(&mut conn, &added[1], false).await;
+        assert!(matches!(rv, Err(ArchiveError::Status(410))));
+
+        let archiver = test_archiver(ArchiveStorageKind::Local, 10);
+        let rv = archiver.snapshot(&mut conn, &added[0], false).await;
+        assert!(matches!(rv, Err(ArchiveError::TooLarge(10))));
+
+        let archiver = Archiver::new(&FetcherConfig::default(), &ArchiveConfig::default());
+        let rv = archiver.snapshot(&mut conn, &added[0], false).await;
+        assert!(matches!(rv, Err(ArchiveError::Disabled)));
+        let archiver = Archiver::new(
+            &FetcherConfig {
+                enabled: true,
+                ..Default::default()
+            },
+            &ArchiveConfig::default(),
+        );
+        let rv = archiver.snapshot(&mut conn, &added[0], false).await;
+        assert!(matches!(
+            rv,
+            Err(ArchiveError::Fetch(FetchError::PrivateAddress(_)))
+        ));
+    }
+}
@@ -0,0 +1,188 @@
+use std::path::PathBuf;
+
+use diesel_async::AsyncPgConnection as Connection;
+
+use crate::api::configs::{ArchiveConfig, ArchiveStorageKind};
+use crate::db::archive;
+use crate::utils::ArchiveError;
+
+/// Content-addressed storage of archives.
+pub trait ArchiveStorage {
+    /// Store the content by its hash, keeping the existing one.
+    async fn put(
+        &self,
+        conn: &mut Connection,
+        hash: &str,
+        content: &[u8],
+    ) -> Result<(), ArchiveError>;
+
+    async fn get(&self, conn: &mut Connection, hash: &str)
+    -> Result<Option<Vec<u8>>, ArchiveError>;
+
+    /// Delete the content by its hash, if it exists.
+    async fn delete(&self, conn: &mut Connection, hash: &str) -> Result<(), ArchiveError>;
+}
+
+/// Stores archives as files in a directory, the default storage.
+pub struct LocalStorage {
+    pub root: PathBuf,
+}
+
+impl LocalStorage {
+    fn path_of(&self, hash: &str) -> PathBuf {
+        // shard by the hash prefix to keep directories small
+        self.root.join(&hash[..2]).join(hash)
+    }
+}
+
+impl ArchiveStorage for LocalStorage {
+    async fn put(
+        &self,
+        _conn: &mut Connection,
+        hash: &str,
+        content: &[u8],
+    ) -> Result<(), ArchiveError> {
+        let path = self.path_of(hash);
+        if tokio::fs::try_exists(&path).await? {
+            return Ok(());
+        }
+        tokio::fs::create_dir_all(path.parent().unwrap()).await?;
+        // write to a temporary file first, so a partial file is never visible
+        let tmp = path.with_extension(format!("tmp-{}", std::process::id()));
+        tokio::fs::write(&tmp, content).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    async fn get(
+        &self,
+        _conn: &mut Connection,
+        hash: &str,
+    ) -> Result<Option<Vec<u8>>, ArchiveError> {
+        match tokio::fs::read(self.path_of(hash)).await {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, _conn: &mut Connection, hash: &str) -> Result<(), ArchiveError> {
+        match tokio::fs::remove_file(self.path_of(hash)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Stores archives in the `archive_blobs` table.
+pub struct PostgresStorage;
+
+impl ArchiveStorage for PostgresStorage {
+    async fn put(
+        &self,
+        conn: &mut Connection,
+        hash: &str,
+        content: &[u8],
+    ) -> Result<(), ArchiveError> {
+        archive::put_blob(conn, hash, content).await;
+        Ok(())
+    }
+
+    async fn get(
+        &self,
+        conn: &mut Connection,
+        hash: &str,
+    ) -> Result<Option<Vec<u8>>, ArchiveError> {
+        Ok(archive::get_blob(conn, hash).await)
+    }
+
+    async fn delete(&self, conn: &mut Connection, hash: &str) -> Result<(), ArchiveError> {
+        archive::delete_blob(conn, hash).await;
+        Ok(())
+    }
+}
+
+pub enum Storage {
+    Local(LocalStorage),
+    Postgres(PostgresStorage),
+}
+
+impl Storage {
+    pub fn new(kind: ArchiveStorageKind, config: &ArchiveConfig) -> Self {
+        match kind {
+            ArchiveStorageKind::Local => Storage::Local(LocalStorage {
+                root: PathBuf::from(&config.path),
+            }),
+            ArchiveStorageKind::Postgres => Storage::Postgres(PostgresStorage),
+        }
+    }
+}
+
+impl ArchiveStorage for Storage {
+    async fn put(
+        &self,
+        conn: &mut Connection,
+        hash: &str,
+        content: &[u8],
+    ) -> Result<(), ArchiveError> {
+        match self {
+            Storage::Local(s) => s.put(conn, hash, content).await,
+            Storage::Postgres(s) => s.put(conn, hash, content).await,
+        }
+    }
+
+    async fn get(
+        &self,
+        conn: &mut Connection,
+        hash: &str,
+    ) -> Result<Option<Vec<u8>>, ArchiveError> {
+        match self {
+            Storage::Local(s) => s.get(conn, hash).await,
+            Storage::Postgres(s) => s.get(conn, hash).await,
+        }
+    }
+
+    async fn delete(&self, conn: &mut Connection, hash: &str) -> Result<(), ArchiveError> {
+        match self {
+            Storage::Local(s) => s.delete(conn, hash).await,
+            Storage::Postgres(s) => s.delete(conn, hash).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::connection;
+    use crate::utils::rand::rand_str;
+
+    #[tokio::test]
+    async fn put_and_get_contents() {
+        let mut conn = connection::establish().await;
+        let config = ArchiveConfig {
+            path: std::env::temp_dir()
+                .join(format!("bearmark-{}", rand_str(10)))
+                .to_string_lossy()
+                .into_owned(),
+            ..Default::default()
+        };
+
+        for kind in [ArchiveStorageKind::Local, ArchiveStorageKind::Postgres] {
+            let storage = Storage::new(kind, &config);
+            let hash = format!("{:0>64}", rand_str(10).to_lowercase());
+            assert_eq!(storage.get(&mut conn, &hash).await.unwrap(), None);
+
+            storage.put(&mut conn, &hash, b"content").await.unwrap();
+            storage.put(&mut conn, &hash, b"ignored").await.unwrap();
+            assert_eq!(
+                storage.get(&mut conn, &hash).await.unwrap().as_deref(),
+                Some(&b"content"[..])
+            );
+
+            storage.delete(&mut conn, &hash).await.unwrap();
+            assert_eq!(storage.get(&mut conn, &hash).await.unwrap(), None);
+            // deleted already
+            storage.delete(&mut conn, &hash).await.unwrap();
+        }
+    }
+}
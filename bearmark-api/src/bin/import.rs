@@ -0,0 +1,87 @@
+use std::process::ExitCode;
+
+use bearmark_api::transfer::Format;
+
+const USAGE: &str = "Usage: import [--format netscape|pinboard|pocket|raindrop] [--dry-run] <FILE>";
+
+struct Args {
+    format: Format,
+    dry_run: bool,
+    path: String,
+}
+
+/// Parse the arguments, or get the message to exit with.
+fn parse_args() -> Result<Args, String> {
+    let (mut format, mut dry_run, mut path) = (Format::Netscape, false, None);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--format" => {
+                format = args
+                    .next()
+                    .ok_or_else(|| USAGE.to_string())?
+                    .parse()
+                    .map_err(|e| format!("{e}\n{USAGE}"))?;
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.ok_or_else(|| USAGE.to_string())?;
+    Ok(Args {
+        format,
+        dry_run,
+        path,
+    })
+}
+
+#[cfg(not(tarpaulin_include))]
+#[rocket::main]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{msg}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let content = match std::fs::read_to_string(&args.path) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("Error reading {}: {e}", args.path);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let report = match bearmark_api::import(args.format, &content, args.dry_run).await {
+        Ok(report) => report,
+        Err(msg) => {
+            eprintln!("Error reading {}: {msg}", args.path);
+            return ExitCode::FAILURE;
+        }
+    };
+    if report.dry_run {
+        println!("Dry run, nothing is written.");
+    }
+    println!(
+        "{} entries: {} created, {} duplicated, {} failed",
+        report.total,
+        report.created,
+        report.duplicated,
+        report.errors.len()
+    );
+    for path in &report.folders_created {
+        println!("Folder created: {path}");
+    }
+    for e in &report.errors {
+        println!(
+            "Entry #{} {}: {}",
+            e.index,
+            e.url.as_deref().unwrap_or("-"),
+            e.message
+        );
+    }
+
+    ExitCode::SUCCESS
+}
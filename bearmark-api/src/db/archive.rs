@@ -0,0 +1,128 @@
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection as Connection, RunQueryDsl};
+use rocket::serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::bookmark::Bookmark;
+use super::schema::{archive_blobs, bookmark_archives};
+
+#[derive(
+    Queryable,
+    Selectable,
+    Identifiable,
+    Associations,
+    Debug,
+    Clone,
+    Deserialize,
+    Serialize,
+    ToSchema,
+)]
+#[diesel(belongs_to(Bookmark))]
+#[diesel(table_name = bookmark_archives)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BookmarkArchive {
+    pub id: i32,
+    pub bookmark_id: i32,
+    /// SHA-256 of the content in hex
+    pub content_hash: String,
+    pub content_type: String,
+    pub size: i32,
+    /// The storage of the content, `local` or `postgres`
+    pub storage: String,
+    #[schema(format = DateTime, value_type=String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: time::OffsetDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = bookmark_archives)]
+pub struct NewBookmarkArchive<'a> {
+    pub bookmark_id: i32,
+    pub content_hash: &'a str,
+    pub content_type: &'a str,
+    pub size: i32,
+    pub storage: &'a str,
+}
+
+pub async fn create_archive(
+    conn: &mut Connection,
+    new: &NewBookmarkArchive<'_>,
+) -> BookmarkArchive {
+    diesel::insert_into(bookmark_archives::table)
+        .values(new)
+        .returning(BookmarkArchive::as_returning())
+        .get_result(conn)
+        .await
+        .expect("Error saving new archive")
+}
+
+/// Get the latest archive of the bookmark.
+pub async fn get_latest_archive(
+    conn: &mut Connection,
+    bookmark_id: i32,
+) -> Option<BookmarkArchive> {
+    bookmark_archives::table
+        .filter(bookmark_archives::bookmark_id.eq(bookmark_id))
+        .order_by(bookmark_archives::id.desc())
+        .first(conn)
+        .await
+        .optional()
+        .expect("Error loading archive")
+}
+
+/// Get all archives of the bookmarks.
+pub async fn get_archives(conn: &mut Connection, bookmark_ids: &[i32]) -> Vec<BookmarkArchive> {
+    bookmark_archives::table
+        .filter(bookmark_archives::bookmark_id.eq_any(bookmark_ids))
+        .load(conn)
+        .await
+        .expect("Error loading archives")
+}
+
+/// Whether any archive refers to the content in the storage.
+pub async fn is_content_referenced(
+    conn: &mut Connection,
+    content_hash: &str,
+    storage: &str,
+) -> bool {
+    diesel::select(diesel::dsl::exists(
+        bookmark_archives::table.filter(
+            bookmark_archives::content_hash
+                .eq(content_hash)
+                .and(bookmark_archives::storage.eq(storage)),
+        ),
+    ))
+    .get_result(conn)
+    .await
+    .expect("Error checking references of archive content")
+}
+
+/// Save the content by its hash, keeping the existing one.
+pub async fn put_blob(conn: &mut Connection, content_hash: &str, content: &[u8]) {
+    diesel::insert_into(archive_blobs::table)
+        .values((
+            archive_blobs::content_hash.eq(content_hash),
+            archive_blobs::content.eq(content),
+        ))
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .await
+        .expect("Error saving archive blob");
+}
+
+pub async fn get_blob(conn: &mut Connection, content_hash: &str) -> Option<Vec<u8>> {
+    archive_blobs::table
+        .find(content_hash)
+        .select(archive_blobs::content)
+        .first(conn)
+        .await
+        .optional()
+        .expect("Error loading archive blob")
+}
+
+pub async fn delete_blob(conn: &mut Connection, content_hash: &str) {
+    diesel::delete(archive_blobs::table.find(content_hash))
+        .execute(conn)
+        .await
+        .expect("Error deleting archive blob");
+}
@@ -3,6 +3,8 @@ use diesel_async::{AsyncPgConnection as Connection, RunQueryDsl};
 use rocket::serde::{Deserialize, Serialize};
 
 use super::schema::bookmarks;
+use crate::utils::DatabaseError;
+use crate::utils::canonical_url::{canonicalize, canonicalize_with};
 
 #[derive(
     Queryable,
@@ -30,6 +32,29 @@ pub struct Bookmark {
     #[serde(with = "time::serde::rfc3339")]
     pub updated_at: time::OffsetDateTime,
     pub folder_id: Option<i32>,
+    pub canonical_url: String,
+    pub notes: Option<String>,
+    pub description: Option<String>,
+    pub visit_count: i32,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_visited_at: Option<time::OffsetDateTime>,
+    pub pinned: bool,
+    /// The manual order of pinned bookmarks, ascending
+    pub pin_order: Option<i32>,
+    /// One of [`super::reading::ReadingState`], or null if not to be read later
+    pub reading_state: Option<String>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub reading_state_changed_at: Option<time::OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub queued_at: Option<time::OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub read_at: Option<time::OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub remind_at: Option<time::OffsetDateTime>,
+    /// Custom key/value metadata, always an object
+    pub attributes: serde_json::Value,
+    /// A unique keyword to open the bookmark, whose URL may be a template with `%s`
+    pub shortcut: Option<String>,
 }
 
 #[derive(Insertable, AsChangeset, Deserialize, Serialize, Debug, Clone)]
@@ -38,6 +63,10 @@ pub struct Bookmark {
 pub struct NewBookmark {
     pub title: String,
     pub url: String,
+    pub notes: Option<String>,
+    pub description: Option<String>,
+    /// An object, or `None` to be empty
+    pub attributes: Option<serde_json::Value>,
 }
 
 #[derive(AsChangeset, Deserialize, Serialize, Debug)]
@@ -46,6 +75,13 @@ pub struct NewBookmark {
 pub struct ModifyBookmark {
     pub title: Option<String>,
     pub url: Option<String>,
+    pub notes: Option<String>,
+    pub description: Option<String>,
+    pub pinned: Option<bool>,
+    pub pin_order: Option<Option<i32>>,
+    pub remind_at: Option<Option<time::OffsetDateTime>>,
+    /// Replace all the attributes with the object
+    pub attributes: Option<serde_json::Value>,
 }
 
 impl Bookmark {
@@ -57,11 +93,44 @@ impl Bookmark {
             .optional()
             .expect("Error loading bookmark")
     }
+
+    /// Get the bookmark, locking it until the end of the transaction.
+    pub async fn get_for_update(conn: &mut Connection, id: i32) -> Option<Bookmark> {
+        bookmarks::table
+            .find(id)
+            .for_update()
+            .first(conn)
+            .await
+            .optional()
+            .expect("Error loading bookmark")
+    }
+
+    /// Get bookmarks which are not deleted, in the order of given ids.
+    pub async fn get_many(conn: &mut Connection, ids: &[i32]) -> Vec<Bookmark> {
+        let mut bookmarks = bookmarks::table
+            .filter(
+                bookmarks::id
+                    .eq_any(ids)
+                    .and(bookmarks::deleted_at.is_null()),
+            )
+            .load::<Bookmark>(conn)
+            .await
+            .expect("Error loading bookmarks");
+        let mut positions = std::collections::HashMap::with_capacity(ids.len());
+        for (i, id) in ids.iter().enumerate() {
+            positions.entry(*id).or_insert(i);
+        }
+        bookmarks.sort_by_key(|b| positions.get(&b.id).copied());
+        bookmarks
+    }
 }
 
 pub async fn create_bookmark(conn: &mut Connection, new_bookmark: &NewBookmark) -> Bookmark {
     diesel::insert_into(bookmarks::table)
-        .values(new_bookmark)
+        .values((
+            new_bookmark,
+            bookmarks::canonical_url.eq(canonicalize(&new_bookmark.url)),
+        ))
         .returning(Bookmark::as_returning())
         .get_result(conn)
         .await
@@ -74,8 +143,12 @@ pub async fn update_bookmark(
     modified: ModifyBookmark,
 ) -> Option<Bookmark> {
     use diesel::{ExpressionMethods, dsl::now};
+    let canonical_url = modified
+        .url
+        .as_deref()
+        .map(|url| bookmarks::canonical_url.eq(canonicalize(url)));
     diesel::update(bookmarks::table.find(id))
-        .set((&modified, bookmarks::updated_at.eq(now)))
+        .set((&modified, canonical_url, bookmarks::updated_at.eq(now)))
         .returning(Bookmark::as_returning())
         .get_result(conn)
         .await
@@ -83,6 +156,177 @@ pub async fn update_bookmark(
         .expect("Error updating bookmark")
 }
 
+/// Canonicalize the URLs of all bookmarks again with the tracking parameters, if they have
+/// changed since the last time or the bookmarks were never canonicalized, e.g. saved before
+/// canonicalizing. Returns the number of updated bookmarks.
+pub async fn recanonicalize_bookmarks(conn: &mut Connection, tracking_params: &[String]) -> usize {
+    use diesel::sql_types::{Array, Integer, Text};
+    use diesel_async::AsyncConnection;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+
+    use super::schema::canonical_url_params;
+
+    const BATCH_SIZE: i64 = 1000;
+
+    let mut params = tracking_params.to_vec();
+    params.sort();
+    params.dedup();
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        async move {
+            let canonicalized = canonical_url_params::table
+                .select(canonical_url_params::tracking_params)
+                .for_update()
+                .first::<Vec<Option<String>>>(conn)
+                .await
+                .optional()?;
+            if canonicalized.is_some_and(|p| p.into_iter().flatten().eq(params.iter().cloned())) {
+                return Ok(0);
+            }
+
+            let mut updated = 0;
+            let mut last_id = 0;
+            loop {
+                let rows = bookmarks::table
+                    .select((bookmarks::id, bookmarks::url, bookmarks::canonical_url))
+                    .filter(bookmarks::id.gt(last_id))
+                    .order(bookmarks::id)
+                    .limit(BATCH_SIZE)
+                    .load::<(i32, String, String)>(conn)
+                    .await?;
+                let Some((id, _, _)) = rows.last() else {
+                    break;
+                };
+                last_id = *id;
+
+                let (ids, urls): (Vec<i32>, Vec<String>) = rows
+                    .into_iter()
+                    .filter_map(|(id, url, canonical_url)| {
+                        let canonicalized = canonicalize_with(&url, &params);
+                        (canonicalized != canonical_url).then_some((id, canonicalized))
+                    })
+                    .unzip();
+                if ids.is_empty() {
+                    continue;
+                }
+                updated += diesel::sql_query(
+                    "UPDATE bookmarks SET canonical_url = c.url \
+                     FROM unnest($1, $2) AS c(id, url) WHERE bookmarks.id = c.id",
+                )
+                .bind::<Array<Integer>, _>(ids)
+                .bind::<Array<Text>, _>(urls)
+                .execute(conn)
+                .await?;
+            }
+
+            diesel::insert_into(canonical_url_params::table)
+                .values(canonical_url_params::tracking_params.eq(&params))
+                .on_conflict(canonical_url_params::id)
+                .do_update()
+                .set((
+                    canonical_url_params::tracking_params.eq(&params),
+                    canonical_url_params::updated_at.eq(diesel::dsl::now),
+                ))
+                .execute(conn)
+                .await?;
+            Ok(updated)
+        }
+        .scope_boxed()
+    })
+    .await
+    .expect("Error canonicalizing bookmark URLs")
+}
+
+/// Fill in the title and description of the bookmark, each only if it's still empty, so the
+/// ones edited meanwhile are kept.
+pub async fn fill_in_bookmark(
+    conn: &mut Connection,
+    id: i32,
+    title: Option<&str>,
+    description: Option<&str>,
+) {
+    use diesel::dsl::{now, sql};
+    use diesel::sql_types::Bool;
+
+    if let Some(title) = title {
+        diesel::update(bookmarks::table.find(id))
+            .filter(sql::<Bool>("trim(title) = ''"))
+            .set((bookmarks::title.eq(title), bookmarks::updated_at.eq(now)))
+            .execute(conn)
+            .await
+            .expect("Error filling in title of bookmark");
+    }
+    if let Some(description) = description {
+        diesel::update(bookmarks::table.find(id))
+            .filter(bookmarks::description.is_null())
+            .set((
+                bookmarks::description.eq(description),
+                bookmarks::updated_at.eq(now),
+            ))
+            .execute(conn)
+            .await
+            .expect("Error filling in description of bookmark");
+    }
+}
+
+/// Set when the bookmark was created, for the ones imported from elsewhere. If it's queued to
+/// read later already, it's regarded as queued since then as well.
+pub async fn backdate_bookmark(
+    conn: &mut Connection,
+    id: i32,
+    created_at: time::OffsetDateTime,
+) -> Option<Bookmark> {
+    diesel::update(bookmarks::table.find(id))
+        .filter(bookmarks::queued_at.is_not_null())
+        .set(bookmarks::queued_at.eq(created_at))
+        .execute(conn)
+        .await
+        .expect("Error backdating bookmark");
+    diesel::update(bookmarks::table.find(id))
+        .set(bookmarks::created_at.eq(created_at))
+        .returning(Bookmark::as_returning())
+        .get_result(conn)
+        .await
+        .optional()
+        .expect("Error backdating bookmark")
+}
+
+/// Record a visit of the bookmark which is not deleted. A visit is not a modification, so
+/// `updated_at` is kept.
+pub async fn visit_bookmark(conn: &mut Connection, id: i32) -> Option<Bookmark> {
+    use diesel::dsl::now;
+
+    diesel::update(
+        bookmarks::table
+            .find(id)
+            .filter(bookmarks::deleted_at.is_null()),
+    )
+    .set((
+        bookmarks::visit_count.eq(bookmarks::visit_count + 1),
+        bookmarks::last_visited_at.eq(now),
+    ))
+    .returning(Bookmark::as_returning())
+    .get_result(conn)
+    .await
+    .optional()
+    .expect("Error visiting bookmark")
+}
+
+/// Bump `updated_at` of bookmarks whose tags or folder are changed.
+pub async fn touch_bookmarks(conn: &mut Connection, ids: &[i32]) -> usize {
+    use diesel::dsl::now;
+
+    if ids.is_empty() {
+        return 0;
+    }
+    diesel::update(bookmarks::table)
+        .filter(bookmarks::id.eq_any(ids))
+        .set(bookmarks::updated_at.eq(now))
+        .execute(conn)
+        .await
+        .expect("Error touching bookmarks")
+}
+
 pub async fn delete_bookmarks(conn: &mut Connection, ids: Vec<i32>) -> usize {
     use diesel::{ExpressionMethods, dsl::now};
 
@@ -96,6 +340,172 @@ pub async fn delete_bookmarks(conn: &mut Connection, ids: Vec<i32>) -> usize {
         .expect("Error deleting bookmarks")
 }
 
+/// Restore bookmarks from the trash, which fails if their shortcuts are taken meanwhile.
+pub async fn restore_bookmarks(
+    conn: &mut Connection,
+    ids: Vec<i32>,
+) -> Result<usize, DatabaseError> {
+    use diesel::{ExpressionMethods, dsl::now};
+
+    use super::schema::bookmarks::{dsl::*, table};
+
+    diesel::update(table)
+        .filter(id.eq_any(ids).and(deleted_at.is_not_null()))
+        .set((
+            deleted_at.eq::<Option<time::OffsetDateTime>>(None),
+            updated_at.eq(now),
+        ))
+        .execute(conn)
+        .await
+        .map_err(|e| match e {
+            diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                _,
+            ) => DatabaseError::DuplicationError {
+                table: "bookmarks".to_string(),
+            },
+            _ => panic!("Unexpected error: {e:?}"),
+        })
+}
+
+/// Permanently delete bookmarks which are already in the trash, along with their tag links.
+pub async fn purge_bookmarks(conn: &mut Connection, ids: Vec<i32>) -> usize {
+    use diesel_async::AsyncConnection;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+
+    use super::schema::bookmarks_tags;
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        async move {
+            let ids = bookmarks::table
+                .filter(
+                    bookmarks::id
+                        .eq_any(ids)
+                        .and(bookmarks::deleted_at.is_not_null()),
+                )
+                .select(bookmarks::id)
+                .load::<i32>(conn)
+                .await?;
+            if ids.is_empty() {
+                return Ok(0);
+            }
+
+            diesel::delete(bookmarks_tags::table)
+                .filter(bookmarks_tags::bookmark_id.eq_any(&ids))
+                .execute(conn)
+                .await?;
+            diesel::delete(bookmarks::table)
+                .filter(bookmarks::id.eq_any(&ids))
+                .execute(conn)
+                .await
+        }
+        .scope_boxed()
+    })
+    .await
+    .expect("Error purging bookmarks")
+}
+
+/// Get ids of bookmarks which were moved into the trash before the given time.
+pub async fn get_bookmarks_deleted_before(
+    conn: &mut Connection,
+    before: time::OffsetDateTime,
+) -> Vec<i32> {
+    bookmarks::table
+        .filter(bookmarks::deleted_at.lt(before))
+        .select(bookmarks::id)
+        .load::<i32>(conn)
+        .await
+        .expect("Error loading expired bookmarks")
+}
+
+/// Get pinned bookmarks which are not deleted, in their manual order, then the latest updated first.
+pub async fn get_pinned_bookmarks(conn: &mut Connection) -> Vec<Bookmark> {
+    bookmarks::table
+        .filter(bookmarks::pinned.and(bookmarks::deleted_at.is_null()))
+        .order_by((
+            bookmarks::pin_order.asc().nulls_last(),
+            bookmarks::updated_at.desc(),
+        ))
+        .load(conn)
+        .await
+        .expect("Error loading pinned bookmarks")
+}
+
+/// Get all bookmarks which are not deleted, in the order they were created.
+pub async fn get_all_bookmarks(conn: &mut Connection) -> Vec<Bookmark> {
+    bookmarks::table
+        .filter(bookmarks::deleted_at.is_null())
+        .order_by(bookmarks::id.asc())
+        .load(conn)
+        .await
+        .expect("Error loading bookmarks")
+}
+
+/// Find bookmarks which are not deleted by their URLs, or by their canonical URLs.
+pub async fn find_bookmarks_by_urls(
+    conn: &mut Connection,
+    urls: &[String],
+    canonical: bool,
+) -> Vec<Bookmark> {
+    let query = bookmarks::table
+        .filter(bookmarks::deleted_at.is_null())
+        .order_by(bookmarks::id.desc())
+        .into_boxed();
+    let query = if canonical {
+        let urls = urls.iter().map(|url| canonicalize(url)).collect::<Vec<_>>();
+        query.filter(bookmarks::canonical_url.eq_any(urls))
+    } else {
+        query.filter(bookmarks::url.eq_any(urls))
+    };
+    query
+        .load::<Bookmark>(conn)
+        .await
+        .expect("Error loading bookmarks")
+}
+
+/// Find groups of bookmarks whose URLs are equal or near-equal after canonicalization.
+pub async fn find_duplicate_bookmarks(conn: &mut Connection) -> Vec<Vec<Bookmark>> {
+    use itertools::Itertools;
+
+    use crate::utils::canonical_url::near_equal_key;
+
+    let candidates = bookmarks::table
+        .filter(bookmarks::deleted_at.is_null())
+        .select((bookmarks::id, bookmarks::canonical_url))
+        .load::<(i32, String)>(conn)
+        .await
+        .expect("Error loading bookmarks");
+    let groups = candidates
+        .iter()
+        .into_group_map_by(|(_, canonical)| near_equal_key(canonical))
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|group| group.into_iter().map(|(id, _)| *id).collect_vec())
+        .collect_vec();
+    if groups.is_empty() {
+        return vec![];
+    }
+
+    let bookmarks = bookmarks::table
+        .filter(bookmarks::id.eq_any(groups.iter().flatten()))
+        .load::<Bookmark>(conn)
+        .await
+        .expect("Error loading bookmarks")
+        .into_iter()
+        .map(|b| (b.id, b))
+        .collect::<std::collections::HashMap<_, _>>();
+    groups
+        .into_iter()
+        .map(|ids| {
+            ids.into_iter()
+                .sorted()
+                .filter_map(|id| bookmarks.get(&id).cloned())
+                .collect_vec()
+        })
+        .sorted_by_key(|group| group[0].id)
+        .collect()
+}
+
 #[cfg(test)]
 pub(crate) mod test {
     use super::super::connection;
@@ -107,6 +517,9 @@ pub(crate) mod test {
         NewBookmark {
             title: utils::rand::rand_str(10),
             url: format!("https://{}.com", utils::rand::rand_str(10)).to_string(),
+            notes: None,
+            description: None,
+            attributes: None,
         }
     }
 
@@ -125,6 +538,112 @@ pub(crate) mod test {
         assert!(m.id > 0);
     }
 
+    #[tokio::test]
+    async fn canonicalize_bookmark_url() {
+        let mut conn = connection::establish().await;
+        let host = utils::rand::rand_str(10);
+        let url = format!("HTTPS://{host}.COM:443/path/?utm_source=x&b=2&a=1");
+        let m = create_bookmark(
+            &mut conn,
+            &NewBookmark {
+                title: utils::rand::rand_str(10),
+                url: url.clone(),
+                notes: None,
+                description: None,
+                attributes: None,
+            },
+        )
+        .await;
+        assert_eq!(m.url, url);
+        assert_eq!(
+            m.canonical_url,
+            format!("https://{}.com/path?a=1&b=2", host.to_lowercase())
+        );
+
+        let url = format!("http://www.{host}.com/?fbclid=x");
+        let m = update_bookmark(
+            &mut conn,
+            m.id,
+            ModifyBookmark {
+                title: None,
+                url: Some(url.clone()),
+                notes: None,
+                description: None,
+                pinned: None,
+                pin_order: None,
+                remind_at: None,
+                attributes: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(m.url, url);
+        assert_eq!(
+            m.canonical_url,
+            format!("http://www.{}.com/", host.to_lowercase())
+        );
+    }
+
+    #[tokio::test]
+    async fn recanonicalize_bookmark_urls() {
+        let mut conn = connection::establish().await;
+        let url = format!("https://{}.com/?utm_source=x", utils::rand::rand_str(10));
+        let m = create_bookmark(
+            &mut conn,
+            &NewBookmark {
+                url: url.clone(),
+                ..rand_bookmark()
+            },
+        )
+        .await;
+        // as migrated
+        diesel::update(bookmarks::table.find(m.id))
+            .set(bookmarks::canonical_url.eq(&url))
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let params = vec![utils::rand::rand_str(10), "utm_*".to_string()];
+        assert!(recanonicalize_bookmarks(&mut conn, &params).await > 0);
+        let m = Bookmark::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(m.canonical_url, canonicalize_with(&url, &params));
+        assert_ne!(m.canonical_url, url);
+
+        // unchanged tracking parameters
+        diesel::update(bookmarks::table.find(m.id))
+            .set(bookmarks::canonical_url.eq(&url))
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(recanonicalize_bookmarks(&mut conn, &params).await, 0);
+        let m = Bookmark::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(m.canonical_url, url);
+    }
+
+    #[tokio::test]
+    async fn fill_in_empty_fields() {
+        let mut conn = connection::establish().await;
+        let m = create_bookmark(
+            &mut conn,
+            &NewBookmark {
+                title: " ".to_string(),
+                ..rand_bookmark()
+            },
+        )
+        .await;
+
+        fill_in_bookmark(&mut conn, m.id, Some("Fetched"), Some("About")).await;
+        let m = Bookmark::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(m.title, "Fetched");
+        assert_eq!(m.description.as_deref(), Some("About"));
+
+        // edited already
+        fill_in_bookmark(&mut conn, m.id, Some("Again"), Some("Again")).await;
+        let m = Bookmark::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(m.title, "Fetched");
+        assert_eq!(m.description.as_deref(), Some("About"));
+    }
+
     #[tokio::test]
     async fn title_search_bookmark() {
         let mut conn = connection::establish().await;
@@ -143,6 +662,64 @@ pub(crate) mod test {
         info!("{:?}", results[0]);
     }
 
+    #[tokio::test]
+    async fn visit_a_bookmark() {
+        let mut conn = connection::establish().await;
+        let m = create_rand_bookmark(&mut conn).await;
+        assert_eq!(m.visit_count, 0);
+        assert!(m.last_visited_at.is_none());
+
+        visit_bookmark(&mut conn, m.id).await.unwrap();
+        let visited = visit_bookmark(&mut conn, m.id).await.unwrap();
+        assert_eq!(visited.visit_count, 2);
+        assert!(visited.last_visited_at.is_some());
+        assert_eq!(visited.updated_at, m.updated_at);
+
+        delete_bookmarks(&mut conn, vec![m.id]).await;
+        assert!(visit_bookmark(&mut conn, m.id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn pin_bookmarks() {
+        let mut conn = connection::establish().await;
+        let mut added = vec![];
+        for pin_order in [Some(Some(2)), Some(Some(1)), Some(None), None] {
+            let m = create_rand_bookmark(&mut conn).await;
+            let m = update_bookmark(
+                &mut conn,
+                m.id,
+                ModifyBookmark {
+                    title: None,
+                    url: None,
+                    notes: None,
+                    description: None,
+                    pinned: Some(pin_order.is_some()),
+                    pin_order,
+                    remind_at: None,
+                    attributes: None,
+                },
+            )
+            .await
+            .unwrap();
+            added.push(m);
+        }
+        assert!(added[0].pinned);
+        assert_eq!(added[0].pin_order, Some(2));
+        assert!(!added[3].pinned);
+
+        let ids = get_pinned_bookmarks(&mut conn)
+            .await
+            .into_iter()
+            .map(|m| m.id)
+            .filter(|id| added.iter().any(|m| m.id == *id))
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![added[1].id, added[0].id, added[2].id]);
+
+        delete_bookmarks(&mut conn, vec![added[1].id]).await;
+        let pinned = get_pinned_bookmarks(&mut conn).await;
+        assert!(!pinned.iter().any(|m| m.id == added[1].id));
+    }
+
     #[tokio::test]
     pub async fn delete_a_bookmark() {
         let mut conn = connection::establish().await;
@@ -157,6 +734,145 @@ pub(crate) mod test {
         assert!(m.deleted_at.is_some());
     }
 
+    #[tokio::test]
+    async fn restore_a_bookmark() {
+        let mut conn = connection::establish().await;
+        let m = create_rand_bookmark(&mut conn).await;
+
+        info!("restore a bookmark not in the trash");
+        let count = restore_bookmarks(&mut conn, vec![m.id]).await.unwrap();
+        assert_eq!(count, 0);
+
+        let count = delete_bookmarks(&mut conn, vec![m.id]).await;
+        assert_eq!(count, 1);
+
+        let count = restore_bookmarks(&mut conn, vec![m.id]).await.unwrap();
+        assert_eq!(count, 1);
+        let m = Bookmark::get(&mut conn, m.id).await.unwrap();
+        assert!(m.deleted_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn purge_a_bookmark() {
+        use crate::db::schema::bookmarks_tags;
+        use crate::db::tag::update_bookmark_tags;
+
+        let mut conn = connection::establish().await;
+        let m = create_rand_bookmark(&mut conn).await;
+        update_bookmark_tags(&mut conn, &m, &[utils::rand::rand_str(4)]).await;
+
+        info!("purge a bookmark not in the trash");
+        let count = purge_bookmarks(&mut conn, vec![m.id]).await;
+        assert_eq!(count, 0);
+        assert!(Bookmark::get(&mut conn, m.id).await.is_some());
+
+        delete_bookmarks(&mut conn, vec![m.id]).await;
+        let count = purge_bookmarks(&mut conn, vec![m.id]).await;
+        assert_eq!(count, 1);
+        assert!(Bookmark::get(&mut conn, m.id).await.is_none());
+
+        let links: i64 = bookmarks_tags::table
+            .filter(bookmarks_tags::bookmark_id.eq(m.id))
+            .count()
+            .get_result(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(links, 0);
+    }
+
+    #[tokio::test]
+    async fn purge_expired_bookmarks() {
+        let mut conn = connection::establish().await;
+        let expired = create_rand_bookmark(&mut conn).await;
+        let recent = create_rand_bookmark(&mut conn).await;
+        delete_bookmarks(&mut conn, vec![expired.id, recent.id]).await;
+
+        let deleted_at = time::OffsetDateTime::now_utc() - time::Duration::days(31);
+        diesel::update(bookmarks::table.find(expired.id))
+            .set(bookmarks::deleted_at.eq(deleted_at))
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let before = time::OffsetDateTime::now_utc() - time::Duration::days(30);
+        let ids = get_bookmarks_deleted_before(&mut conn, before).await;
+        assert!(ids.contains(&expired.id));
+        assert!(!ids.contains(&recent.id));
+        let count = purge_bookmarks(&mut conn, ids).await;
+        assert!(count >= 1);
+        assert!(Bookmark::get(&mut conn, expired.id).await.is_none());
+        assert!(Bookmark::get(&mut conn, recent.id).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn find_by_urls() {
+        let mut conn = connection::establish().await;
+        let host = utils::rand::rand_str(10).to_lowercase();
+        let url = format!("https://{host}.com/?utm_source=x");
+        let m = create_bookmark(
+            &mut conn,
+            &NewBookmark {
+                title: utils::rand::rand_str(10),
+                url: url.clone(),
+                notes: None,
+                description: None,
+                attributes: None,
+            },
+        )
+        .await;
+
+        let (urls, others) = (vec![url], vec![format!("https://{host}.com")]);
+        let rv = find_bookmarks_by_urls(&mut conn, &urls, false).await;
+        assert_eq!(rv.iter().map(|b| b.id).collect::<Vec<_>>(), vec![m.id]);
+        let rv = find_bookmarks_by_urls(&mut conn, &others, false).await;
+        assert!(rv.is_empty());
+        let rv = find_bookmarks_by_urls(&mut conn, &others, true).await;
+        assert_eq!(rv.iter().map(|b| b.id).collect::<Vec<_>>(), vec![m.id]);
+
+        delete_bookmarks(&mut conn, vec![m.id]).await;
+        let rv = find_bookmarks_by_urls(&mut conn, &urls, false).await;
+        assert!(rv.is_empty());
+    }
+
+    #[tokio::test]
+    async fn find_duplicates() {
+        let mut conn = connection::establish().await;
+        let host = utils::rand::rand_str(10).to_lowercase();
+        let mut ids = vec![];
+        for url in [
+            format!("https://{host}.com/"),
+            format!("http://www.{host}.com"),
+            format!("https://{host}.com/?utm_source=x"),
+        ] {
+            let m = create_bookmark(
+                &mut conn,
+                &NewBookmark {
+                    title: utils::rand::rand_str(10),
+                    url,
+                    notes: None,
+                    description: None,
+                    attributes: None,
+                },
+            )
+            .await;
+            ids.push(m.id);
+        }
+        let unique = create_rand_bookmark(&mut conn).await;
+
+        let groups = find_duplicate_bookmarks(&mut conn).await;
+        info!(?groups, "found duplicates");
+        let group = groups
+            .iter()
+            .find(|g| g.iter().any(|b| b.id == ids[0]))
+            .unwrap();
+        assert_eq!(group.iter().map(|b| b.id).collect::<Vec<_>>(), ids);
+        assert!(groups.iter().flatten().all(|b| b.id != unique.id));
+
+        delete_bookmarks(&mut conn, ids[1..].to_vec()).await;
+        let groups = find_duplicate_bookmarks(&mut conn).await;
+        assert!(groups.iter().flatten().all(|b| b.id != ids[0]));
+    }
+
     #[tokio::test]
     async fn update_exists_bookmark() {
         let mut conn = connection::establish().await;
@@ -177,6 +893,12 @@ pub(crate) mod test {
             ModifyBookmark {
                 title: Some(modified.title.clone()),
                 url: Some(modified.url.clone()),
+                notes: None,
+                description: None,
+                pinned: None,
+                pin_order: None,
+                remind_at: None,
+                attributes: None,
             },
         )
         .await;
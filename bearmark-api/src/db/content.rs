@@ -0,0 +1,71 @@
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection as Connection, RunQueryDsl};
+
+use super::bookmark::Bookmark;
+use super::schema::bookmark_contents;
+
+/// The readable text extracted from the page of a bookmark, indexed for full-text search.
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
+#[diesel(belongs_to(Bookmark))]
+#[diesel(primary_key(bookmark_id))]
+#[diesel(table_name = bookmark_contents)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BookmarkContent {
+    pub bookmark_id: i32,
+    pub content: String,
+    pub updated_at: time::OffsetDateTime,
+}
+
+impl BookmarkContent {
+    pub async fn get(conn: &mut Connection, bookmark_id: i32) -> Option<Self> {
+        bookmark_contents::table
+            .find(bookmark_id)
+            .first(conn)
+            .await
+            .optional()
+            .expect("Error loading bookmark content")
+    }
+}
+
+/// Save the content of the bookmark, replacing the previous one.
+pub async fn save_content(conn: &mut Connection, bookmark_id: i32, content: &str) {
+    use diesel::dsl::now;
+
+    diesel::insert_into(bookmark_contents::table)
+        .values((
+            bookmark_contents::bookmark_id.eq(bookmark_id),
+            bookmark_contents::content.eq(content),
+        ))
+        .on_conflict(bookmark_contents::bookmark_id)
+        .do_update()
+        .set((
+            bookmark_contents::content.eq(content),
+            bookmark_contents::updated_at.eq(now),
+        ))
+        .execute(conn)
+        .await
+        .expect("Error saving bookmark content");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::bookmark::test::create_rand_bookmark;
+    use crate::db::connection;
+
+    #[tokio::test]
+    async fn save_and_replace_content() {
+        let mut conn = connection::establish().await;
+        let m = create_rand_bookmark(&mut conn).await;
+        assert!(BookmarkContent::get(&mut conn, m.id).await.is_none());
+
+        save_content(&mut conn, m.id, "The first version").await;
+        let content = BookmarkContent::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(content.content, "The first version");
+
+        save_content(&mut conn, m.id, "The second version").await;
+        let replaced = BookmarkContent::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(replaced.content, "The second version");
+        assert!(replaced.updated_at >= content.updated_at);
+    }
+}
@@ -1,5 +1,6 @@
 use diesel::prelude::*;
 use diesel_async::{AsyncPgConnection as Connection, RunQueryDsl};
+use itertools::Itertools;
 use rocket::serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -63,11 +64,17 @@ impl Folder {
     }
 }
 
+/// Normalize the path of folder to start with `/` and end without `/`, with empty segments
+/// removed, or `None` for the root.
+pub fn normalize_path(path: &str) -> Option<String> {
+    let path = path.split('/').filter(|s| !s.is_empty()).join("/");
+    (!path.is_empty()).then(|| format!("/{path}"))
+}
+
 pub async fn create_folder(conn: &mut Connection, path: &str) -> Result<Folder, DatabaseError> {
     diesel::insert_into(folders::table)
         .values(&NewFolder {
-            // path should be normalized, start with `/` and end without `/`
-            path: format!("/{}", path.trim_matches('/')).as_str(),
+            path: normalize_path(path).as_deref().unwrap_or("/"),
         })
         .returning(Folder::as_returning())
         .get_result(conn)
@@ -83,6 +90,48 @@ pub async fn create_folder(conn: &mut Connection, path: &str) -> Result<Folder,
         })
 }
 
+/// Get the folder by path, creating it and its missing ancestors like `mkdir -p`.
+///
+/// Returns `None` for the root, which is not a folder.
+pub async fn get_or_create_folder(
+    conn: &mut Connection,
+    path: &str,
+) -> Result<Option<Folder>, DatabaseError> {
+    Ok(get_or_create_folder_with_created(conn, path)
+        .await?
+        .map(|(f, _)| f))
+}
+
+/// Get the folder by path like [`get_or_create_folder`], along with the folders created for it
+/// from the top level.
+pub async fn get_or_create_folder_with_created(
+    conn: &mut Connection,
+    path: &str,
+) -> Result<Option<(Folder, Vec<Folder>)>, DatabaseError> {
+    let Some(path) = normalize_path(path) else {
+        return Ok(None);
+    };
+    let segments = path.trim_start_matches('/').split('/').collect::<Vec<_>>();
+    let (mut folder, mut created) = (None, vec![]);
+    // ancestors are in reverse order, so create them from the top level
+    for (depth, existing) in Folder::get_with_ancestors(conn, &path)
+        .await
+        .into_iter()
+        .rev()
+        .enumerate()
+    {
+        folder = Some(match existing {
+            Some(f) => f,
+            None => {
+                let f = create_folder(conn, &segments[..=depth].join("/")).await?;
+                created.push(f.clone());
+                f
+            }
+        });
+    }
+    Ok(folder.map(|f| (f, created)))
+}
+
 #[allow(dead_code)]
 pub async fn delete_folder(conn: &mut Connection, id: i32) {
     diesel::delete(folders::table.filter(folders::dsl::id.eq(id)))
@@ -95,10 +144,13 @@ pub async fn move_bookmarks(
     conn: &mut Connection,
     folder_id: i32,
     bookmark_ids: &Vec<i32>,
-) -> Result<(), DatabaseError> {
+) -> Result<usize, DatabaseError> {
     diesel::update(bookmarks::table)
         .filter(bookmarks::dsl::id.eq_any(bookmark_ids))
-        .set(bookmarks::dsl::folder_id.eq(folder_id))
+        .set((
+            bookmarks::dsl::folder_id.eq(folder_id),
+            bookmarks::dsl::updated_at.eq(diesel::dsl::now),
+        ))
         .execute(conn)
         .await
         .map_err(|e| match e {
@@ -107,18 +159,19 @@ pub async fn move_bookmarks(
                 _,
             ) => DatabaseError::ViolationError(),
             _ => panic!("Unexpected error: {e:?}"),
-        })?;
-
-    Ok(())
+        })
 }
 
-pub async fn move_out_bookmarks(conn: &mut Connection, bookmark_ids: &Vec<i32>) {
+pub async fn move_out_bookmarks(conn: &mut Connection, bookmark_ids: &Vec<i32>) -> usize {
     diesel::update(bookmarks::table)
         .filter(bookmarks::dsl::id.eq_any(bookmark_ids))
-        .set(bookmarks::dsl::folder_id.eq::<Option<i32>>(None))
+        .set((
+            bookmarks::dsl::folder_id.eq::<Option<i32>>(None),
+            bookmarks::dsl::updated_at.eq(diesel::dsl::now),
+        ))
         .execute(conn)
         .await
-        .expect("Error moving out bookmarks");
+        .expect("Error moving out bookmarks")
 }
 
 pub async fn list_folders(conn: &mut Connection, cwd: &str) -> Vec<Folder> {
@@ -132,6 +185,16 @@ pub async fn list_folders(conn: &mut Connection, cwd: &str) -> Vec<Folder> {
         .expect("Error loading folders")
 }
 
+/// List all folders, parents before their children.
+pub async fn list_all_folders(conn: &mut Connection) -> Vec<Folder> {
+    folders::table
+        .select(Folder::as_select())
+        .order_by(folders::dsl::path.asc())
+        .load::<Folder>(conn)
+        .await
+        .expect("Error loading folders")
+}
+
 #[cfg(test)]
 pub(crate) mod test {
     use super::*;
@@ -166,6 +229,61 @@ pub(crate) mod test {
         ));
     }
 
+    #[tokio::test]
+    async fn get_or_create_nested_folder() {
+        let mut conn = connection::establish().await;
+
+        let parent = create_rand_folder(&mut conn).await;
+        let path = format!("{}/{}/{}/", parent.path, rand_str(10), rand_str(10));
+        let rv = get_or_create_folder(&mut conn, &path)
+            .await
+            .unwrap()
+            .unwrap();
+        info!(?rv, "get_or_create_folder returns");
+        assert_eq!(rv.path, path.trim_end_matches('/'));
+
+        let ancestors = Folder::get_with_ancestors(&mut conn, &path).await;
+        assert_eq!(ancestors.len(), 3);
+        assert!(ancestors.iter().all(|f| f.is_some()));
+        assert_eq!(ancestors[2].as_ref().unwrap().id, parent.id);
+
+        let again = get_or_create_folder(&mut conn, &path)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(again.id, rv.id);
+
+        let child = format!("{}/{}", rv.path, rand_str(10));
+        let (f, created) = get_or_create_folder_with_created(&mut conn, &child)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(f.path, child);
+        assert_eq!(
+            created.into_iter().map(|f| f.id).collect::<Vec<_>>(),
+            vec![f.id]
+        );
+        assert_eq!(normalize_path("a/b/"), Some("/a/b".to_string()));
+        assert_eq!(normalize_path("a//b"), Some("/a/b".to_string()));
+        assert_eq!(normalize_path("/"), None);
+
+        let doubled = format!("{}//{}", rv.path, rand_str(10));
+        let (f, created) = get_or_create_folder_with_created(&mut conn, &doubled)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(f.path, normalize_path(&doubled).unwrap());
+        assert_eq!(created.len(), 1);
+        for root in ["/", "", "//"] {
+            assert!(
+                get_or_create_folder(&mut conn, root)
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+        }
+    }
+
     #[tokio::test]
     async fn bookmarks_movements() {
         let mut conn = connection::establish().await;
@@ -0,0 +1,234 @@
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection as Connection, RunQueryDsl};
+use rocket::serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::bookmark::Bookmark;
+use super::schema::{bookmarks, link_checks};
+
+/// A link is broken after this number of consecutive failed checks.
+pub const BROKEN_AFTER_FAILURES: i32 = 2;
+
+#[derive(
+    Queryable,
+    Selectable,
+    Identifiable,
+    Associations,
+    Debug,
+    Clone,
+    Deserialize,
+    Serialize,
+    ToSchema,
+)]
+#[diesel(belongs_to(Bookmark))]
+#[diesel(table_name = link_checks)]
+#[diesel(primary_key(bookmark_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LinkCheck {
+    pub bookmark_id: i32,
+    /// The last status code, or null if the request failed
+    pub status_code: Option<i32>,
+    /// The URL redirected to, if different from the bookmark
+    pub redirect_url: Option<String>,
+    pub error: Option<String>,
+    pub consecutive_failures: i32,
+    #[schema(format = DateTime, value_type=String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub checked_at: time::OffsetDateTime,
+}
+
+#[derive(Insertable, AsChangeset, Debug, Default)]
+#[diesel(table_name = link_checks)]
+#[diesel(primary_key(bookmark_id))]
+#[diesel(treat_none_as_null = true)]
+pub struct NewLinkCheck {
+    pub bookmark_id: i32,
+    pub status_code: Option<i32>,
+    pub redirect_url: Option<String>,
+    pub error: Option<String>,
+}
+
+impl NewLinkCheck {
+    pub fn is_failed(&self) -> bool {
+        self.error.is_some() || self.status_code.is_none_or(|c| c >= 400)
+    }
+}
+
+impl LinkCheck {
+    pub async fn get(conn: &mut Connection, bookmark_id: i32) -> Option<Self> {
+        link_checks::table
+            .find(bookmark_id)
+            .first(conn)
+            .await
+            .optional()
+            .expect("Error loading link check")
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.consecutive_failures >= BROKEN_AFTER_FAILURES
+    }
+}
+
+/// Get bookmarks never checked or checked before the given time, the least recently checked first.
+/// Only the HTTP(S) links are checked, others like `javascript:` and `mailto:` can't be broken.
+pub async fn get_bookmarks_to_check(
+    conn: &mut Connection,
+    checked_before: time::OffsetDateTime,
+    limit: i64,
+) -> Vec<Bookmark> {
+    bookmarks::table
+        .left_join(link_checks::table)
+        .filter(bookmarks::deleted_at.is_null())
+        .filter(
+            bookmarks::url
+                .ilike("http://%")
+                .or(bookmarks::url.ilike("https://%")),
+        )
+        .filter(
+            link_checks::checked_at
+                .is_null()
+                .or(link_checks::checked_at.lt(checked_before)),
+        )
+        .order_by((link_checks::checked_at.asc().nulls_first(), bookmarks::id))
+        .limit(limit)
+        .select(Bookmark::as_select())
+        .load(conn)
+        .await
+        .expect("Error loading bookmarks to check")
+}
+
+/// Save the result of a check, counting the consecutive failures.
+pub async fn save_link_check(conn: &mut Connection, check: &NewLinkCheck) {
+    use diesel::dsl::now;
+
+    let query = diesel::insert_into(link_checks::table)
+        .values((
+            check,
+            link_checks::consecutive_failures.eq(i32::from(check.is_failed())),
+        ))
+        .on_conflict(link_checks::bookmark_id)
+        .do_update();
+    if check.is_failed() {
+        query
+            .set((
+                check,
+                link_checks::consecutive_failures.eq(link_checks::consecutive_failures + 1),
+                link_checks::checked_at.eq(now),
+            ))
+            .execute(conn)
+            .await
+    } else {
+        query
+            .set((
+                check,
+                link_checks::consecutive_failures.eq(0),
+                link_checks::checked_at.eq(now),
+            ))
+            .execute(conn)
+            .await
+    }
+    .expect("Error saving link check");
+}
+
+/// Get the broken links with their bookmarks, the most failed first.
+pub async fn get_broken_links(conn: &mut Connection) -> Vec<(LinkCheck, Bookmark)> {
+    link_checks::table
+        .inner_join(bookmarks::table)
+        .filter(link_checks::consecutive_failures.ge(BROKEN_AFTER_FAILURES))
+        .filter(bookmarks::deleted_at.is_null())
+        .order_by((
+            link_checks::consecutive_failures.desc(),
+            link_checks::bookmark_id.desc(),
+        ))
+        .select((LinkCheck::as_select(), Bookmark::as_select()))
+        .load(conn)
+        .await
+        .expect("Error loading broken links")
+}
+
+/// Count the checked links of bookmarks which are not deleted.
+pub async fn count_checked_links(conn: &mut Connection) -> i64 {
+    link_checks::table
+        .inner_join(bookmarks::table)
+        .filter(bookmarks::deleted_at.is_null())
+        .count()
+        .get_result(conn)
+        .await
+        .expect("Error counting link checks")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::bookmark::test::create_rand_bookmark;
+    use crate::db::connection;
+
+    #[tokio::test]
+    async fn save_link_checks() {
+        let mut conn = connection::establish().await;
+        let m = create_rand_bookmark(&mut conn).await;
+
+        let to_check =
+            get_bookmarks_to_check(&mut conn, time::OffsetDateTime::now_utc(), i64::MAX).await;
+        assert!(to_check.iter().any(|b| b.id == m.id));
+
+        let failed = NewLinkCheck {
+            bookmark_id: m.id,
+            status_code: Some(404),
+            ..Default::default()
+        };
+        save_link_check(&mut conn, &failed).await;
+        let check = LinkCheck::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(check.consecutive_failures, 1);
+        assert!(!check.is_broken());
+
+        save_link_check(&mut conn, &failed).await;
+        let check = LinkCheck::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(check.consecutive_failures, 2);
+        assert!(check.is_broken());
+        let broken = get_broken_links(&mut conn).await;
+        assert!(
+            broken
+                .iter()
+                .any(|(c, b)| c.bookmark_id == m.id && b.id == m.id)
+        );
+
+        let to_check = get_bookmarks_to_check(&mut conn, check.checked_at, i64::MAX).await;
+        assert!(!to_check.iter().any(|b| b.id == m.id));
+
+        save_link_check(
+            &mut conn,
+            &NewLinkCheck {
+                bookmark_id: m.id,
+                status_code: Some(200),
+                redirect_url: Some("https://example.com/".to_string()),
+                ..Default::default()
+            },
+        )
+        .await;
+        let check = LinkCheck::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(check.consecutive_failures, 0);
+        assert_eq!(check.status_code, Some(200));
+        assert_eq!(check.redirect_url.as_deref(), Some("https://example.com/"));
+        assert!(count_checked_links(&mut conn).await > 0);
+    }
+
+    #[tokio::test]
+    async fn skip_non_http_links() {
+        use crate::db::bookmark::{NewBookmark, create_bookmark, test::rand_bookmark};
+
+        let mut conn = connection::establish().await;
+        let mut added = vec![];
+        for url in ["javascript:alert(1)", "mailto:someone@example.com"] {
+            let new = NewBookmark {
+                url: url.to_string(),
+                ..rand_bookmark()
+            };
+            added.push(create_bookmark(&mut conn, &new).await.id);
+        }
+
+        let to_check =
+            get_bookmarks_to_check(&mut conn, time::OffsetDateTime::now_utc(), i64::MAX).await;
+        assert!(!to_check.iter().any(|b| added.contains(&b.id)));
+    }
+}
@@ -0,0 +1,170 @@
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection as Connection, RunQueryDsl};
+use rocket::serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::bookmark::Bookmark;
+use super::schema::{bookmark_metadata, bookmarks};
+
+/// Status of fetching the metadata of a bookmark.
+pub mod status {
+    pub const PENDING: &str = "pending";
+    pub const SUCCEEDED: &str = "succeeded";
+    pub const FAILED: &str = "failed";
+}
+
+#[derive(
+    Queryable,
+    Selectable,
+    Identifiable,
+    Associations,
+    Debug,
+    Clone,
+    Deserialize,
+    Serialize,
+    ToSchema,
+)]
+#[diesel(belongs_to(Bookmark))]
+#[diesel(table_name = bookmark_metadata)]
+#[diesel(primary_key(bookmark_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BookmarkMetadata {
+    pub bookmark_id: i32,
+    /// One of `pending`, `succeeded` and `failed`
+    pub status: String,
+    pub http_status: Option<i32>,
+    pub error: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub site_name: Option<String>,
+    pub favicon_url: Option<String>,
+    #[schema(format = DateTime, value_type=String, nullable)]
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub fetched_at: Option<time::OffsetDateTime>,
+    #[schema(format = DateTime, value_type=String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: time::OffsetDateTime,
+    #[schema(format = DateTime, value_type=String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: time::OffsetDateTime,
+}
+
+#[derive(AsChangeset, Debug, Default)]
+#[diesel(table_name = bookmark_metadata)]
+#[diesel(treat_none_as_null = true)]
+pub struct FetchedMetadata {
+    pub status: String,
+    pub http_status: Option<i32>,
+    pub error: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub site_name: Option<String>,
+    pub favicon_url: Option<String>,
+}
+
+impl BookmarkMetadata {
+    pub async fn get(conn: &mut Connection, bookmark_id: i32) -> Option<Self> {
+        bookmark_metadata::table
+            .find(bookmark_id)
+            .first(conn)
+            .await
+            .optional()
+            .expect("Error loading bookmark metadata")
+    }
+}
+
+/// Mark the metadata of bookmarks as pending, so they are fetched in the background.
+pub async fn enqueue_bookmarks(conn: &mut Connection, bookmark_ids: &[i32]) -> usize {
+    use diesel::dsl::now;
+
+    diesel::insert_into(bookmark_metadata::table)
+        .values(
+            bookmark_ids
+                .iter()
+                .map(|id| bookmark_metadata::bookmark_id.eq(id))
+                .collect::<Vec<_>>(),
+        )
+        .on_conflict(bookmark_metadata::bookmark_id)
+        .do_update()
+        .set((
+            bookmark_metadata::status.eq(status::PENDING),
+            bookmark_metadata::updated_at.eq(now),
+        ))
+        .execute(conn)
+        .await
+        .expect("Error enqueuing bookmarks")
+}
+
+/// Get bookmarks waiting for fetching, in the order of enqueuing.
+pub async fn get_pending_bookmarks(conn: &mut Connection, limit: i64) -> Vec<Bookmark> {
+    bookmarks::table
+        .inner_join(bookmark_metadata::table)
+        .filter(
+            bookmark_metadata::status
+                .eq(status::PENDING)
+                .and(bookmarks::deleted_at.is_null()),
+        )
+        .order_by(bookmark_metadata::updated_at.asc())
+        .limit(limit)
+        .select(Bookmark::as_select())
+        .load(conn)
+        .await
+        .expect("Error loading pending bookmarks")
+}
+
+pub async fn save_metadata(conn: &mut Connection, bookmark_id: i32, fetched: &FetchedMetadata) {
+    use diesel::dsl::now;
+
+    diesel::update(bookmark_metadata::table.find(bookmark_id))
+        .set((
+            fetched,
+            bookmark_metadata::fetched_at.eq(now),
+            bookmark_metadata::updated_at.eq(now),
+        ))
+        .execute(conn)
+        .await
+        .expect("Error saving bookmark metadata");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::bookmark::test::create_rand_bookmark;
+    use crate::db::connection;
+
+    #[tokio::test]
+    async fn enqueue_and_save_metadata() {
+        let mut conn = connection::establish().await;
+        let m = create_rand_bookmark(&mut conn).await;
+        assert!(BookmarkMetadata::get(&mut conn, m.id).await.is_none());
+
+        assert_eq!(enqueue_bookmarks(&mut conn, &[m.id]).await, 1);
+        let meta = BookmarkMetadata::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(meta.status, status::PENDING);
+        assert!(meta.fetched_at.is_none());
+        let pending = get_pending_bookmarks(&mut conn, i64::MAX).await;
+        assert!(pending.iter().any(|b| b.id == m.id));
+
+        let fetched = FetchedMetadata {
+            status: status::SUCCEEDED.to_string(),
+            http_status: Some(200),
+            title: Some("Title".to_string()),
+            ..Default::default()
+        };
+        save_metadata(&mut conn, m.id, &fetched).await;
+        let meta = BookmarkMetadata::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(meta.status, status::SUCCEEDED);
+        assert_eq!(meta.title.as_deref(), Some("Title"));
+        assert!(meta.fetched_at.is_some());
+        let pending = get_pending_bookmarks(&mut conn, i64::MAX).await;
+        assert!(!pending.iter().any(|b| b.id == m.id));
+
+        // enqueue again, e.g. the URL is changed
+        assert_eq!(enqueue_bookmarks(&mut conn, &[m.id]).await, 1);
+        let meta = BookmarkMetadata::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(meta.status, status::PENDING);
+        assert_eq!(meta.title.as_deref(), Some("Title"));
+    }
+}
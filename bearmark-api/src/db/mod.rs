@@ -2,8 +2,16 @@
 pub mod schema;
 
 // ORM Models
+pub mod archive;
 pub mod bookmark;
+pub mod content;
 pub mod folder;
+pub mod link_check;
+pub mod metadata;
+pub mod reading;
+pub mod reminder;
+pub mod revision;
+pub mod shortcut;
 pub mod tag;
 
 // Driver
@@ -15,4 +23,6 @@ pub mod extending;
 // Utilities
 pub(crate) mod search;
 
-pub use search::{get_bookmark_details, search_bookmarks};
+pub use search::{
+    Sort, find_bookmark_ids, get_bookmark_details, search_bookmarks, search_deleted_bookmarks,
+};
@@ -0,0 +1,196 @@
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection as Connection, RunQueryDsl};
+use rocket::serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::bookmark::Bookmark;
+use super::schema::bookmarks;
+use crate::utils::CommonError;
+
+/// The state of a bookmark in the read-later workflow.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum ReadingState {
+    Unread,
+    Reading,
+    Done,
+    /// Kept searchable, but excluded from the default search
+    Archived,
+}
+
+impl ReadingState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReadingState::Unread => "unread",
+            ReadingState::Reading => "reading",
+            ReadingState::Done => "done",
+            ReadingState::Archived => "archived",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        [Self::Unread, Self::Reading, Self::Done, Self::Archived]
+            .into_iter()
+            .find(|state| state.as_str() == s)
+    }
+
+    /// Whether a bookmark can be changed from the state, `None` is out of the workflow.
+    /// A finished bookmark must be queued again before reading it again, anything else goes.
+    pub fn can_transition(from: Option<Self>, to: Option<Self>) -> bool {
+        use ReadingState::*;
+        !matches!((from, to), (Some(Done | Archived), Some(Reading)))
+    }
+}
+
+/// Change the reading state of the bookmark which is not deleted, recording the time of the
+/// transition. Queuing again starts over, changing to the same state is a no-op.
+pub async fn set_reading_state(
+    conn: &mut Connection,
+    id: i32,
+    state: Option<ReadingState>,
+) -> Result<Option<Bookmark>, CommonError> {
+    use diesel::dsl::{now, sql};
+    use diesel::sql_types::{Nullable, Timestamptz};
+
+    let Some(m) = Bookmark::get(conn, id)
+        .await
+        .filter(|m| m.deleted_at.is_none())
+    else {
+        return Ok(None);
+    };
+    let current = m.reading_state.as_deref().and_then(ReadingState::parse);
+    if current == state {
+        return Ok(Some(m));
+    }
+    if !ReadingState::can_transition(current, state) {
+        return Err(CommonError::InvalidTransition {
+            from: current.map_or("none", |s| s.as_str()).to_string(),
+            to: state.map_or("none", |s| s.as_str()).to_string(),
+        });
+    }
+
+    let timestamp = |expr: &str| Some(sql::<Nullable<Timestamptz>>(expr));
+    let (queued_at, read_at) = match state {
+        Some(ReadingState::Unread) => (timestamp("now()"), timestamp("NULL")),
+        Some(ReadingState::Reading) => (timestamp("coalesce(queued_at, now())"), None),
+        Some(ReadingState::Done) => (timestamp("coalesce(queued_at, now())"), timestamp("now()")),
+        Some(ReadingState::Archived) => (None, None),
+        None => (timestamp("NULL"), timestamp("NULL")),
+    };
+    let rv = diesel::update(bookmarks::table.find(id))
+        .set((
+            bookmarks::reading_state.eq(state.map(|s| s.as_str())),
+            bookmarks::reading_state_changed_at.eq(now),
+            queued_at.map(|e| bookmarks::queued_at.eq(e)),
+            read_at.map(|e| bookmarks::read_at.eq(e)),
+            bookmarks::updated_at.eq(now),
+        ))
+        .returning(Bookmark::as_returning())
+        .get_result(conn)
+        .await
+        .optional()
+        .expect("Error updating reading state");
+    Ok(rv)
+}
+
+/// Get unread bookmarks which are not deleted, the earliest queued first.
+pub async fn get_reading_queue(conn: &mut Connection, limit: i64) -> Vec<Bookmark> {
+    bookmarks::table
+        .filter(
+            bookmarks::reading_state
+                .eq(ReadingState::Unread.as_str())
+                .and(bookmarks::deleted_at.is_null()),
+        )
+        .order_by((bookmarks::queued_at.asc(), bookmarks::id.asc()))
+        .limit(limit)
+        .load(conn)
+        .await
+        .expect("Error loading reading queue")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::bookmark::delete_bookmarks;
+    use crate::db::bookmark::test::create_rand_bookmark;
+    use crate::db::connection;
+
+    use ReadingState::*;
+
+    #[test]
+    fn test_transitions() {
+        for (from, to, expected) in [
+            (None, Some(Unread), true),
+            (None, Some(Done), true),
+            (Some(Unread), Some(Reading), true),
+            (Some(Reading), Some(Done), true),
+            (Some(Done), Some(Archived), true),
+            (Some(Archived), Some(Unread), true),
+            (Some(Archived), None, true),
+            (Some(Done), Some(Reading), false),
+            (Some(Archived), Some(Reading), false),
+        ] {
+            assert_eq!(
+                ReadingState::can_transition(from, to),
+                expected,
+                "{from:?} -> {to:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn read_later_workflow() {
+        let mut conn = connection::establish().await;
+        let (m1, m2) = (
+            create_rand_bookmark(&mut conn).await,
+            create_rand_bookmark(&mut conn).await,
+        );
+        assert!(m1.reading_state.is_none());
+
+        let queued = set_reading_state(&mut conn, m1.id, Some(Unread))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(queued.reading_state.as_deref(), Some("unread"));
+        assert!(queued.queued_at.is_some());
+        assert!(queued.reading_state_changed_at.is_some());
+        set_reading_state(&mut conn, m2.id, Some(Unread))
+            .await
+            .unwrap();
+
+        let ids = get_reading_queue(&mut conn, i64::MAX)
+            .await
+            .into_iter()
+            .map(|m| m.id)
+            .filter(|id| [m1.id, m2.id].contains(id))
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![m1.id, m2.id]);
+
+        let done = set_reading_state(&mut conn, m1.id, Some(Done))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(done.reading_state.as_deref(), Some("done"));
+        assert_eq!(done.queued_at, queued.queued_at);
+        assert!(done.read_at.is_some());
+        let queue = get_reading_queue(&mut conn, i64::MAX).await;
+        assert!(!queue.iter().any(|m| m.id == m1.id));
+
+        let rv = set_reading_state(&mut conn, m1.id, Some(Reading)).await;
+        assert!(matches!(rv, Err(CommonError::InvalidTransition { .. })));
+
+        let removed = set_reading_state(&mut conn, m1.id, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(removed.reading_state.is_none());
+        assert!(removed.queued_at.is_none());
+        assert!(removed.read_at.is_none());
+
+        delete_bookmarks(&mut conn, vec![m2.id]).await;
+        let rv = set_reading_state(&mut conn, m2.id, Some(Done)).await;
+        assert!(rv.unwrap().is_none());
+        let queue = get_reading_queue(&mut conn, i64::MAX).await;
+        assert!(!queue.iter().any(|m| m.id == m2.id));
+    }
+}
@@ -0,0 +1,89 @@
+use diesel::dsl::now;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection as Connection, RunQueryDsl};
+
+use super::bookmark::Bookmark;
+use super::schema::bookmarks;
+
+/// Get bookmarks which are not deleted and due to be reminded of, the earliest first.
+pub async fn get_due_reminders(conn: &mut Connection, limit: i64) -> Vec<Bookmark> {
+    bookmarks::table
+        .filter(
+            bookmarks::remind_at
+                .le(now)
+                .and(bookmarks::deleted_at.is_null()),
+        )
+        .order_by((bookmarks::remind_at.asc(), bookmarks::id.asc()))
+        .limit(limit)
+        .load(conn)
+        .await
+        .expect("Error loading due reminders")
+}
+
+/// Get bookmarks which are not deleted and have reminders, due or not, the earliest first.
+pub async fn get_reminders(conn: &mut Connection) -> Vec<Bookmark> {
+    bookmarks::table
+        .filter(
+            bookmarks::remind_at
+                .is_not_null()
+                .and(bookmarks::deleted_at.is_null()),
+        )
+        .order_by((bookmarks::remind_at.asc(), bookmarks::id.asc()))
+        .load(conn)
+        .await
+        .expect("Error loading reminders")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::bookmark::test::create_rand_bookmark;
+    use crate::db::bookmark::{ModifyBookmark, delete_bookmarks, update_bookmark};
+    use crate::db::connection;
+
+    use time::{Duration, OffsetDateTime};
+
+    async fn remind(conn: &mut Connection, id: i32, remind_at: OffsetDateTime) {
+        update_bookmark(
+            conn,
+            id,
+            ModifyBookmark {
+                title: None,
+                url: None,
+                notes: None,
+                description: None,
+                pinned: None,
+                pin_order: None,
+                remind_at: Some(Some(remind_at)),
+                attributes: None,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn list_reminders() {
+        let mut conn = connection::establish().await;
+        let (m1, m2, m3) = (
+            create_rand_bookmark(&mut conn).await,
+            create_rand_bookmark(&mut conn).await,
+            create_rand_bookmark(&mut conn).await,
+        );
+        let current = OffsetDateTime::now_utc();
+        remind(&mut conn, m1.id, current - Duration::days(1)).await;
+        remind(&mut conn, m2.id, current + Duration::days(1)).await;
+        remind(&mut conn, m3.id, current - Duration::days(2)).await;
+        delete_bookmarks(&mut conn, vec![m3.id]).await;
+
+        let due = get_due_reminders(&mut conn, i64::MAX).await;
+        assert!(due.iter().any(|m| m.id == m1.id));
+        assert!(!due.iter().any(|m| m.id == m2.id || m.id == m3.id));
+        assert!(due.windows(2).all(|w| w[0].remind_at <= w[1].remind_at));
+
+        let all = get_reminders(&mut conn).await;
+        assert!(all.iter().any(|m| m.id == m1.id));
+        assert!(all.iter().any(|m| m.id == m2.id));
+        assert!(!all.iter().any(|m| m.id == m3.id));
+    }
+}
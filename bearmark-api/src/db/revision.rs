@@ -0,0 +1,189 @@
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection as Connection, RunQueryDsl};
+
+use super::bookmark::Bookmark;
+use super::schema::bookmark_revisions;
+
+/// The state of a bookmark which is tracked by revisions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub title: String,
+    pub url: String,
+    /// The path of the folder
+    pub folder: Option<String>,
+    /// Tag names in order
+    pub tags: Vec<String>,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
+#[diesel(belongs_to(Bookmark))]
+#[diesel(table_name = bookmark_revisions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BookmarkRevision {
+    pub id: i32,
+    pub bookmark_id: i32,
+    pub old_title: String,
+    pub new_title: String,
+    pub old_url: String,
+    pub new_url: String,
+    pub old_folder: Option<String>,
+    pub new_folder: Option<String>,
+    pub old_tags: Vec<Option<String>>,
+    pub new_tags: Vec<Option<String>>,
+    pub created_at: time::OffsetDateTime,
+}
+
+impl BookmarkRevision {
+    pub async fn get(conn: &mut Connection, id: i32) -> Option<Self> {
+        bookmark_revisions::table
+            .find(id)
+            .first(conn)
+            .await
+            .optional()
+            .expect("Error loading bookmark revision")
+    }
+
+    /// The state before the change.
+    pub fn before(&self) -> Snapshot {
+        Snapshot {
+            title: self.old_title.clone(),
+            url: self.old_url.clone(),
+            folder: self.old_folder.clone(),
+            tags: self.old_tags.iter().flatten().cloned().collect(),
+        }
+    }
+
+    /// The state after the change.
+    pub fn after(&self) -> Snapshot {
+        Snapshot {
+            title: self.new_title.clone(),
+            url: self.new_url.clone(),
+            folder: self.new_folder.clone(),
+            tags: self.new_tags.iter().flatten().cloned().collect(),
+        }
+    }
+}
+
+/// Take snapshots of bookmarks which are not deleted, to record their changes later.
+pub async fn take_snapshots(conn: &mut Connection, ids: &[i32]) -> Vec<(i32, Snapshot)> {
+    let bookmarks = Bookmark::get_many(conn, ids).await;
+    if bookmarks.is_empty() {
+        return vec![];
+    }
+    super::get_bookmark_details(conn, bookmarks)
+        .await
+        .into_iter()
+        .map(|(m, folder, tags)| {
+            (
+                m.id,
+                Snapshot {
+                    title: m.title,
+                    url: m.url,
+                    folder: folder.map(|f| f.path),
+                    tags: tags.into_iter().map(|t| t.name).collect(),
+                },
+            )
+        })
+        .collect()
+}
+
+/// Record a revision for each bookmark changed since its snapshot was taken. Returns the number
+/// of revisions.
+pub async fn record_revisions(conn: &mut Connection, before: Vec<(i32, Snapshot)>) -> usize {
+    let ids = before.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    let after = take_snapshots(conn, &ids).await;
+
+    let to_array = |tags: &[String]| tags.iter().map(|t| Some(t.clone())).collect::<Vec<_>>();
+    let revisions = before
+        .into_iter()
+        .filter_map(|(id, old)| {
+            let (_, new) = after.iter().find(|(i, _)| *i == id)?;
+            (old != *new).then(|| {
+                (
+                    bookmark_revisions::bookmark_id.eq(id),
+                    bookmark_revisions::old_title.eq(old.title),
+                    bookmark_revisions::new_title.eq(new.title.clone()),
+                    bookmark_revisions::old_url.eq(old.url),
+                    bookmark_revisions::new_url.eq(new.url.clone()),
+                    bookmark_revisions::old_folder.eq(old.folder),
+                    bookmark_revisions::new_folder.eq(new.folder.clone()),
+                    bookmark_revisions::old_tags.eq(to_array(&old.tags)),
+                    bookmark_revisions::new_tags.eq(to_array(&new.tags)),
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+    if revisions.is_empty() {
+        return 0;
+    }
+
+    diesel::insert_into(bookmark_revisions::table)
+        .values(revisions)
+        .execute(conn)
+        .await
+        .expect("Error recording bookmark revisions")
+}
+
+/// Get revisions of the bookmark, the latest first.
+pub async fn get_revisions(conn: &mut Connection, bookmark_id: i32) -> Vec<BookmarkRevision> {
+    bookmark_revisions::table
+        .filter(bookmark_revisions::bookmark_id.eq(bookmark_id))
+        .order_by(bookmark_revisions::id.desc())
+        .load(conn)
+        .await
+        .expect("Error loading bookmark revisions")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::bookmark::test::create_rand_bookmark;
+    use crate::db::bookmark::{ModifyBookmark, update_bookmark};
+    use crate::db::connection;
+    use crate::db::tag::update_bookmark_tags;
+
+    #[tokio::test]
+    async fn record_changes_of_bookmarks() {
+        let mut conn = connection::establish().await;
+        let (m1, m2) = (
+            create_rand_bookmark(&mut conn).await,
+            create_rand_bookmark(&mut conn).await,
+        );
+
+        let before = take_snapshots(&mut conn, &[m1.id, m2.id]).await;
+        assert_eq!(before.len(), 2);
+        update_bookmark(
+            &mut conn,
+            m1.id,
+            ModifyBookmark {
+                title: Some("renamed".to_string()),
+                url: None,
+                notes: None,
+                description: None,
+                pinned: None,
+                pin_order: None,
+                remind_at: None,
+                attributes: None,
+            },
+        )
+        .await;
+        update_bookmark_tags(&mut conn, &m1, &["revised".to_string()]).await;
+        // m2 is not changed
+        assert_eq!(record_revisions(&mut conn, before).await, 1);
+
+        let revisions = get_revisions(&mut conn, m1.id).await;
+        assert_eq!(revisions.len(), 1);
+        let (old, new) = (revisions[0].before(), revisions[0].after());
+        assert_eq!(old.title, m1.title);
+        assert!(old.tags.is_empty());
+        assert_eq!(new.title, "renamed");
+        assert_eq!(new.url, m1.url);
+        assert_eq!(new.tags, vec!["revised".to_string()]);
+        assert!(get_revisions(&mut conn, m2.id).await.is_empty());
+
+        let revision = BookmarkRevision::get(&mut conn, revisions[0].id)
+            .await
+            .unwrap();
+        assert_eq!(revision.bookmark_id, m1.id);
+    }
+}
@@ -1,5 +1,66 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    archive_blobs (content_hash) {
+        content_hash -> Varchar,
+        content -> Bytea,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    bookmark_archives (id) {
+        id -> Int4,
+        bookmark_id -> Int4,
+        content_hash -> Varchar,
+        content_type -> Varchar,
+        size -> Int4,
+        storage -> Varchar,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    bookmark_contents (bookmark_id) {
+        bookmark_id -> Int4,
+        content -> Text,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    bookmark_metadata (bookmark_id) {
+        bookmark_id -> Int4,
+        status -> Varchar,
+        http_status -> Nullable<Int4>,
+        error -> Nullable<Varchar>,
+        title -> Nullable<Varchar>,
+        description -> Nullable<Varchar>,
+        image_url -> Nullable<Varchar>,
+        site_name -> Nullable<Varchar>,
+        favicon_url -> Nullable<Varchar>,
+        fetched_at -> Nullable<Timestamptz>,
+        updated_at -> Timestamptz,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    bookmark_revisions (id) {
+        id -> Int4,
+        bookmark_id -> Int4,
+        old_title -> Varchar,
+        new_title -> Varchar,
+        old_url -> Varchar,
+        new_url -> Varchar,
+        old_folder -> Nullable<Varchar>,
+        new_folder -> Nullable<Varchar>,
+        old_tags -> Array<Nullable<Text>>,
+        new_tags -> Array<Nullable<Text>>,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     bookmarks (id) {
         id -> Int4,
@@ -9,6 +70,20 @@ diesel::table! {
         deleted_at -> Nullable<Timestamptz>,
         updated_at -> Timestamptz,
         folder_id -> Nullable<Int4>,
+        canonical_url -> Varchar,
+        notes -> Nullable<Text>,
+        description -> Nullable<Varchar>,
+        visit_count -> Int4,
+        last_visited_at -> Nullable<Timestamptz>,
+        pinned -> Bool,
+        pin_order -> Nullable<Int4>,
+        reading_state -> Nullable<Varchar>,
+        reading_state_changed_at -> Nullable<Timestamptz>,
+        queued_at -> Nullable<Timestamptz>,
+        read_at -> Nullable<Timestamptz>,
+        remind_at -> Nullable<Timestamptz>,
+        attributes -> Jsonb,
+        shortcut -> Nullable<Varchar>,
     }
 }
 
@@ -19,6 +94,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    canonical_url_params (id) {
+        id -> Bool,
+        tracking_params -> Array<Nullable<Text>>,
+        updated_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     folders (id) {
         id -> Int4,
@@ -28,6 +111,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    link_checks (bookmark_id) {
+        bookmark_id -> Int4,
+        status_code -> Nullable<Int4>,
+        redirect_url -> Nullable<Varchar>,
+        error -> Nullable<Varchar>,
+        consecutive_failures -> Int4,
+        checked_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     tags (id) {
         id -> Int4,
@@ -37,8 +131,25 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(bookmark_archives -> bookmarks (bookmark_id));
+diesel::joinable!(bookmark_contents -> bookmarks (bookmark_id));
+diesel::joinable!(bookmark_metadata -> bookmarks (bookmark_id));
+diesel::joinable!(bookmark_revisions -> bookmarks (bookmark_id));
 diesel::joinable!(bookmarks -> folders (folder_id));
 diesel::joinable!(bookmarks_tags -> bookmarks (bookmark_id));
 diesel::joinable!(bookmarks_tags -> tags (tag_id));
+diesel::joinable!(link_checks -> bookmarks (bookmark_id));
 
-diesel::allow_tables_to_appear_in_same_query!(bookmarks, bookmarks_tags, folders, tags,);
+diesel::allow_tables_to_appear_in_same_query!(
+    archive_blobs,
+    bookmark_archives,
+    bookmark_contents,
+    bookmark_metadata,
+    bookmark_revisions,
+    bookmarks,
+    bookmarks_tags,
+    canonical_url_params,
+    folders,
+    link_checks,
+    tags,
+);
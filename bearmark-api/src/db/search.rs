@@ -75,30 +75,80 @@ fn find_bookmarks_in_path(
     Ok(Box::new(condition))
 }
 
+/// Find bookmarks having the attribute, of the value if it's not `*`. A value of number or
+/// boolean also matches the string of it, since the type can't be told by the query.
+fn find_bookmarks_by_attribute(
+    key: &str,
+    value: &str,
+) -> Box<dyn BoxableExpression<schema::bookmarks::table, Pg, SqlType = Bool>> {
+    use super::schema::bookmarks;
+    use serde_json::{Value, json};
+
+    if value == "*" {
+        return Box::new(bookmarks::dsl::attributes.has_key(key.to_string()));
+    }
+    // `@>` is supported by the GIN index of attributes
+    let condition = bookmarks::dsl::attributes.contains(json!({ key: value }));
+    match serde_json::from_str::<Value>(value) {
+        Ok(parsed @ (Value::Number(_) | Value::Bool(_))) => {
+            Box::new(condition.or(bookmarks::dsl::attributes.contains(json!({ key: parsed }))))
+        }
+        _ => Box::new(condition),
+    }
+}
+
+/// Find bookmarks on the site, by the host of their canonical URLs, including its subdomains.
+/// Returns `None` if the site is not a valid host.
+fn find_bookmarks_on_site(
+    site: &str,
+) -> Option<Box<dyn BoxableExpression<schema::bookmarks::table, Pg, SqlType = Bool>>> {
+    use diesel::dsl::sql;
+    use diesel::sql_types::Text;
+
+    // lowercased, and IDN converted into punycode, the same as canonical URLs
+    let url = url::Url::parse(&format!("http://{site}/")).ok()?;
+    let host = url.host_str()?;
+    let host = host
+        .replace('.', r"\.")
+        .replace('[', r"\[")
+        .replace(']', r"\]");
+    let pattern = format!(r"^https?://([^/?#@]*@)?([^/?#@]*\.)?{host}(:[0-9]+)?([/?#]|$)");
+    Some(Box::new(
+        sql::<Bool>("bookmarks.canonical_url ~ ")
+            .bind::<Text, _>(pattern)
+            .sql(""),
+    ))
+}
+
+/// Defaults of searching which are overridden by the query.
+#[derive(Default)]
+struct Overrides {
+    /// A path is given, so the cwd is not applied
+    cwd: bool,
+    /// `is:archived` is given, so archived bookmarks are not excluded
+    archived: bool,
+}
+
 fn find_bookmarks(
     query: &bearmark_ql::Query,
     cwd: &str,
-    cwd_overwrited: &mut bool,
+    overrides: &mut Overrides,
 ) -> Result<Box<dyn BoxableExpression<schema::bookmarks::table, Pg, SqlType = Bool>>, CommonError> {
-    use super::schema::{bookmarks, bookmarks_tags, tags};
+    use super::schema::{bookmark_contents, bookmarks, bookmarks_tags, link_checks, tags};
     use bearmark_ql::Query::*;
 
     Ok(match query {
-        Or(a, b) => Box::new(find_bookmarks(a, cwd, cwd_overwrited)?.or(find_bookmarks(
-            b,
-            cwd,
-            cwd_overwrited,
-        )?)),
-        And(a, b) => Box::new(find_bookmarks(a, cwd, cwd_overwrited)?.and(find_bookmarks(
-            b,
-            cwd,
-            cwd_overwrited,
-        )?)),
-        Parenthesized(a) => find_bookmarks(a, cwd, cwd_overwrited)?,
+        Or(a, b) => {
+            Box::new(find_bookmarks(a, cwd, overrides)?.or(find_bookmarks(b, cwd, overrides)?))
+        }
+        And(a, b) => {
+            Box::new(find_bookmarks(a, cwd, overrides)?.and(find_bookmarks(b, cwd, overrides)?))
+        }
+        Parenthesized(a) => find_bookmarks(a, cwd, overrides)?,
         Path(p) => {
             let target = p.to_string();
             let path = join_folder_path(cwd, &target);
-            *cwd_overwrited = true;
+            overrides.cwd = true;
             debug!(?path, ?cwd, ?target, "searching in path");
             if path == "/" {
                 Box::new(bookmarks::dsl::id.eq(bookmarks::dsl::id)) // always true, no side effects
@@ -137,44 +187,225 @@ fn find_bookmarks(
             Box::new(
                 bookmarks::dsl::title
                     .ilike(format!("%{k}%"))
-                    .or(bookmarks::dsl::url.ilike(format!("%{k}%"))),
+                    .or(bookmarks::dsl::url.ilike(format!("%{k}%")))
+                    .or(bookmarks::dsl::description
+                        .ilike(format!("%{k}%"))
+                        .assume_not_null()) // .assume_not_null() is a dirty patch, NULL is falsy in the filter
+                    .or(bookmarks::dsl::notes
+                        .ilike(format!("%{k}%"))
+                        .assume_not_null()),
             )
         }
+        Qualifier(k, v) => {
+            let (k, v) = (k.to_string(), v.trim().to_string());
+            if v.is_empty() {
+                return Err(CommonError::BearQL(BearQLError::EmptyQualifier(k)));
+            }
+            match k.as_str() {
+                "notes" => Box::new(
+                    bookmarks::dsl::notes
+                        .ilike(format!("%{v}%"))
+                        .assume_not_null(),
+                ),
+                "content" => Box::new(
+                    bookmarks::dsl::id.eq_any(
+                        bookmark_contents::table
+                            .filter(
+                                // the same expression as the index of contents
+                                diesel::dsl::sql::<Bool>(
+                                    "to_tsvector('english', bookmark_contents.content) @@ plainto_tsquery('english', ",
+                                )
+                                .bind::<diesel::sql_types::Text, _>(v)
+                                .sql(")"),
+                            )
+                            .select(bookmark_contents::bookmark_id),
+                    ),
+                ),
+                "site" => match find_bookmarks_on_site(&v) {
+                    Some(condition) => condition,
+                    None => {
+                        return Err(CommonError::BearQL(BearQLError::UnknownQualifier(format!(
+                            "{k}:{v}"
+                        ))));
+                    }
+                },
+                "is" => match v.as_str() {
+                    "pinned" => Box::new(bookmarks::dsl::pinned),
+                    "due" => Box::new(
+                        bookmarks::dsl::remind_at
+                            .le(diesel::dsl::now)
+                            .assume_not_null(),
+                    ),
+                    "unread" | "reading" | "done" | "archived" => {
+                        overrides.archived |= v == "archived";
+                        Box::new(bookmarks::dsl::reading_state.eq(v).assume_not_null())
+                    }
+                    "broken" => Box::new(
+                        bookmarks::dsl::id.eq_any(
+                            link_checks::table
+                                .filter(
+                                    link_checks::dsl::consecutive_failures
+                                        .ge(super::link_check::BROKEN_AFTER_FAILURES),
+                                )
+                                .select(link_checks::bookmark_id),
+                        ),
+                    ),
+                    _ => {
+                        return Err(CommonError::BearQL(BearQLError::UnknownQualifier(format!(
+                            "{k}:{v}"
+                        ))));
+                    }
+                },
+                _ => match k.strip_prefix("attr.").filter(|key| !key.is_empty()) {
+                    Some(key) => find_bookmarks_by_attribute(key, &v),
+                    None => return Err(CommonError::BearQL(BearQLError::UnknownQualifier(k))),
+                },
+            }
+        }
     })
 }
 
-/// Search bookmarks by paths, keywords, and tags.
+/// The order of searched bookmarks.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    /// The latest created first
+    #[default]
+    Newest,
+    /// The most frequently and recently visited first
+    Frecency,
+}
+
+/// Visits weighted by the recency of the last visit, buckets of days like the frecency of Firefox.
+const FRECENCY: &str = "bookmarks.visit_count * CASE \
+    WHEN bookmarks.last_visited_at > now() - interval '4 days' THEN 100 \
+    WHEN bookmarks.last_visited_at > now() - interval '14 days' THEN 70 \
+    WHEN bookmarks.last_visited_at > now() - interval '31 days' THEN 50 \
+    WHEN bookmarks.last_visited_at > now() - interval '90 days' THEN 30 \
+    ELSE 10 END";
+
+/// Search bookmarks by paths, keywords, and tags, in the given order. Pinned bookmarks float
+/// to the top in their manual order if `pinned_first`.
 pub async fn search_bookmarks(
     conn: &mut Connection,
     query: Option<&str>,
     cwd: Option<&str>,
+    sort: Sort,
+    pinned_first: bool,
     before: i32,
     limit: i64,
 ) -> Result<Vec<(Bookmark, Option<Folder>, Vec<Tag>)>, CommonError> {
+    search(conn, query, cwd, sort, pinned_first, before, limit, false).await
+}
+
+/// Search bookmarks in the trash by paths, keywords, and tags.
+pub async fn search_deleted_bookmarks(
+    conn: &mut Connection,
+    query: Option<&str>,
+    cwd: Option<&str>,
+    before: i32,
+    limit: i64,
+) -> Result<Vec<(Bookmark, Option<Folder>, Vec<Tag>)>, CommonError> {
+    search(conn, query, cwd, Sort::Newest, false, before, limit, true).await
+}
+
+/// Find ids of all bookmarks matching the query, in descending order.
+pub async fn find_bookmark_ids(
+    conn: &mut Connection,
+    query: Option<&str>,
+    cwd: Option<&str>,
+) -> Result<Vec<i32>, CommonError> {
     use super::schema::bookmarks;
 
     let mut builder = bookmarks::table
-        .select(Bookmark::as_select())
-        .distinct_on(bookmarks::id)
+        .select(bookmarks::id)
         .filter(bookmarks::dsl::deleted_at.is_null())
         .into_boxed();
+    for expression in filter_bookmarks(query, cwd, true)? {
+        builder = builder.filter(expression);
+    }
+
+    let ids = builder
+        .order_by(bookmarks::id.desc())
+        .load::<i32>(conn)
+        .await
+        .expect("Error loading bookmarks");
+    Ok(ids)
+}
 
-    let mut cwd_overwrited = false;
+type Filter = Box<dyn BoxableExpression<schema::bookmarks::table, Pg, SqlType = Bool>>;
+
+/// Build the filters of bookmarks by the query and the cwd. Archived bookmarks are excluded
+/// unless `is:archived` is queried, if `exclude_archived`.
+fn filter_bookmarks(
+    query: Option<&str>,
+    cwd: Option<&str>,
+    exclude_archived: bool,
+) -> Result<Vec<Filter>, CommonError> {
+    use super::reading::ReadingState;
+    use super::schema::bookmarks;
+    use diesel::PgExpressionMethods;
+
+    let mut filters = vec![];
+    let mut overrides = Overrides::default();
     if let Some(query) = query {
         let cwd = cwd.unwrap_or("/");
         let bump = bumpalo::Bump::new();
         let query = parse_query(query, &bump)?;
-        builder = builder.filter(find_bookmarks(&query, cwd, &mut cwd_overwrited)?)
+        filters.push(find_bookmarks(&query, cwd, &mut overrides)?);
+    }
+    if exclude_archived && !overrides.archived {
+        filters.push(Box::new(
+            bookmarks::dsl::reading_state.is_distinct_from(ReadingState::Archived.as_str()),
+        ));
     }
-    if !cwd_overwrited
+    if !overrides.cwd
         && let Some(cwd) = cwd
         && cwd != "/"
     {
-        let expression = if cwd == "//" {
+        let expression: Filter = if cwd == "//" {
             Box::new(bookmarks::dsl::folder_id.is_null()) // special syntax. search bookmarks which are not in any folder
         } else {
             find_bookmarks_in_path(cwd)?
         };
+        filters.push(expression);
+    }
+    Ok(filters)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn search(
+    conn: &mut Connection,
+    query: Option<&str>,
+    cwd: Option<&str>,
+    sort: Sort,
+    pinned_first: bool,
+    before: i32,
+    limit: i64,
+    deleted: bool,
+) -> Result<Vec<(Bookmark, Option<Folder>, Vec<Tag>)>, CommonError> {
+    use super::schema::bookmarks;
+    use diesel::dsl::sql;
+    use diesel::sql_types::Integer;
+
+    // Filters are subqueries without joins, so bookmarks are never duplicated
+    let mut builder = bookmarks::table.select(Bookmark::as_select()).into_boxed();
+    if pinned_first {
+        builder = builder
+            .then_order_by(bookmarks::pinned.desc())
+            .then_order_by(bookmarks::pin_order.asc().nulls_last());
+    }
+    builder = match sort {
+        Sort::Newest => builder.then_order_by(bookmarks::id.desc()),
+        Sort::Frecency => builder
+            .then_order_by(sql::<Integer>(FRECENCY).desc())
+            .then_order_by(bookmarks::id.desc()),
+    };
+    builder = if deleted {
+        builder.filter(bookmarks::dsl::deleted_at.is_not_null())
+    } else {
+        builder.filter(bookmarks::dsl::deleted_at.is_null())
+    };
+    for expression in filter_bookmarks(query, cwd, !deleted)? {
         builder = builder.filter(expression);
     }
 
@@ -183,7 +414,6 @@ pub async fn search_bookmarks(
     }
 
     let lst = builder
-        .order_by(bookmarks::id.desc())
         .limit(limit)
         .load::<Bookmark>(conn)
         .await
@@ -294,6 +524,7 @@ pub(crate) mod test {
         Path(String),
         Tag(String),
         Keyword(String),
+        Qualifier(String, String),
     }
 
     fn simplify_query(q: &bearmark_ql::Query) -> Query {
@@ -309,6 +540,7 @@ pub(crate) mod test {
             bearmark_ql::Query::Path(p) => Path(p.to_string()),
             bearmark_ql::Query::Tag(t) => Tag(t.to_string().trim_start_matches('#').to_string()),
             bearmark_ql::Query::Keyword(k) => Keyword(k.to_string()),
+            bearmark_ql::Query::Qualifier(k, v) => Qualifier(k.to_string(), v.to_string()),
         }
     }
 
@@ -327,6 +559,7 @@ pub(crate) mod test {
             ("//", Path("//".into())),
             (".//", Path(".//".into())),
             ("/blog/", Path("/blog/".into())),
+            ("notes:rust", Qualifier("notes".into(), "rust".into())),
             (
                 "title #rust",
                 And(
@@ -378,6 +611,9 @@ pub(crate) mod test {
                 NewBookmark {
                     title: "Weather".to_string(),
                     url: "https://weather.com".to_string(),
+                    notes: None,
+                    description: None,
+                    attributes: None,
                 },
                 vec!["weather", "forecast"],
             ),
@@ -385,6 +621,9 @@ pub(crate) mod test {
                 NewBookmark {
                     title: "News".to_string(),
                     url: "https://news.com".to_string(),
+                    notes: None,
+                    description: None,
+                    attributes: None,
                 },
                 vec!["news", "world"],
             ),
@@ -392,6 +631,9 @@ pub(crate) mod test {
                 NewBookmark {
                     title: "Sports".to_string(),
                     url: "https://sports.com".to_string(),
+                    notes: None,
+                    description: None,
+                    attributes: None,
                 },
                 vec!["sports", "football"],
             ),
@@ -399,6 +641,9 @@ pub(crate) mod test {
                 NewBookmark {
                     title: "Tech".to_string(),
                     url: "https://tech.com".to_string(),
+                    notes: None,
+                    description: None,
+                    attributes: None,
                 },
                 vec!["tech", "gadgets"],
             ),
@@ -406,6 +651,9 @@ pub(crate) mod test {
                 NewBookmark {
                     title: "Weather Global".to_string(),
                     url: "https://example.com".to_string(),
+                    notes: None,
+                    description: None,
+                    attributes: None,
                 },
                 vec!["weather", "global"],
             ),
@@ -413,6 +661,9 @@ pub(crate) mod test {
                 NewBookmark {
                     title: "Weather West".to_string(),
                     url: "https://example.com".to_string(),
+                    notes: None,
+                    description: None,
+                    attributes: None,
                 },
                 vec!["weather", "west"],
             ),
@@ -441,7 +692,7 @@ pub(crate) mod test {
         let mut conn = connection::establish().await;
         setup_searchable_bookmarks(&mut conn).await;
 
-        let rv = search_bookmarks(&mut conn, None, None, 0, 10).await;
+        let rv = search_bookmarks(&mut conn, None, None, Sort::Newest, false, 0, 10).await;
         info!(?rv, "searched bookmarks");
         let rv = rv.unwrap();
         assert!(
@@ -450,7 +701,8 @@ pub(crate) mod test {
             rv.len()
         );
 
-        let rv = search_bookmarks(&mut conn, Some("Weather"), None, 0, 10).await;
+        let rv =
+            search_bookmarks(&mut conn, Some("Weather"), None, Sort::Newest, false, 0, 10).await;
         info!(?rv, "searched bookmarks");
         let rv = rv.unwrap();
         assert!(
@@ -459,12 +711,22 @@ pub(crate) mod test {
             rv.len()
         );
 
-        let rv = search_bookmarks(&mut conn, Some("Weather"), None, 0, 2).await;
+        let rv =
+            search_bookmarks(&mut conn, Some("Weather"), None, Sort::Newest, false, 0, 2).await;
         info!(?rv, "searched bookmarks");
         let rv = rv.unwrap();
         assert!(rv.len() == 2, "Expected 2 bookmarks, got {}", rv.len());
 
-        let rv = search_bookmarks(&mut conn, Some("Weather"), None, rv[1].0.id, 2).await;
+        let rv = search_bookmarks(
+            &mut conn,
+            Some("Weather"),
+            None,
+            Sort::Newest,
+            false,
+            rv[1].0.id,
+            2,
+        )
+        .await;
         info!(?rv, "searched bookmarks");
         let rv = rv.unwrap();
         assert!(rv.len() == 1, "Expected 1 bookmarks, got {}", rv.len());
@@ -480,7 +742,8 @@ pub(crate) mod test {
         assert!(m.id > 0);
         assert!(m.deleted_at.is_none());
 
-        let result = search_bookmarks(&mut conn, Some(&title), None, 0, 1).await;
+        let result =
+            search_bookmarks(&mut conn, Some(&title), None, Sort::Newest, false, 0, 1).await;
         info!(?result, "searched");
         let result = result.unwrap();
         assert_eq!(result.len(), 1);
@@ -488,56 +751,417 @@ pub(crate) mod test {
         let count = delete_bookmarks(&mut conn, vec![m.id]).await;
         assert_eq!(count, 1);
 
-        let result = search_bookmarks(&mut conn, Some(&title), None, 0, 1).await;
+        let result =
+            search_bookmarks(&mut conn, Some(&title), None, Sort::Newest, false, 0, 1).await;
         info!(?result, "searched");
         let result = result.unwrap();
         assert_eq!(result.len(), 0);
     }
 
+    #[tokio::test]
+    async fn search_deleted_bookmarks_in_trash() {
+        let mut conn = connection::establish().await;
+        let new = rand_bookmark();
+        let title = new.title.clone();
+        let m = create_bookmark(&mut conn, &new).await;
+
+        let result = search_deleted_bookmarks(&mut conn, Some(&title), None, 0, 1).await;
+        info!(?result, "searched in trash");
+        assert_eq!(result.unwrap().len(), 0);
+
+        delete_bookmarks(&mut conn, vec![m.id]).await;
+
+        let result = search_deleted_bookmarks(&mut conn, Some(&title), None, 0, 1).await;
+        info!(?result, "searched in trash");
+        let result = result.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].0.id, m.id);
+    }
+
+    #[tokio::test]
+    async fn search_bookmarks_with_notes() {
+        let mut conn = connection::establish().await;
+        let (notes, description) = (rand_str(10), rand_str(10));
+        let m = create_bookmark(
+            &mut conn,
+            &NewBookmark {
+                notes: Some(format!("# Why\n\n{notes}")),
+                description: Some(description.clone()),
+                ..rand_bookmark()
+            },
+        )
+        .await;
+
+        for query in [notes.clone(), description, format!("notes:{notes}")] {
+            let rv =
+                search_bookmarks(&mut conn, Some(&query), None, Sort::Newest, false, 0, 10).await;
+            info!(?rv, ?query, "searched bookmarks with notes");
+            let rv = rv.unwrap();
+            assert_eq!(rv.len(), 1);
+            assert_eq!(rv[0].0.id, m.id);
+        }
+
+        let rv = search_bookmarks(
+            &mut conn,
+            Some(&format!("notes:{}", m.title)),
+            None,
+            Sort::Newest,
+            false,
+            0,
+            10,
+        )
+        .await;
+        assert!(rv.unwrap().is_empty());
+
+        for query in ["notes:", "unknown:value"] {
+            let rv =
+                search_bookmarks(&mut conn, Some(query), None, Sort::Newest, false, 0, 10).await;
+            info!(?rv, ?query, "searched bookmarks with bad qualifier");
+            assert!(matches!(rv, Err(CommonError::BearQL(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn search_bookmarks_by_frecency() {
+        use crate::db::bookmark::visit_bookmark;
+
+        let mut conn = connection::establish().await;
+        let keyword = rand_str(10);
+        let mut added = vec![];
+        for visits in [0, 1, 3, 5] {
+            let m = create_bookmark(
+                &mut conn,
+                &NewBookmark {
+                    title: format!("{keyword} {visits}"),
+                    ..rand_bookmark()
+                },
+            )
+            .await;
+            for _ in 0..visits {
+                visit_bookmark(&mut conn, m.id).await.unwrap();
+            }
+            added.push(m);
+        }
+        // the most visited one was visited long ago
+        diesel::update(bookmarks::table.find(added[3].id))
+            .set(
+                bookmarks::last_visited_at
+                    .eq(time::OffsetDateTime::now_utc() - time::Duration::days(100)),
+            )
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let rv = search_bookmarks(
+            &mut conn,
+            Some(&keyword),
+            None,
+            Sort::Frecency,
+            false,
+            0,
+            10,
+        )
+        .await
+        .unwrap();
+        let ids = rv.into_iter().map(|(m, _, _)| m.id).collect_vec();
+        assert_eq!(
+            ids,
+            vec![added[2].id, added[1].id, added[3].id, added[0].id]
+        );
+
+        let rv = search_bookmarks(&mut conn, Some(&keyword), None, Sort::Newest, false, 0, 10)
+            .await
+            .unwrap();
+        let ids = rv.into_iter().map(|(m, _, _)| m.id).collect_vec();
+        assert_eq!(ids, added.iter().rev().map(|m| m.id).collect_vec());
+    }
+
+    #[tokio::test]
+    async fn search_bookmarks_by_reading_state() {
+        use crate::db::reading::{ReadingState, set_reading_state};
+
+        let mut conn = connection::establish().await;
+        let keyword = rand_str(10);
+        let mut added = vec![];
+        for state in [ReadingState::Unread, ReadingState::Archived] {
+            let m = create_bookmark(
+                &mut conn,
+                &NewBookmark {
+                    title: format!("{keyword} {}", state.as_str()),
+                    ..rand_bookmark()
+                },
+            )
+            .await;
+            set_reading_state(&mut conn, m.id, Some(state))
+                .await
+                .unwrap();
+            added.push(m);
+        }
+
+        for (query, expected) in [
+            // archived bookmarks are excluded by default
+            (keyword.clone(), vec![added[0].id]),
+            (format!("{keyword} is:unread"), vec![added[0].id]),
+            (format!("{keyword} is:archived"), vec![added[1].id]),
+            (format!("{keyword} is:done"), vec![]),
+        ] {
+            let rv = search_bookmarks(&mut conn, Some(&query), None, Sort::Newest, false, 0, 10)
+                .await
+                .unwrap();
+            let ids = rv.into_iter().map(|(m, _, _)| m.id).collect_vec();
+            assert_eq!(ids, expected, "{query}");
+        }
+
+        let rv = search_bookmarks(
+            &mut conn,
+            Some("is:unknown"),
+            None,
+            Sort::Newest,
+            false,
+            0,
+            10,
+        )
+        .await;
+        assert!(matches!(rv, Err(CommonError::BearQL(_))));
+    }
+
+    #[tokio::test]
+    async fn search_due_bookmarks() {
+        use crate::db::bookmark::{ModifyBookmark, update_bookmark};
+        use time::{Duration, OffsetDateTime};
+
+        let mut conn = connection::establish().await;
+        let keyword = rand_str(10);
+        let mut added = vec![];
+        for days in [-1, 1] {
+            let m = create_bookmark(
+                &mut conn,
+                &NewBookmark {
+                    title: format!("{keyword} {days}"),
+                    ..rand_bookmark()
+                },
+            )
+            .await;
+            update_bookmark(
+                &mut conn,
+                m.id,
+                ModifyBookmark {
+                    title: None,
+                    url: None,
+                    notes: None,
+                    description: None,
+                    pinned: None,
+                    pin_order: None,
+                    remind_at: Some(Some(OffsetDateTime::now_utc() + Duration::days(days))),
+                    attributes: None,
+                },
+            )
+            .await;
+            added.push(m);
+        }
+
+        let query = format!("{keyword} is:due");
+        let rv = search_bookmarks(&mut conn, Some(&query), None, Sort::Newest, false, 0, 10)
+            .await
+            .unwrap();
+        let ids = rv.into_iter().map(|(m, _, _)| m.id).collect_vec();
+        assert_eq!(ids, vec![added[0].id]);
+    }
+
+    #[tokio::test]
+    async fn search_bookmarks_by_attributes() {
+        use serde_json::json;
+
+        let mut conn = connection::establish().await;
+        let keyword = rand_str(10);
+        let mut added = vec![];
+        for attributes in [
+            json!({"owner": "alice", "priority": 1}),
+            json!({"owner": "bob", "priority": "1"}),
+            json!({}),
+        ] {
+            let m = create_bookmark(
+                &mut conn,
+                &NewBookmark {
+                    title: keyword.clone(),
+                    attributes: Some(attributes),
+                    ..rand_bookmark()
+                },
+            )
+            .await;
+            added.push(m);
+        }
+
+        for (query, expected) in [
+            (format!("{keyword} attr.owner:alice"), vec![added[0].id]),
+            (
+                format!("{keyword} attr.owner:*"),
+                vec![added[1].id, added[0].id],
+            ),
+            (
+                format!("{keyword} attr.priority:1"),
+                vec![added[1].id, added[0].id],
+            ),
+            (format!("{keyword} attr.unknown:*"), vec![]),
+            (
+                format!("{keyword} (attr.owner:bob | attr.owner:alice)"),
+                vec![added[1].id, added[0].id],
+            ),
+        ] {
+            let rv = search_bookmarks(&mut conn, Some(&query), None, Sort::Newest, false, 0, 10)
+                .await
+                .unwrap();
+            let ids = rv.into_iter().map(|(m, _, _)| m.id).collect_vec();
+            assert_eq!(ids, expected, "{query}");
+        }
+
+        let rv = search_bookmarks(
+            &mut conn,
+            Some("attr.:alice"),
+            None,
+            Sort::Newest,
+            false,
+            0,
+            10,
+        )
+        .await;
+        assert!(matches!(rv, Err(CommonError::BearQL(_))));
+    }
+
+    #[tokio::test]
+    async fn search_bookmarks_with_content() {
+        use crate::db::content::save_content;
+
+        let mut conn = connection::establish().await;
+        let word = rand_str(10);
+        let m = create_bookmark(&mut conn, &rand_bookmark()).await;
+        save_content(
+            &mut conn,
+            m.id,
+            &format!("The {word} bears were running in the woods."),
+        )
+        .await;
+
+        // words are stemmed
+        for query in [
+            format!("content:{word}"),
+            format!("content:{word} content:runs"),
+        ] {
+            let rv =
+                search_bookmarks(&mut conn, Some(&query), None, Sort::Newest, false, 0, 10).await;
+            info!(?rv, ?query, "searched bookmarks with content");
+            let rv = rv.unwrap();
+            assert_eq!(rv.len(), 1);
+            assert_eq!(rv[0].0.id, m.id);
+        }
+
+        let rv = search_bookmarks(&mut conn, Some(&word), None, Sort::Newest, false, 0, 10).await;
+        assert!(rv.unwrap().is_empty());
+        let query = format!("content:{word} content:{}", rand_str(10));
+        let rv = search_bookmarks(&mut conn, Some(&query), None, Sort::Newest, false, 0, 10).await;
+        assert!(rv.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_search_bookmarks_with_tags() {
         let mut conn = connection::establish().await;
         setup_searchable_bookmarks(&mut conn).await;
 
-        let rv = search_bookmarks(&mut conn, Some("Weather"), None, 0, 10).await;
+        let rv =
+            search_bookmarks(&mut conn, Some("Weather"), None, Sort::Newest, false, 0, 10).await;
         info!(?rv, "searched bookmarks");
         let rv = rv.unwrap();
         assert_eq!(rv.len(), 3);
 
-        let rv = search_bookmarks(&mut conn, Some("Weather #global"), None, 0, 10).await;
+        let rv = search_bookmarks(
+            &mut conn,
+            Some("Weather #global"),
+            None,
+            Sort::Newest,
+            false,
+            0,
+            10,
+        )
+        .await;
         info!(?rv, "searched bookmarks with tag");
         let rv = rv.unwrap();
         assert_eq!(rv.len(), 1);
 
-        let rv = search_bookmarks(&mut conn, Some("Weather #west"), None, 0, 10).await;
+        let rv = search_bookmarks(
+            &mut conn,
+            Some("Weather #west"),
+            None,
+            Sort::Newest,
+            false,
+            0,
+            10,
+        )
+        .await;
         info!(?rv, "searched bookmarks with tag");
         let rv = rv.unwrap();
         assert_eq!(rv.len(), 1);
 
-        let rv = search_bookmarks(&mut conn, Some("#weather #global"), None, 0, 10).await;
+        let rv = search_bookmarks(
+            &mut conn,
+            Some("#weather #global"),
+            None,
+            Sort::Newest,
+            false,
+            0,
+            10,
+        )
+        .await;
         info!(?rv, "searched bookmarks with tag");
         let rv = rv.unwrap();
         assert_eq!(rv.len(), 1);
 
-        let rv = search_bookmarks(&mut conn, Some("Weather #west #global"), None, 0, 10).await;
+        let rv = search_bookmarks(
+            &mut conn,
+            Some("Weather #west #global"),
+            None,
+            Sort::Newest,
+            false,
+            0,
+            10,
+        )
+        .await;
         info!(?rv, "searched bookmarks with tag");
         let rv = rv.unwrap();
         assert_eq!(rv.len(), 0);
 
-        let rv = search_bookmarks(&mut conn, Some("#weather"), None, 0, 10).await;
+        let rv = search_bookmarks(
+            &mut conn,
+            Some("#weather"),
+            None,
+            Sort::Newest,
+            false,
+            0,
+            10,
+        )
+        .await;
         info!(?rv, "searched bookmarks with tag");
         let rv = rv.unwrap();
         assert_eq!(rv.len(), 3);
 
         info!("search bookmarks with pagination, limit first");
 
-        let rv = search_bookmarks(&mut conn, Some("#weather"), None, 0, 1).await;
+        let rv =
+            search_bookmarks(&mut conn, Some("#weather"), None, Sort::Newest, false, 0, 1).await;
         info!(?rv, "searched bookmarks with tag");
         let rv = rv.unwrap();
         assert_eq!(rv.len(), 1);
 
         info!("search bookmarks with pagination, paginated by cursor");
-        let rv = search_bookmarks(&mut conn, Some("#weather"), None, rv[0].0.id, 3).await;
+        let rv = search_bookmarks(
+            &mut conn,
+            Some("#weather"),
+            None,
+            Sort::Newest,
+            false,
+            rv[0].0.id,
+            3,
+        )
+        .await;
         info!(?rv, "searched bookmarks with tag");
         let rv = rv.unwrap();
         assert_eq!(rv.len(), 2);
@@ -564,7 +1188,7 @@ pub(crate) mod test {
             let mut conn = connection::establish().await;
             let query: Option<&str> = $query;
             let cwd: Option<&str> = $cwd;
-            let rv = search_bookmarks(&mut conn, query, cwd, 0, 100).await;
+            let rv = search_bookmarks(&mut conn, query, cwd, Sort::Newest, false, 0, 100).await;
             info!(?query, ?cwd, ?rv, "searched bookmarks");
             let rv = rv.unwrap();
             assert_eq!(rv.len(), $expected_size);
@@ -647,12 +1271,21 @@ pub(crate) mod test {
         } = setup_folders_and_bookmarks_default(&mut conn).await;
 
         let query = format!("{folder1_path} | {folder2_path}");
-        let rv = search_bookmarks(&mut conn, Some(&query), None, 0, 5).await;
+        let rv = search_bookmarks(&mut conn, Some(&query), None, Sort::Newest, false, 0, 5).await;
         info!(?query, ?rv, "searched bookmarks");
         let rv = rv.unwrap();
         assert_eq!(rv.len(), 5);
 
-        let rv = search_bookmarks(&mut conn, Some(&query), None, rv[4].0.id, 100).await;
+        let rv = search_bookmarks(
+            &mut conn,
+            Some(&query),
+            None,
+            Sort::Newest,
+            false,
+            rv[4].0.id,
+            100,
+        )
+        .await;
         info!(?query, ?rv, "searched bookmarks");
         let rv = rv.unwrap();
         assert_eq!(rv.len(), 7);
@@ -676,7 +1309,7 @@ pub(crate) mod test {
     #[tokio::test]
     async fn search_bookmarks_with_invalid_cwd() {
         let mut conn = connection::establish().await;
-        let rv = search_bookmarks(&mut conn, None, Some("///"), 0, 100).await;
+        let rv = search_bookmarks(&mut conn, None, Some("///"), Sort::Newest, false, 0, 100).await;
         info!(?rv, "searched bookmarks");
         assert!(rv.is_err());
         let rv = rv.unwrap_err();
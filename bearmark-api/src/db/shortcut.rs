@@ -0,0 +1,93 @@
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection as Connection, RunQueryDsl};
+
+use super::bookmark::Bookmark;
+use super::schema::bookmarks;
+use crate::utils::DatabaseError;
+
+/// Get the bookmark which is not deleted by its shortcut.
+pub async fn get_by_shortcut(conn: &mut Connection, shortcut: &str) -> Option<Bookmark> {
+    bookmarks::table
+        .filter(
+            bookmarks::shortcut
+                .eq(shortcut)
+                .and(bookmarks::deleted_at.is_null()),
+        )
+        .first(conn)
+        .await
+        .optional()
+        .expect("Error loading bookmark by shortcut")
+}
+
+/// Set or clear the shortcut of the bookmark, which must be unique among bookmarks not deleted.
+pub async fn set_shortcut(
+    conn: &mut Connection,
+    id: i32,
+    shortcut: Option<&str>,
+) -> Result<Option<Bookmark>, DatabaseError> {
+    use diesel::dsl::now;
+
+    diesel::update(bookmarks::table.find(id))
+        .set((
+            bookmarks::shortcut.eq(shortcut),
+            bookmarks::updated_at.eq(now),
+        ))
+        .returning(Bookmark::as_returning())
+        .get_result(conn)
+        .await
+        .optional()
+        .map_err(|e| match e {
+            diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                _,
+            ) => DatabaseError::DuplicationError {
+                table: "bookmarks".to_string(),
+            },
+            _ => panic!("Unexpected error: {e:?}"),
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::bookmark::test::create_rand_bookmark;
+    use crate::db::bookmark::{delete_bookmarks, restore_bookmarks};
+    use crate::db::connection;
+    use crate::utils::rand::rand_str;
+
+    #[tokio::test]
+    async fn unique_shortcuts() {
+        let mut conn = connection::establish().await;
+        let (m1, m2) = (
+            create_rand_bookmark(&mut conn).await,
+            create_rand_bookmark(&mut conn).await,
+        );
+        let shortcut = rand_str(8);
+
+        let m = set_shortcut(&mut conn, m1.id, Some(&shortcut))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(m.shortcut.as_deref(), Some(shortcut.as_str()));
+        let found = get_by_shortcut(&mut conn, &shortcut).await.unwrap();
+        assert_eq!(found.id, m1.id);
+
+        let rv = set_shortcut(&mut conn, m2.id, Some(&shortcut)).await;
+        assert!(matches!(rv, Err(DatabaseError::DuplicationError { .. })));
+
+        // the shortcut is released to others once in the trash
+        delete_bookmarks(&mut conn, vec![m1.id]).await;
+        assert!(get_by_shortcut(&mut conn, &shortcut).await.is_none());
+        assert!(
+            set_shortcut(&mut conn, m2.id, Some(&shortcut))
+                .await
+                .is_ok()
+        );
+        let rv = restore_bookmarks(&mut conn, vec![m1.id]).await;
+        assert!(matches!(rv, Err(DatabaseError::DuplicationError { .. })));
+
+        let m = set_shortcut(&mut conn, m1.id, None).await.unwrap().unwrap();
+        assert!(m.shortcut.is_none());
+        assert_eq!(restore_bookmarks(&mut conn, vec![m1.id]).await.unwrap(), 1);
+    }
+}
@@ -2,7 +2,7 @@ use diesel::prelude::*;
 use diesel_async::{AsyncPgConnection as Connection, RunQueryDsl};
 use rocket::serde::{Deserialize, Serialize};
 
-use super::bookmark::Bookmark;
+use super::bookmark::{Bookmark, touch_bookmarks};
 use super::schema::{bookmarks_tags, tags};
 
 #[derive(Queryable, Selectable, Identifiable, Debug, Deserialize, Serialize)]
@@ -40,6 +40,30 @@ pub struct ModifyTag {
     pub name: Option<String>,
 }
 
+impl Tag {
+    /// Get the tag, locking it until the end of the transaction.
+    pub async fn get_for_update(conn: &mut Connection, id: i32) -> Option<Self> {
+        tags::table
+            .find(id)
+            .for_update()
+            .first(conn)
+            .await
+            .optional()
+            .expect("Error loading tag")
+    }
+}
+
+/// Get ids of bookmarks tagged with any of the tags.
+async fn get_tagged_bookmark_ids(conn: &mut Connection, tag_ids: &[i32]) -> Vec<i32> {
+    bookmarks_tags::table
+        .filter(bookmarks_tags::tag_id.eq_any(tag_ids))
+        .select(bookmarks_tags::bookmark_id)
+        .distinct()
+        .load(conn)
+        .await
+        .expect("Error loading tagged bookmarks")
+}
+
 pub async fn get_tags(conn: &mut Connection, tags: &[String]) -> Vec<Tag> {
     tags::table
         .filter(tags::name.eq_any(tags))
@@ -90,6 +114,61 @@ pub async fn update_bookmark_tags(conn: &mut Connection, bookmark: &Bookmark, ta
         .execute(conn)
         .await
         .expect("Error updating bookmark tags");
+    touch_bookmarks(conn, &[bookmark.id]).await;
+}
+
+/// Add tags to bookmarks, keeping their existing tags.
+pub async fn add_bookmarks_tags(
+    conn: &mut Connection,
+    bookmark_ids: &[i32],
+    tags: &[String],
+) -> usize {
+    if bookmark_ids.is_empty() || tags.is_empty() {
+        return 0;
+    }
+    let tags = get_or_create_tags(conn, tags).await;
+    let bookmark_tags = bookmark_ids
+        .iter()
+        .flat_map(|bookmark_id| {
+            tags.iter().map(|tag| BookmarkTag {
+                bookmark_id: *bookmark_id,
+                tag_id: tag.id,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let added = diesel::insert_into(bookmarks_tags::table)
+        .values(&bookmark_tags)
+        .on_conflict_do_nothing()
+        .returning(bookmarks_tags::bookmark_id)
+        .get_results::<i32>(conn)
+        .await
+        .expect("Error adding bookmark tags");
+    touch_bookmarks(conn, &added).await;
+    added.len()
+}
+
+/// Remove tags from bookmarks, keeping their other tags.
+pub async fn remove_bookmarks_tags(
+    conn: &mut Connection,
+    bookmark_ids: &[i32],
+    tags: &[String],
+) -> usize {
+    if bookmark_ids.is_empty() || tags.is_empty() {
+        return 0;
+    }
+    let removed = diesel::delete(bookmarks_tags::table)
+        .filter(bookmarks_tags::bookmark_id.eq_any(bookmark_ids))
+        .filter(
+            bookmarks_tags::tag_id
+                .eq_any(tags::table.filter(tags::name.eq_any(tags)).select(tags::id)),
+        )
+        .returning(bookmarks_tags::bookmark_id)
+        .get_results::<i32>(conn)
+        .await
+        .expect("Error removing bookmark tags");
+    touch_bookmarks(conn, &removed).await;
+    removed.len()
 }
 
 pub async fn search_tags(
@@ -120,6 +199,8 @@ pub async fn delete_tags(conn: &mut Connection, ids: Vec<i32>) -> usize {
     if ids.is_empty() {
         return 0;
     }
+    let tagged = get_tagged_bookmark_ids(conn, &ids).await;
+    touch_bookmarks(conn, &tagged).await;
     diesel::delete(tags::table)
         .filter(tags::dsl::id.eq_any(ids))
         .execute(conn)
@@ -130,13 +211,18 @@ pub async fn delete_tags(conn: &mut Connection, ids: Vec<i32>) -> usize {
 pub async fn update_tag(conn: &mut Connection, id: i32, modified: ModifyTag) -> Option<Tag> {
     use diesel::{ExpressionMethods, dsl::now};
 
-    diesel::update(tags::table.find(id))
+    let rv = diesel::update(tags::table.find(id))
         .set((&modified, tags::updated_at.eq(now)))
         .returning(Tag::as_returning())
         .get_result(conn)
         .await
         .optional()
-        .expect("Error updating tag")
+        .expect("Error updating tag");
+    if rv.is_some() && modified.name.is_some() {
+        let tagged = get_tagged_bookmark_ids(conn, &[id]).await;
+        touch_bookmarks(conn, &tagged).await;
+    }
+    rv
 }
 
 #[cfg(test)]
@@ -227,6 +313,31 @@ pub mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_add_and_remove_bookmarks_tags() {
+        let mut conn = connection::establish().await;
+
+        let b1 = create_rand_bookmark(&mut conn).await;
+        let b2 = create_rand_bookmark(&mut conn).await;
+        let (t1, t2, t3) = (rand_str(4), rand_str(4), rand_str(4));
+        update_bookmark_tags(&mut conn, &b1, std::slice::from_ref(&t1)).await;
+
+        let added = add_bookmarks_tags(&mut conn, &[b1.id, b2.id], &[t1.clone(), t2.clone()]).await;
+        assert_eq!(added, 3);
+
+        let removed = remove_bookmarks_tags(&mut conn, &[b1.id, b2.id], &[t1.clone(), t3]).await;
+        assert_eq!(removed, 2);
+
+        let bookmarks_tags = get_tags_per_bookmark(&mut conn, vec![b1, b2]).await;
+        for (bookmark, tags) in bookmarks_tags {
+            info!(?bookmark, ?tags, "bookmark has tags");
+            assert_eq!(
+                tags.into_iter().map(|t| t.name).collect_vec(),
+                vec![t2.clone()]
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_search_tags() {
         let mut conn = connection::establish().await;
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use scraper::{Html, Selector};
+use url::Url;
+
+/// Metadata of a web page, read from the `<head>` of its HTML.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PageMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub site_name: Option<String>,
+    pub favicon_url: Option<String>,
+}
+
+fn normalize_text(text: &str) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+impl PageMetadata {
+    /// Parse the metadata of the HTML, relative URLs are resolved against the base.
+    pub fn parse(html: &str, base: &Url) -> Self {
+        let doc = Html::parse_document(html);
+
+        // Open Graph uses `property`, others use `name`. The first one wins.
+        let mut metas = HashMap::new();
+        for el in doc.select(&Selector::parse("meta[content]").unwrap()) {
+            let el = el.value();
+            let (Some(key), Some(content)) = (
+                el.attr("property").or_else(|| el.attr("name")),
+                el.attr("content").and_then(normalize_text),
+            ) else {
+                continue;
+            };
+            metas.entry(key.to_lowercase()).or_insert(content);
+        }
+        let meta = |keys: &[&str]| keys.iter().find_map(|k| metas.get(*k).cloned());
+        let resolve = |href: String| base.join(&href).ok().map(|u| u.to_string());
+
+        let title = meta(&["og:title", "twitter:title"]).or_else(|| {
+            doc.select(&Selector::parse("title").unwrap())
+                .next()
+                .and_then(|el| normalize_text(&el.text().collect::<String>()))
+        });
+        let favicon_url = doc
+            .select(&Selector::parse("link[rel][href]").unwrap())
+            .find(|el| {
+                el.value()
+                    .attr("rel")
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case("icon"))
+            })
+            .and_then(|el| el.value().attr("href"))
+            .unwrap_or("/favicon.ico")
+            .to_string();
+
+        Self {
+            title,
+            description: meta(&["og:description", "description", "twitter:description"]),
+            image_url: meta(&["og:image", "twitter:image"]).and_then(resolve),
+            site_name: meta(&["og:site_name"]),
+            favicon_url: resolve(favicon_url),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_metadata() {
+        let base = Url::parse("https://example.com/blog/post").unwrap();
+        let html = r#"<!DOCTYPE html>
+            <html><head>
+                <title>
+                    Post   Title
+                </title>
+                <meta name="description" content="A post">
+                <meta property="og:site_name" content="Example">
+                <meta property="og:image" content="/cover.png">
+                <link rel="shortcut icon" href="icons/favicon.png">
+            </head><body><h1>Post</h1></body></html>"#;
+        assert_eq!(
+            PageMetadata::parse(html, &base),
+            PageMetadata {
+                title: Some("Post Title".to_string()),
+                description: Some("A post".to_string()),
+                image_url: Some("https://example.com/cover.png".to_string()),
+                site_name: Some("Example".to_string()),
+                favicon_url: Some("https://example.com/blog/icons/favicon.png".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_open_graph_first() {
+        let base = Url::parse("https://example.com/").unwrap();
+        let html = r#"<title>Title</title>
+            <meta property="og:title" content="OG Title">
+            <meta property="og:description" content="OG description">
+            <meta name="description" content="description">
+            <meta name="og:empty" content=" ">"#;
+        let rv = PageMetadata::parse(html, &base);
+        assert_eq!(rv.title.as_deref(), Some("OG Title"));
+        assert_eq!(rv.description.as_deref(), Some("OG description"));
+        assert_eq!(rv.image_url, None);
+        assert_eq!(
+            rv.favicon_url.as_deref(),
+            Some("https://example.com/favicon.ico")
+        );
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        let base = Url::parse("https://example.com/").unwrap();
+        let rv = PageMetadata::parse("not html at all", &base);
+        assert_eq!(rv.title, None);
+        assert_eq!(rv.description, None);
+    }
+}
@@ -0,0 +1,381 @@
+mod html;
+mod readable;
+
+pub use html::PageMetadata;
+pub use readable::extract_readable_text;
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use url::{Host, Url};
+
+use crate::api::configs::FetcherConfig;
+use crate::utils::FetchError;
+
+/// A fetched web page, whose body is truncated to the size limit.
+#[derive(Debug)]
+pub struct Page {
+    pub status: u16,
+    /// The URL after redirects
+    pub url: Url,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl Page {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn text(&self) -> std::borrow::Cow<'_, str> {
+        String::from_utf8_lossy(&self.body)
+    }
+
+    pub fn is_html(&self) -> bool {
+        self.content_type
+            .as_deref()
+            .is_none_or(|t| t.to_lowercase().contains("html"))
+    }
+}
+
+/// The HTTP client of the fetcher, which refuses to request loopback, private and link-local
+/// addresses unless they are allowed.
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    allow_private_addresses: bool,
+}
+
+pub fn client(config: &FetcherConfig) -> Client {
+    let mut builder = reqwest::Client::builder()
+        .user_agent(&config.user_agent)
+        .timeout(Duration::from_secs(config.timeout_secs));
+    if !config.allow_private_addresses {
+        builder = builder.dns_resolver(Arc::new(PublicResolver)).redirect(
+            reqwest::redirect::Policy::custom(|attempt| {
+                if is_private_host(attempt.url()) {
+                    let url = attempt.url().to_string();
+                    attempt.error(FetchError::PrivateAddress(url))
+                } else {
+                    reqwest::redirect::Policy::default().redirect(attempt)
+                }
+            }),
+        );
+    }
+    Client {
+        http: builder.build().expect("Error building HTTP client"),
+        allow_private_addresses: config.allow_private_addresses,
+    }
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified())
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local()
+                    || ip.is_unspecified())
+            }
+        },
+    }
+}
+
+/// Whether the host of the URL is a non-public IP address, the domains are checked on resolving.
+fn is_private_host(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Ipv4(ip)) => !is_public_ip(IpAddr::V4(ip)),
+        Some(Host::Ipv6(ip)) => !is_public_ip(IpAddr::V6(ip)),
+        _ => false,
+    }
+}
+
+/// Resolves domains to their public addresses only.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect::<Vec<_>>();
+            if addrs.is_empty() {
+                return Err(FetchError::PrivateAddress(name.as_str().to_string()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+impl Client {
+    fn parse_url(&self, url: &str) -> Result<Url, FetchError> {
+        let parsed = Url::parse(url)
+            .ok()
+            .filter(|u| matches!(u.scheme(), "http" | "https"))
+            .ok_or_else(|| FetchError::InvalidUrl(url.to_string()))?;
+        if !self.allow_private_addresses && is_private_host(&parsed) {
+            return Err(FetchError::PrivateAddress(url.to_string()));
+        }
+        Ok(parsed)
+    }
+}
+
+/// Fetch the page by GET, reading at most `max_bytes` of the body.
+pub async fn fetch_page(client: &Client, url: &str, max_bytes: usize) -> Result<Page, FetchError> {
+    let url = client.parse_url(url)?;
+
+    let mut response = client.http.get(url).send().await?;
+    let status = response.status().as_u16();
+    let url = response.url().clone();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+
+    let mut body = Vec::new();
+    while body.len() < max_bytes
+        && let Some(chunk) = response.chunk().await?
+    {
+        body.extend_from_slice(&chunk);
+    }
+    body.truncate(max_bytes);
+
+    Ok(Page {
+        status,
+        url,
+        content_type,
+        body,
+    })
+}
+
+/// Check the link by HEAD, falling back to GET if HEAD is failed or not allowed.
+///
+/// Returns the status code, and the URL redirected to if any.
+pub async fn check_link(client: &Client, url: &str) -> Result<(u16, Option<Url>), FetchError> {
+    let url = client.parse_url(url)?;
+    let redirected =
+        |response: &reqwest::Response| (response.url() != &url).then(|| response.url().clone());
+
+    if let Ok(response) = client.http.head(url.clone()).send().await
+        && response.status().as_u16() < 400
+    {
+        return Ok((response.status().as_u16(), redirected(&response)));
+    }
+    let response = client.http.get(url.clone()).send().await?;
+    Ok((response.status().as_u16(), redirected(&response)))
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// A canned response of the fixture server.
+    #[derive(Debug, Clone)]
+    pub struct Fixture {
+        pub status: u16,
+        pub headers: Vec<(&'static str, String)>,
+        pub body: String,
+        pub delay: Duration,
+    }
+
+    impl Fixture {
+        pub fn html(body: &str) -> Self {
+            Self {
+                status: 200,
+                headers: vec![("Content-Type", "text/html; charset=utf-8".to_string())],
+                body: body.to_string(),
+                delay: Duration::ZERO,
+            }
+        }
+
+        pub fn status(status: u16) -> Self {
+            Self {
+                status,
+                headers: vec![],
+                body: String::new(),
+                delay: Duration::ZERO,
+            }
+        }
+
+        pub fn redirect(location: &str) -> Self {
+            Self {
+                headers: vec![("Location", location.to_string())],
+                ..Self::status(301)
+            }
+        }
+    }
+
+    /// Serve the fixtures by path on a random local port, returns the base URL.
+    ///
+    /// A fixture keyed by `METHOD /path` takes precedence over the one keyed by `/path`.
+    /// Paths without a fixture are responded with 404.
+    pub async fn serve(routes: Vec<(&'static str, Fixture)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let routes = Arc::new(routes);
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let routes = routes.clone();
+                tokio::spawn(async move {
+                    let mut buf = vec![0; 8192];
+                    let mut n = 0;
+                    while !buf[..n].windows(4).any(|w| w == b"\r\n\r\n") {
+                        match stream.read(&mut buf[n..]).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(m) => n += m,
+                        }
+                    }
+                    let request = String::from_utf8_lossy(&buf[..n]);
+                    let mut parts = request.split_whitespace();
+                    let (method, path) =
+                        (parts.next().unwrap_or("GET"), parts.next().unwrap_or("/"));
+
+                    let fixture = [format!("{method} {path}"), path.to_string()]
+                        .iter()
+                        .find_map(|key| routes.iter().find(|(p, _)| p == key))
+                        .map(|(_, f)| f.clone())
+                        .unwrap_or_else(|| Fixture::status(404));
+                    tokio::time::sleep(fixture.delay).await;
+
+                    let mut response = format!("HTTP/1.1 {} Fixture\r\n", fixture.status);
+                    for (k, v) in &fixture.headers {
+                        response.push_str(&format!("{k}: {v}\r\n"));
+                    }
+                    response.push_str(&format!(
+                        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+                        fixture.body.len()
+                    ));
+                    if method != "HEAD" {
+                        response.push_str(&fixture.body);
+                    }
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+
+        format!("http://{addr}")
+    }
+
+    fn test_client(timeout_secs: u64) -> Client {
+        client(&FetcherConfig {
+            timeout_secs,
+            allow_private_addresses: true,
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn fetch_pages() {
+        let base = serve(vec![
+            ("/page", Fixture::html("<title>Page</title>")),
+            ("/moved", Fixture::redirect("/page")),
+            ("/large", Fixture::html(&"a".repeat(100))),
+            (
+                "/slow",
+                Fixture {
+                    delay: Duration::from_secs(3),
+                    ..Fixture::html("slow")
+                },
+            ),
+        ])
+        .await;
+        let client = test_client(1);
+
+        let page = fetch_page(&client, &format!("{base}/page"), 1024)
+            .await
+            .unwrap();
+        assert!(page.is_success());
+        assert!(page.is_html());
+        assert_eq!(page.text(), "<title>Page</title>");
+
+        let page = fetch_page(&client, &format!("{base}/moved"), 1024)
+            .await
+            .unwrap();
+        assert!(page.is_success());
+        assert_eq!(page.url.as_str(), format!("{base}/page"));
+
+        let page = fetch_page(&client, &format!("{base}/large"), 10)
+            .await
+            .unwrap();
+        assert_eq!(page.text(), "a".repeat(10));
+
+        let page = fetch_page(&client, &format!("{base}/missing"), 1024)
+            .await
+            .unwrap();
+        assert_eq!(page.status, 404);
+        assert!(!page.is_success());
+
+        let rv = fetch_page(&client, &format!("{base}/slow"), 1024).await;
+        assert!(matches!(rv, Err(FetchError::Request(_))));
+
+        let rv = fetch_page(&client, "mailto:someone@example.com", 1024).await;
+        assert!(matches!(rv, Err(FetchError::InvalidUrl(_))));
+    }
+
+    #[tokio::test]
+    async fn refuse_private_addresses() {
+        let base = serve(vec![("/page", Fixture::html("private"))]).await;
+        let client = client(&FetcherConfig::default());
+
+        for url in [
+            format!("{base}/page"),
+            "http://[::1]/".to_string(),
+            "http://169.254.169.254/latest/meta-data/".to_string(),
+            "http://[::ffff:10.0.0.1]/".to_string(),
+        ] {
+            let rv = fetch_page(&client, &url, 1024).await;
+            assert!(matches!(rv, Err(FetchError::PrivateAddress(_))), "{url}");
+        }
+        // resolved to the loopback address
+        let url = base.replace("127.0.0.1", "localhost");
+        let rv = fetch_page(&client, &format!("{url}/page"), 1024).await;
+        assert!(matches!(rv, Err(FetchError::Request(_))));
+        let rv = check_link(&client, &format!("{url}/page")).await;
+        assert!(matches!(rv, Err(FetchError::Request(_))));
+
+        assert!(is_public_ip("93.184.215.14".parse().unwrap()));
+        assert!(!is_public_ip("192.168.1.1".parse().unwrap()));
+        assert!(!is_public_ip("fd00::1".parse().unwrap()));
+        assert!(!is_public_ip("fe80::1".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn check_links() {
+        let base = serve(vec![
+            ("/ok", Fixture::status(200)),
+            ("/moved", Fixture::redirect("/ok")),
+            ("HEAD /no-head", Fixture::status(405)),
+            ("/no-head", Fixture::html("ok")),
+        ])
+        .await;
+        let client = test_client(1);
+
+        for (path, status, redirected) in [
+            ("/ok", 200, None),
+            ("/moved", 200, Some(format!("{base}/ok"))),
+            ("/no-head", 200, None),
+            ("/missing", 404, None),
+        ] {
+            let (code, url) = check_link(&client, &format!("{base}{path}")).await.unwrap();
+            assert_eq!(code, status, "{path}");
+            assert_eq!(url.map(|u| u.to_string()), redirected, "{path}");
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use scraper::{ElementRef, Html, Node, Selector};
+
+/// Elements which are never part of the main content.
+const SKIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "nav", "header", "footer", "aside", "form",
+    "button", "select", "iframe", "svg", "canvas",
+];
+
+/// Tokens of `class` or `id` marking navigation, ads, comments, etc.
+const BOILERPLATE_HINTS: &[&str] = &[
+    "ad",
+    "ads",
+    "advert",
+    "banner",
+    "breadcrumb",
+    "breadcrumbs",
+    "comment",
+    "comments",
+    "cookie",
+    "footer",
+    "header",
+    "menu",
+    "nav",
+    "navbar",
+    "popup",
+    "promo",
+    "related",
+    "share",
+    "sidebar",
+    "social",
+    "sponsor",
+    "subscribe",
+];
+
+/// Elements starting a new line of text.
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+/// Paragraphs shorter than this are not counted when looking for the main content.
+const MIN_PARAGRAPH_LEN: usize = 25;
+
+fn is_boilerplate(el: &ElementRef) -> bool {
+    let el = el.value();
+    let name = el.name();
+    if matches!(name, "html" | "body") {
+        return false;
+    }
+    if SKIPPED_TAGS.contains(&name)
+        || el.attr("hidden").is_some()
+        || el.attr("aria-hidden") == Some("true")
+    {
+        return true;
+    }
+    let hints = [el.attr("class"), el.attr("id")];
+    hints
+        .into_iter()
+        .flatten()
+        .flat_map(|v| v.split(|c: char| c.is_whitespace() || c == '-' || c == '_'))
+        .any(|t| BOILERPLATE_HINTS.contains(&t.to_lowercase().as_str()))
+}
+
+fn collect_text(el: ElementRef, out: &mut String) {
+    for child in el.children() {
+        match child.value() {
+            Node::Text(text) => out.push_str(text),
+            Node::Element(e) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                if is_boilerplate(&child) {
+                    continue;
+                }
+                let block = BLOCK_TAGS.contains(&e.name());
+                if block {
+                    out.push('\n');
+                }
+                collect_text(child, out);
+                if block {
+                    out.push('\n');
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn text_len(el: &ElementRef) -> usize {
+    el.text().map(|t| t.trim().len()).sum()
+}
+
+/// Score the parents of paragraphs by the length of their text, the grandparents get half.
+fn best_scored<'a>(doc: &'a Html) -> Option<ElementRef<'a>> {
+    let mut scores = HashMap::new();
+    for p in doc.select(&Selector::parse("p, pre, blockquote").unwrap()) {
+        let mut ancestors = p.ancestors().filter_map(ElementRef::wrap);
+        if is_boilerplate(&p) || ancestors.clone().any(|a| is_boilerplate(&a)) {
+            continue;
+        }
+        let len = text_len(&p);
+        if len < MIN_PARAGRAPH_LEN {
+            continue;
+        }
+        if let Some(parent) = ancestors.next() {
+            *scores.entry(parent.id()).or_insert(0) += len;
+        }
+        if let Some(grandparent) = ancestors.next() {
+            *scores.entry(grandparent.id()).or_insert(0) += len / 2;
+        }
+    }
+    scores
+        .into_iter()
+        .max_by_key(|&(id, score)| (score, id))
+        .and_then(|(id, _)| doc.tree.get(id))
+        .and_then(ElementRef::wrap)
+}
+
+/// Extract the readable text of the main content of the HTML, leaving out navigation, ads,
+/// comments and other boilerplates. Blocks of the text are separated by new lines.
+pub fn extract_readable_text(html: &str) -> Option<String> {
+    let doc = Html::parse_document(html);
+
+    let root = doc
+        .select(&Selector::parse("article, main, [role=main]").unwrap())
+        .filter(|el| !is_boilerplate(el))
+        .max_by_key(text_len)
+        .or_else(|| best_scored(&doc))
+        .or_else(|| doc.select(&Selector::parse("body").unwrap()).next())?;
+
+    let mut text = String::new();
+    collect_text(root, &mut text);
+    let text = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_article() {
+        let html = r#"<html><body>
+            <nav><a href="/">Home</a></nav>
+            <div class="sidebar">Popular posts</div>
+            <article>
+                <h1>Rust   Ownership</h1>
+                <p>Each value has an <em>owner</em>.</p>
+                <div class="share-buttons">Share on</div>
+                <script>track()</script>
+                <ul><li>One</li><li>Two</li></ul>
+            </article>
+            <section id="comments"><p>Nice post, thanks for writing it up!</p></section>
+            <footer>Copyright</footer>
+        </body></html>"#;
+        assert_eq!(
+            extract_readable_text(html).as_deref(),
+            Some("Rust Ownership\nEach value has an owner.\nOne\nTwo")
+        );
+    }
+
+    #[test]
+    fn test_extract_by_paragraphs() {
+        let html = r#"<body>
+            <div class="menu"><p>A menu which is long enough to be a paragraph</p></div>
+            <div class="teaser"><p>Short</p></div>
+            <div class="content">
+                <p>The first paragraph of the main content, long enough.</p>
+                <p>The second paragraph of the main content, long enough.</p>
+            </div>
+        </body>"#;
+        assert_eq!(
+            extract_readable_text(html).as_deref(),
+            Some(
+                "The first paragraph of the main content, long enough.\n\
+                The second paragraph of the main content, long enough."
+            )
+        );
+    }
+
+    #[test]
+    fn test_extract_body() {
+        assert_eq!(
+            extract_readable_text("plain <b>text</b>").as_deref(),
+            Some("plain text")
+        );
+        assert_eq!(extract_readable_text("<body><nav>Home</nav></body>"), None);
+    }
+}
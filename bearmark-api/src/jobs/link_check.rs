@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use diesel_async::AsyncPgConnection as Connection;
+use futures::StreamExt;
+use itertools::Itertools;
+use tokio::time::Instant;
+use tracing::info;
+
+use crate::api::configs::{FetcherConfig, LinkCheckerConfig};
+use crate::db::bookmark::Bookmark;
+use crate::db::connection;
+use crate::db::link_check::{self, NewLinkCheck};
+use crate::fetcher;
+
+const BATCH_SIZE: i64 = 100;
+/// Hosts checked at the same time, links of a host are checked one by one.
+const HOST_CONCURRENCY: usize = 8;
+
+/// Check links of bookmarks which are not checked recently, periodically.
+#[cfg(not(tarpaulin_include))]
+pub async fn check_periodically(config: LinkCheckerConfig, fetcher_config: FetcherConfig) {
+    let client = fetcher::client(&fetcher_config);
+    let per_host_delay = Duration::from_millis(config.per_host_delay_ms);
+    let mut last_requests = HashMap::new();
+    let mut interval = tokio::time::interval(Duration::from_secs(config.interval_secs));
+    loop {
+        interval.tick().await;
+
+        let mut conn = connection::establish().await;
+        let before = time::OffsetDateTime::now_utc()
+            - time::Duration::hours(config.recheck_after_hours.into());
+        loop {
+            let bookmarks = link_check::get_bookmarks_to_check(&mut conn, before, BATCH_SIZE).await;
+            if bookmarks.is_empty() {
+                break;
+            }
+            let failed = check_bookmarks(
+                &mut conn,
+                &client,
+                per_host_delay,
+                &mut last_requests,
+                bookmarks,
+            )
+            .await;
+            info!(failed, "Checked links of bookmarks");
+        }
+    }
+}
+
+fn host_of(url: &str) -> Option<String> {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_string()))
+}
+
+/// Check links of the bookmarks, waiting between requests to the same host, since the last
+/// request to it in `last_requests` which is updated for the next batches.
+/// Returns the number of failed links.
+pub async fn check_bookmarks(
+    conn: &mut Connection,
+    client: &fetcher::Client,
+    per_host_delay: Duration,
+    last_requests: &mut HashMap<Option<String>, Instant>,
+    bookmarks: Vec<Bookmark>,
+) -> usize {
+    // hosts requested long enough ago don't hold back requests anymore
+    last_requests.retain(|_, last| last.elapsed() < per_host_delay);
+
+    let groups = bookmarks
+        .into_iter()
+        .into_group_map_by(|m| host_of(&m.url))
+        .into_iter()
+        .map(|(host, group)| {
+            let last = last_requests.get(&host).copied();
+            (host, last, group)
+        })
+        .collect_vec();
+    let results = futures::stream::iter(groups.into_iter().map(
+        |(host, mut last, group)| async move {
+            let mut checks = vec![];
+            for m in group {
+                if let Some(last) = last {
+                    tokio::time::sleep_until(last + per_host_delay).await;
+                }
+                last = Some(Instant::now());
+                checks.push(match fetcher::check_link(client, &m.url).await {
+                    Ok((status_code, redirected)) => NewLinkCheck {
+                        bookmark_id: m.id,
+                        status_code: Some(status_code.into()),
+                        redirect_url: redirected.map(|u| u.to_string()),
+                        error: None,
+                    },
+                    Err(e) => NewLinkCheck {
+                        bookmark_id: m.id,
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    },
+                });
+            }
+            (host, last, checks)
+        },
+    ))
+    .buffer_unordered(HOST_CONCURRENCY)
+    .collect::<Vec<_>>()
+    .await;
+
+    let mut failed = 0;
+    for (host, last, checks) in results {
+        if let Some(last) = last {
+            last_requests.insert(host, last);
+        }
+        for check in checks {
+            if check.is_failed() {
+                failed += 1;
+            }
+            link_check::save_link_check(conn, &check).await;
+        }
+    }
+    failed
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::bookmark::{NewBookmark, create_bookmark};
+    use crate::db::link_check::LinkCheck;
+    use crate::fetcher::test::{Fixture, serve};
+
+    #[tokio::test]
+    async fn check_bookmarks_links() {
+        let mut conn = connection::establish().await;
+        let base = serve(vec![
+            ("/ok", Fixture::status(200)),
+            ("/moved", Fixture::redirect("/ok")),
+        ])
+        .await;
+
+        let mut added = vec![];
+        for url in [
+            format!("{base}/ok"),
+            format!("{base}/moved"),
+            format!("{base}/missing"),
+            "not a url".to_string(),
+        ] {
+            added.push(
+                create_bookmark(
+                    &mut conn,
+                    &NewBookmark {
+                        title: "link".to_string(),
+                        url,
+                        notes: None,
+                        description: None,
+                        attributes: None,
+                    },
+                )
+                .await,
+            );
+        }
+
+        let client = fetcher::client(&FetcherConfig {
+            allow_private_addresses: true,
+            ..Default::default()
+        });
+        let delay = Duration::from_millis(200);
+        let started = std::time::Instant::now();
+        let mut last_requests = HashMap::new();
+        let failed =
+            check_bookmarks(&mut conn, &client, delay, &mut last_requests, added.clone()).await;
+        assert_eq!(failed, 2);
+        // three links of the fixture server are checked one by one
+        assert!(started.elapsed() >= delay * 2);
+        assert!(last_requests.contains_key(&host_of(&base)));
+
+        // the next batch waits for the last request to the same host
+        let started = std::time::Instant::now();
+        let failed = check_bookmarks(
+            &mut conn,
+            &client,
+            delay,
+            &mut last_requests,
+            vec![added[0].clone()],
+        )
+        .await;
+        assert_eq!(failed, 0);
+        assert!(started.elapsed() >= delay / 2);
+
+        let checks = futures::future::join_all(added.iter().map(|m| async {
+            let mut conn = connection::establish().await;
+            LinkCheck::get(&mut conn, m.id).await.unwrap()
+        }))
+        .await;
+        assert_eq!(checks[0].status_code, Some(200));
+        assert_eq!(checks[0].redirect_url, None);
+        assert_eq!(checks[0].consecutive_failures, 0);
+        assert_eq!(checks[1].status_code, Some(200));
+        assert_eq!(checks[1].redirect_url, Some(format!("{base}/ok")));
+        assert_eq!(checks[2].status_code, Some(404));
+        assert_eq!(checks[2].consecutive_failures, 1);
+        assert_eq!(checks[3].status_code, None);
+        assert!(checks[3].error.is_some());
+    }
+}
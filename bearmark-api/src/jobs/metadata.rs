@@ -0,0 +1,202 @@
+use std::time::Duration;
+
+use diesel_async::AsyncPgConnection as Connection;
+use tracing::{info, warn};
+
+use crate::api::configs::FetcherConfig;
+use crate::archive::Archiver;
+use crate::db::bookmark::{self, Bookmark};
+use crate::db::metadata::{self, FetchedMetadata, status};
+use crate::db::{connection, content};
+use crate::fetcher::{self, PageMetadata};
+
+const BATCH_SIZE: i64 = 20;
+/// Keep the indexed content well below the size limit of `tsvector`.
+const MAX_CONTENT_CHARS: usize = 100_000;
+
+/// Fetch metadata of the pending bookmarks periodically.
+#[cfg(not(tarpaulin_include))]
+pub async fn fetch_periodically(config: FetcherConfig, archiver: Option<Archiver>) {
+    let client = fetcher::client(&config);
+    let mut interval = tokio::time::interval(Duration::from_secs(config.interval_secs));
+    loop {
+        interval.tick().await;
+
+        let mut conn = connection::establish().await;
+        loop {
+            let pending = metadata::get_pending_bookmarks(&mut conn, BATCH_SIZE).await;
+            if pending.is_empty() {
+                break;
+            }
+            let count = fetch_bookmarks(
+                &mut conn,
+                &client,
+                config.max_bytes,
+                archiver.as_ref(),
+                pending,
+            )
+            .await;
+            info!(count, "Fetched metadata of bookmarks");
+        }
+    }
+}
+
+/// Fetch pages of the bookmarks, saving their metadata and readable content, filling in their
+/// empty title and description, and archiving their pages if an archiver is given. Returns the number of
+/// succeeded bookmarks.
+pub async fn fetch_bookmarks(
+    conn: &mut Connection,
+    client: &fetcher::Client,
+    max_bytes: usize,
+    archiver: Option<&Archiver>,
+    bookmarks: Vec<Bookmark>,
+) -> usize {
+    let mut succeeded = 0;
+    for m in bookmarks {
+        let fetched = match fetcher::fetch_page(client, &m.url, max_bytes).await {
+            Ok(page) if page.is_success() => {
+                let meta = if page.is_html() {
+                    let html = page.text();
+                    if let Some(text) = fetcher::extract_readable_text(&html) {
+                        let text = text.chars().take(MAX_CONTENT_CHARS).collect::<String>();
+                        content::save_content(conn, m.id, &text).await;
+                    }
+                    PageMetadata::parse(&html, &page.url)
+                } else {
+                    PageMetadata::default()
+                };
+                bookmark::fill_in_bookmark(
+                    conn,
+                    m.id,
+                    meta.title.as_deref(),
+                    meta.description.as_deref(),
+                )
+                .await;
+                if let Some(archiver) = archiver
+                    && let Err(e) = archiver.snapshot(conn, &m, archiver.inline_assets()).await
+                {
+                    warn!(id = m.id, url = m.url, %e, "Failed to archive bookmark");
+                }
+                succeeded += 1;
+                FetchedMetadata {
+                    status: status::SUCCEEDED.to_string(),
+                    http_status: Some(page.status.into()),
+                    error: None,
+                    title: meta.title,
+                    description: meta.description,
+                    image_url: meta.image_url,
+                    site_name: meta.site_name,
+                    favicon_url: meta.favicon_url,
+                }
+            }
+            Ok(page) => FetchedMetadata {
+                status: status::FAILED.to_string(),
+                http_status: Some(page.status.into()),
+                error: Some(format!("Unexpected HTTP status {}", page.status)),
+                ..Default::default()
+            },
+            Err(e) => {
+                warn!(id = m.id, url = m.url, %e, "Failed to fetch bookmark");
+                FetchedMetadata {
+                    status: status::FAILED.to_string(),
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                }
+            }
+        };
+        metadata::save_metadata(conn, m.id, &fetched).await;
+    }
+    succeeded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::bookmark::{NewBookmark, create_bookmark};
+    use crate::db::content::BookmarkContent;
+    use crate::db::metadata::BookmarkMetadata;
+    use crate::fetcher::test::{Fixture, serve};
+
+    #[tokio::test]
+    async fn fetch_bookmarks_metadata() {
+        let mut conn = connection::establish().await;
+        let base = serve(vec![
+            (
+                "/page",
+                Fixture::html(
+                    r#"<title>Page Title</title>
+                    <meta property="og:site_name" content="Fixture">
+                    <meta property="og:image" content="/cover.png">
+                    <meta name="description" content="About the page">
+                    <article><p>Readable content</p></article>"#,
+                ),
+            ),
+            ("/gone", Fixture::status(410)),
+        ])
+        .await;
+
+        let mut added = vec![];
+        for (title, path) in [("", "/page"), ("Kept", "/page"), ("", "/gone")] {
+            let m = create_bookmark(
+                &mut conn,
+                &NewBookmark {
+                    title: title.to_string(),
+                    url: format!("{base}{path}"),
+                    notes: None,
+                    description: None,
+                    attributes: None,
+                },
+            )
+            .await;
+            metadata::enqueue_bookmarks(&mut conn, &[m.id]).await;
+            added.push(m);
+        }
+        added.push(
+            create_bookmark(
+                &mut conn,
+                &NewBookmark {
+                    title: "".to_string(),
+                    url: "not a url".to_string(),
+                    notes: None,
+                    description: None,
+                    attributes: None,
+                },
+            )
+            .await,
+        );
+        metadata::enqueue_bookmarks(&mut conn, &[added[3].id]).await;
+
+        let client = fetcher::client(&FetcherConfig {
+            allow_private_addresses: true,
+            ..Default::default()
+        });
+        let count = fetch_bookmarks(&mut conn, &client, 1024, None, added.clone()).await;
+        assert_eq!(count, 2);
+
+        let m = Bookmark::get(&mut conn, added[0].id).await.unwrap();
+        assert_eq!(m.title, "Page Title");
+        assert_eq!(m.description.as_deref(), Some("About the page"));
+        let meta = BookmarkMetadata::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(meta.status, status::SUCCEEDED);
+        assert_eq!(meta.http_status, Some(200));
+        assert_eq!(meta.site_name.as_deref(), Some("Fixture"));
+        assert_eq!(meta.image_url, Some(format!("{base}/cover.png")));
+        assert_eq!(meta.favicon_url, Some(format!("{base}/favicon.ico")));
+        let content = BookmarkContent::get(&mut conn, m.id).await.unwrap();
+        assert_eq!(content.content, "Readable content");
+
+        let m = Bookmark::get(&mut conn, added[1].id).await.unwrap();
+        assert_eq!(m.title, "Kept");
+        assert_eq!(m.description.as_deref(), Some("About the page"));
+
+        let meta = BookmarkMetadata::get(&mut conn, added[2].id).await.unwrap();
+        assert_eq!(meta.status, status::FAILED);
+        assert_eq!(meta.http_status, Some(410));
+        let m = Bookmark::get(&mut conn, added[2].id).await.unwrap();
+        assert_eq!(m.title, "");
+
+        let meta = BookmarkMetadata::get(&mut conn, added[3].id).await.unwrap();
+        assert_eq!(meta.status, status::FAILED);
+        assert!(meta.error.unwrap().contains("Invalid URL"));
+    }
+}
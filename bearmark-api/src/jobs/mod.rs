@@ -0,0 +1,47 @@
+mod link_check;
+mod metadata;
+mod trash;
+
+use rocket::fairing::AdHoc;
+use tracing::info;
+
+use crate::api::configs::Config;
+use crate::archive::Archiver;
+
+/// Spawn the enabled background jobs once the server has launched.
+#[cfg(not(tarpaulin_include))]
+pub fn stage() -> AdHoc {
+    AdHoc::on_liftoff("Background Jobs", |rocket| {
+        Box::pin(async move {
+            let config = rocket.state::<Config>().expect("Missing Config");
+
+            if let Some(days) = config.trash_retention_days {
+                info!(days, "Purging the trash periodically");
+                tokio::spawn(trash::purge_periodically(
+                    days,
+                    Archiver::new(&config.fetcher, &config.archive),
+                ));
+            }
+
+            if config.fetcher.enabled {
+                info!("Fetching metadata of bookmarks in the background");
+                let archiver = config
+                    .archive
+                    .on_create
+                    .then(|| Archiver::new(&config.fetcher, &config.archive));
+                tokio::spawn(metadata::fetch_periodically(
+                    config.fetcher.clone(),
+                    archiver,
+                ));
+            }
+
+            if config.link_checker.enabled {
+                info!("Checking links of bookmarks periodically");
+                tokio::spawn(link_check::check_periodically(
+                    config.link_checker.clone(),
+                    config.fetcher.clone(),
+                ));
+            }
+        })
+    })
+}
@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use tracing::info;
+
+use crate::archive::Archiver;
+use crate::db::{bookmark, connection};
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Permanently delete bookmarks which stay in the trash longer than the retention period.
+#[cfg(not(tarpaulin_include))]
+pub async fn purge_periodically(retention_days: u32, archiver: Archiver) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let mut conn = connection::establish().await;
+        let before = time::OffsetDateTime::now_utc() - time::Duration::days(retention_days.into());
+        let ids = bookmark::get_bookmarks_deleted_before(&mut conn, before).await;
+        if ids.is_empty() {
+            continue;
+        }
+        let count = archiver.purge_bookmarks(&mut conn, ids).await;
+        info!(count, %before, "Purged expired bookmarks from the trash");
+    }
+}
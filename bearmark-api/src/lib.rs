@@ -2,7 +2,11 @@
 extern crate rocket;
 
 mod api;
+mod archive;
 mod db;
+mod fetcher;
+mod jobs;
+pub mod transfer;
 mod utils;
 
 #[cfg(test)]
@@ -25,13 +29,19 @@ pub(crate) mod misc {
 
 - [Bookmarks API](/swagger-ui/?urls.primaryName=bookmarks)
 - [Folders API](/swagger-ui/?urls.primaryName=folders)
+- [Trash API](/swagger-ui/?urls.primaryName=trash)
+- [Go API](/swagger-ui/?urls.primaryName=go)
+- [Shortcut API](/swagger-ui/?urls.primaryName=shortcut)
+- [OpenSearch API](/swagger-ui/?urls.primaryName=opensearch)
+- [Save API](/swagger-ui/?urls.primaryName=save)
+- [Transfer API](/swagger-ui/?urls.primaryName=transfer)
     ",
         version = "1.0"
     ))]
     pub struct ApiDoc;
 
     pub fn docs() -> Vec<rocket::Route> {
-        use crate::api::{bookmark, folder};
+        use crate::api::{bookmark, folder, go, opensearch, save, shortcut, transfer, trash};
         SwaggerUi::new("/swagger-ui/<_..>")
             .urls(vec![
                 (
@@ -46,6 +56,30 @@ pub(crate) mod misc {
                     Url::new("folders", "/api-docs/openapi-folders.json"),
                     folder::misc::ApiDoc::openapi(),
                 ),
+                (
+                    Url::new("trash", "/api-docs/openapi-trash.json"),
+                    trash::misc::ApiDoc::openapi(),
+                ),
+                (
+                    Url::new("go", "/api-docs/openapi-go.json"),
+                    go::misc::ApiDoc::openapi(),
+                ),
+                (
+                    Url::new("shortcut", "/api-docs/openapi-shortcut.json"),
+                    shortcut::misc::ApiDoc::openapi(),
+                ),
+                (
+                    Url::new("opensearch", "/api-docs/openapi-opensearch.json"),
+                    opensearch::misc::ApiDoc::openapi(),
+                ),
+                (
+                    Url::new("save", "/api-docs/openapi-save.json"),
+                    save::misc::ApiDoc::openapi(),
+                ),
+                (
+                    Url::new("transfer", "/api-docs/openapi-transfer.json"),
+                    transfer::misc::ApiDoc::openapi(),
+                ),
             ])
             .into()
     }
@@ -59,17 +93,30 @@ pub async fn rocket() -> rocket::Rocket<rocket::Build> {
 
     use crate::api::configs::{self, Config};
     use crate::api::fairings::db::Db;
-    use crate::api::{bookmark, folder, tag};
-    use crate::misc;
+    use crate::api::{bookmark, folder, go, opensearch, save, shortcut, tag, transfer, trash};
+    use crate::{jobs, misc};
 
     crate::utils::logging::setup_console_log();
     crate::db::connection::run_migrations().await;
+    let updated = crate::db::bookmark::recanonicalize_bookmarks(
+        &mut crate::db::connection::establish().await,
+        configs::get_tracking_params(),
+    )
+    .await;
+    if updated > 0 {
+        tracing::info!(updated, "Canonicalized URLs of bookmarks");
+    }
 
     let cfg_provider = configs::config_provider();
     let ui_path = cfg_provider
         .extract_inner::<Option<String>>("ui_path")
         .unwrap();
 
+    let archiver = crate::archive::Archiver::new(
+        &cfg_provider.extract_inner("fetcher").unwrap_or_default(),
+        &cfg_provider.extract_inner("archive").unwrap_or_default(),
+    );
+
     let mut builder = rocket::custom(cfg_provider);
     if let Some(ui_path) = ui_path {
         // Serve the UI files if the path is provided
@@ -77,9 +124,30 @@ pub async fn rocket() -> rocket::Rocket<rocket::Build> {
     }
     builder
         .attach(Db::init())
+        .manage(archiver)
         .mount("/api/bookmarks", bookmark::routes())
         .mount("/api/tags", tag::routes())
         .mount("/api/folders", folder::routes())
+        .mount("/api/trash", trash::routes())
+        .mount("/api/transfer", transfer::routes())
+        .mount("/go", go::routes())
+        .mount("/api/shortcut", shortcut::routes())
+        .mount("/", opensearch::routes())
+        .mount("/save", save::routes())
         .mount("/", misc::docs())
         .attach(AdHoc::config::<Config>())
+        .attach(jobs::stage())
+}
+
+/// Import bookmarks from the content of a file, for the command line.
+#[cfg(not(tarpaulin_include))]
+pub async fn import(
+    format: transfer::Format,
+    content: &str,
+    dry_run: bool,
+) -> Result<transfer::ImportReport, String> {
+    let doc = format.read(content)?;
+    crate::db::connection::run_migrations().await;
+    let mut conn = crate::db::connection::establish().await;
+    Ok(transfer::import(&mut conn, doc, dry_run).await)
 }
@@ -0,0 +1,477 @@
+//! Importing bookmarks from files exported by browsers or other services, and exporting them.
+pub mod netscape;
+mod pinboard;
+mod pocket;
+mod raindrop;
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection as Connection};
+use rocket::serde::{Deserialize, Serialize};
+use url::Url;
+use utoipa::ToSchema;
+
+use crate::api::configs;
+use crate::db::bookmark::{self, NewBookmark};
+use crate::db::folder::{self, normalize_path};
+use crate::db::reading::{self, ReadingState};
+use crate::db::{self, metadata, tag};
+use crate::utils::canonical_url::canonicalize;
+
+/// Formats of files to import bookmarks from.
+#[derive(FromFormField, Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum Format {
+    /// The `NETSCAPE-Bookmark-file-1` HTML, exported by browsers
+    Netscape,
+    /// The JSON exported by Pinboard
+    Pinboard,
+    /// The HTML or CSV exported by Pocket
+    Pocket,
+    /// The CSV exported by Raindrop.io
+    Raindrop,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "netscape" => Ok(Format::Netscape),
+            "pinboard" => Ok(Format::Pinboard),
+            "pocket" => Ok(Format::Pocket),
+            "raindrop" => Ok(Format::Raindrop),
+            _ => Err(format!("Unknown format: {s}")),
+        }
+    }
+}
+
+impl Format {
+    /// Read the content of a file in the format, or get why it can't be read at all.
+    pub fn read(self, content: &str) -> Result<Document, String> {
+        match self {
+            Format::Netscape => Ok(netscape::parse(content)),
+            Format::Pinboard => pinboard::parse(content),
+            Format::Pocket => pocket::parse(content),
+            Format::Raindrop => raindrop::parse(content),
+        }
+    }
+}
+
+/// A bookmark read from, or written to, a file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Item {
+    pub url: String,
+    /// Filled in from the page in the background if empty, when the fetcher is enabled
+    pub title: String,
+    pub description: Option<String>,
+    pub notes: Option<String>,
+    /// The path of folder, such as `/a/b`
+    pub folder: Option<String>,
+    pub tags: Vec<String>,
+    pub created_at: Option<time::OffsetDateTime>,
+    pub reading_state: Option<ReadingState>,
+}
+
+/// Bookmarks and folders of a file. Entries which can't be read are kept as error messages, to
+/// be reported along with the others.
+#[derive(Debug, Default)]
+pub struct Document {
+    /// Paths of folders, including the empty ones
+    pub folders: Vec<String>,
+    pub entries: Vec<Result<Item, String>>,
+}
+
+/// An entry failed to be imported.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "rocket::serde")]
+pub struct ItemError {
+    /// The position of the entry in the file, from 0
+    pub index: usize,
+    pub url: Option<String>,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct ImportReport {
+    /// Nothing is written if it's a dry run
+    pub dry_run: bool,
+    /// The number of entries in the file
+    pub total: usize,
+    /// The number of bookmarks created
+    pub created: usize,
+    /// The number of entries skipped, whose canonical URLs are saved already or repeated in the
+    /// file
+    pub duplicated: usize,
+    /// Paths of folders created
+    pub folders_created: Vec<String>,
+    pub errors: Vec<ItemError>,
+}
+
+/// The path and its ancestors, from the top level.
+fn with_ancestors(path: &str) -> impl Iterator<Item = String> + '_ {
+    path.match_indices('/')
+        .skip(1)
+        .map(|(i, _)| path[..i].to_string())
+        .chain(std::iter::once(path.to_string()))
+}
+
+/// Split tags by the separator, dropping empty ones.
+fn split_tags(tags: &str, separator: char) -> Vec<String> {
+    tags.split(separator)
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn parse_unix_timestamp(s: &str) -> Result<time::OffsetDateTime, String> {
+    s.parse()
+        .ok()
+        .and_then(|s| time::OffsetDateTime::from_unix_timestamp(s).ok())
+        .ok_or_else(|| format!("Invalid timestamp: {s}"))
+}
+
+fn parse_rfc3339(s: &str) -> Result<time::OffsetDateTime, String> {
+    time::OffsetDateTime::parse(s, &time::format_description::well_known::Rfc3339)
+        .map_err(|_| format!("Invalid timestamp: {s}"))
+}
+
+/// A record of a CSV file, whose fields are got by the names of columns.
+struct Row<'a> {
+    columns: &'a HashMap<String, usize>,
+    record: csv::StringRecord,
+}
+
+impl Row<'_> {
+    /// The trimmed field, or `None` if it's empty or missing.
+    fn get(&self, column: &str) -> Option<&str> {
+        self.columns
+            .get(column)
+            .and_then(|i| self.record.get(*i))
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    }
+}
+
+/// Read a CSV file with a header of the required columns, rows which can't be read are kept as
+/// errors.
+fn read_csv(
+    content: &str,
+    required: &[&str],
+    read: impl Fn(&Row) -> Result<Item, String>,
+) -> Result<Document, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+    let columns = reader
+        .headers()
+        .map_err(|e| format!("Invalid CSV: {e}"))?
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.trim().to_lowercase(), i))
+        .collect::<HashMap<_, _>>();
+    if let Some(missing) = required.iter().find(|c| !columns.contains_key(**c)) {
+        return Err(format!("Missing column: {missing}"));
+    }
+
+    let entries = reader
+        .into_records()
+        .map(|record| {
+            let record = record.map_err(|e| format!("Invalid CSV: {e}"))?;
+            read(&Row {
+                columns: &columns,
+                record,
+            })
+        })
+        .collect();
+    Ok(Document {
+        folders: vec![],
+        entries,
+    })
+}
+
+enum Rollback {
+    DryRun(ImportReport),
+    Database(diesel::result::Error),
+}
+
+impl From<diesel::result::Error> for Rollback {
+    fn from(e: diesel::result::Error) -> Self {
+        Rollback::Database(e)
+    }
+}
+
+/// Import the document in a transaction. Entries whose canonical URLs are saved already, or
+/// repeated in the document, are skipped. Nothing is written in a dry run, but the report is
+/// the same as it would be.
+pub async fn import(conn: &mut Connection, doc: Document, dry_run: bool) -> ImportReport {
+    let rv = conn
+        .transaction::<_, Rollback, _>(|conn| {
+            async move {
+                let report = write(conn, doc, dry_run).await;
+                if dry_run {
+                    Err(Rollback::DryRun(report))
+                } else {
+                    Ok(report)
+                }
+            }
+            .scope_boxed()
+        })
+        .await;
+    match rv {
+        Ok(report) | Err(Rollback::DryRun(report)) => report,
+        Err(Rollback::Database(e)) => panic!("Error importing bookmarks: {e:?}"),
+    }
+}
+
+async fn write(conn: &mut Connection, doc: Document, dry_run: bool) -> ImportReport {
+    let mut report = ImportReport {
+        dry_run,
+        total: doc.entries.len(),
+        ..Default::default()
+    };
+
+    let paths = doc
+        .folders
+        .iter()
+        .map(String::as_str)
+        .chain(
+            doc.entries
+                .iter()
+                .flatten()
+                .filter_map(|i| i.folder.as_deref()),
+        )
+        .filter_map(normalize_path)
+        .collect::<BTreeSet<_>>();
+    let mut folder_ids = HashMap::new();
+    for path in paths {
+        let Some((f, created)) = folder::get_or_create_folder_with_created(conn, &path)
+            .await
+            .expect("Error creating folder")
+        else {
+            continue;
+        };
+        report
+            .folders_created
+            .extend(created.into_iter().map(|f| f.path));
+        folder_ids.insert(path, f.id);
+    }
+
+    let urls = doc
+        .entries
+        .iter()
+        .flatten()
+        .map(|i| i.url.clone())
+        .collect::<Vec<_>>();
+    let mut seen = bookmark::find_bookmarks_by_urls(conn, &urls, true)
+        .await
+        .into_iter()
+        .map(|m| m.canonical_url)
+        .collect::<HashSet<_>>();
+
+    let mut created = vec![];
+    for (index, entry) in doc.entries.into_iter().enumerate() {
+        let item = match entry {
+            Ok(item) => item,
+            Err(message) => {
+                report.errors.push(ItemError {
+                    index,
+                    url: None,
+                    message,
+                });
+                continue;
+            }
+        };
+        if let Err(e) = Url::parse(item.url.trim()) {
+            report.errors.push(ItemError {
+                index,
+                url: Some(item.url),
+                message: format!("Invalid URL: {e}"),
+            });
+            continue;
+        }
+        if !seen.insert(canonicalize(&item.url)) {
+            report.duplicated += 1;
+            continue;
+        }
+
+        let mut m = bookmark::create_bookmark(
+            conn,
+            &NewBookmark {
+                title: item.title,
+                url: item.url.trim().to_string(),
+                notes: item.notes,
+                description: item.description,
+                attributes: None,
+            },
+        )
+        .await;
+        if let Some(state) = item.reading_state {
+            reading::set_reading_state(conn, m.id, Some(state))
+                .await
+                .expect("Error queuing imported bookmark");
+        }
+        if let Some(created_at) = item.created_at {
+            m = bookmark::backdate_bookmark(conn, m.id, created_at)
+                .await
+                .expect("Error backdating imported bookmark");
+        }
+        tag::update_bookmark_tags(conn, &m, &item.tags).await;
+        let folder_id = item
+            .folder
+            .as_deref()
+            .and_then(normalize_path)
+            .and_then(|p| folder_ids.get(&p));
+        if let Some(folder_id) = folder_id {
+            folder::move_bookmarks(conn, *folder_id, &vec![m.id])
+                .await
+                .expect("Error moving imported bookmark");
+        }
+        created.push(m.id);
+    }
+    if configs::is_fetcher_enabled() {
+        metadata::enqueue_bookmarks(conn, &created).await;
+    }
+    report.created = created.len();
+
+    report
+}
+
+/// Export all folders, and all bookmarks which are not deleted.
+pub async fn export(conn: &mut Connection) -> Document {
+    let folders = folder::list_all_folders(conn)
+        .await
+        .into_iter()
+        .map(|f| f.path)
+        .collect();
+    let bookmarks = bookmark::get_all_bookmarks(conn).await;
+    let entries = db::get_bookmark_details(conn, bookmarks)
+        .await
+        .into_iter()
+        .map(|(m, f, tags)| {
+            Ok(Item {
+                reading_state: m.reading_state.as_deref().and_then(ReadingState::parse),
+                url: m.url,
+                title: m.title,
+                description: m.description,
+                notes: m.notes,
+                folder: f.map(|f| f.path),
+                tags: tags.into_iter().map(|t| t.name).collect(),
+                created_at: Some(m.created_at),
+            })
+        })
+        .collect();
+
+    Document { folders, entries }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::connection;
+    use crate::db::folder::Folder;
+    use crate::utils::rand::rand_str;
+
+    use time::macros::datetime;
+
+    #[test]
+    fn test_with_ancestors() {
+        assert_eq!(
+            with_ancestors("/a/b/c").collect::<Vec<_>>(),
+            vec!["/a", "/a/b", "/a/b/c"]
+        );
+        assert_eq!(with_ancestors("/a").collect::<Vec<_>>(), vec!["/a"]);
+    }
+
+    #[tokio::test]
+    async fn import_and_export() {
+        let mut conn = connection::establish().await;
+        let (root, host) = (format!("/{}", rand_str(10)), rand_str(10));
+        let url = |path: &str| format!("https://{host}.example.com/{path}");
+        let document = || Document {
+            folders: vec![format!("{root}/empty")],
+            entries: vec![
+                Ok(Item {
+                    url: url("one"),
+                    title: "One".to_string(),
+                    description: Some("The first".to_string()),
+                    notes: None,
+                    folder: Some(format!("{root}/a/b")),
+                    tags: vec!["x".to_string(), "y".to_string()],
+                    created_at: Some(datetime!(2020-01-02 03:04:05 UTC)),
+                    reading_state: Some(ReadingState::Unread),
+                }),
+                Ok(Item {
+                    url: url("one?utm_source=feed"),
+                    title: "One again".to_string(),
+                    ..Default::default()
+                }),
+                Ok(Item {
+                    url: "not a url".to_string(),
+                    ..Default::default()
+                }),
+                Err("Missing HREF".to_string()),
+                Ok(Item {
+                    url: url("two"),
+                    ..Default::default()
+                }),
+            ],
+        };
+
+        let report = import(&mut conn, document(), true).await;
+        assert!(report.dry_run);
+        assert_eq!(report.total, 5);
+        assert_eq!(report.created, 2);
+        assert_eq!(report.duplicated, 1);
+        assert_eq!(
+            report.folders_created,
+            vec![
+                root.clone(),
+                format!("{root}/a"),
+                format!("{root}/a/b"),
+                format!("{root}/empty"),
+            ]
+        );
+        assert_eq!(
+            report.errors.iter().map(|e| e.index).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        // nothing is written
+        assert!(Folder::get_by_path(&mut conn, &root).await.is_none());
+        let found = bookmark::find_bookmarks_by_urls(&mut conn, &[url("one")], false).await;
+        assert!(found.is_empty());
+
+        let report = import(&mut conn, document(), false).await;
+        assert!(!report.dry_run);
+        assert_eq!(report.created, 2);
+        assert_eq!(report.folders_created.len(), 4);
+
+        let exported = export(&mut conn).await;
+        assert!(exported.folders.contains(&format!("{root}/empty")));
+        let item = exported
+            .entries
+            .into_iter()
+            .flatten()
+            .find(|i| i.url == url("one"))
+            .unwrap();
+        assert_eq!(item.title, "One");
+        assert_eq!(item.description.as_deref(), Some("The first"));
+        assert_eq!(item.folder, Some(format!("{root}/a/b")));
+        assert_eq!(item.tags, vec!["x", "y"]);
+        assert_eq!(item.created_at, Some(datetime!(2020-01-02 03:04:05 UTC)));
+        assert_eq!(item.reading_state, Some(ReadingState::Unread));
+        let m = bookmark::find_bookmarks_by_urls(&mut conn, &[url("one")], false)
+            .await
+            .remove(0);
+        assert_eq!(m.queued_at, Some(datetime!(2020-01-02 03:04:05 UTC)));
+
+        // imported already
+        let report = import(&mut conn, document(), false).await;
+        assert_eq!(report.created, 0);
+        assert_eq!(report.duplicated, 3);
+        assert!(report.folders_created.is_empty());
+    }
+}
@@ -0,0 +1,335 @@
+//! The `NETSCAPE-Bookmark-file-1` HTML, which every browser imports and exports.
+//!
+//! Folders are `<DT><H3>` followed by a nested `<DL>`, bookmarks are `<DT><A>` optionally
+//! followed by a `<DD>` description. The HTML parser keeps the nesting of unclosed `<DT>` and
+//! `<DD>`, so a nested list is a child of the `<DT>` or `<DD>` before it.
+use std::collections::{BTreeSet, HashMap};
+
+use scraper::{ElementRef, Html, Selector};
+
+use super::{Document, Item};
+use crate::db::folder::normalize_path;
+use crate::db::reading::ReadingState;
+use crate::utils::html::escape_html;
+
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The name of folder as a segment of path.
+fn folder_name(el: ElementRef) -> String {
+    let name = normalize_text(&el.text().collect::<String>()).replace('/', "-");
+    if name.is_empty() {
+        "Untitled".to_string()
+    } else {
+        name
+    }
+}
+
+fn read_link(el: ElementRef, path: &str) -> Result<Item, String> {
+    let attr = |name: &str| el.value().attr(name).map(str::trim);
+    let title = normalize_text(&el.text().collect::<String>());
+    let Some(url) = attr("href").filter(|href| !href.is_empty()) else {
+        return Err(format!("Missing HREF of {title:?}"));
+    };
+
+    Ok(Item {
+        url: url.to_string(),
+        title,
+        description: None,
+        notes: None,
+        folder: (!path.is_empty()).then(|| path.to_string()),
+        tags: attr("tags")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect(),
+        created_at: attr("add_date")
+            .and_then(|s| s.parse().ok())
+            .and_then(|s| time::OffsetDateTime::from_unix_timestamp(s).ok()),
+        reading_state: (attr("toread") == Some("1")).then_some(ReadingState::Unread),
+    })
+}
+
+/// What a `<DD>` belongs to.
+enum Previous {
+    Link(usize),
+    Folder(String),
+}
+
+/// Read the entries of a `<DL>` in the folder.
+fn read_list(list: ElementRef, path: &str, doc: &mut Document) {
+    let mut previous = None;
+    for child in list.child_elements() {
+        match child.value().name() {
+            "dt" => {
+                previous = None;
+                for el in child.child_elements() {
+                    match el.value().name() {
+                        "h3" => {
+                            let folder = format!("{path}/{}", folder_name(el));
+                            doc.folders.push(folder.clone());
+                            previous = Some(Previous::Folder(folder));
+                        }
+                        "a" => {
+                            doc.entries.push(read_link(el, path));
+                            previous = Some(Previous::Link(doc.entries.len() - 1));
+                        }
+                        "dl" => match &previous {
+                            Some(Previous::Folder(folder)) => read_list(el, folder, doc),
+                            _ => read_list(el, path, doc),
+                        },
+                        _ => {}
+                    }
+                }
+            }
+            "dd" => {
+                let text = child
+                    .children()
+                    .filter_map(|n| n.value().as_text())
+                    .map(|t| &**t)
+                    .collect::<String>();
+                let text = text.trim();
+                let described = match &previous {
+                    Some(Previous::Link(i)) if !text.is_empty() => doc.entries.get_mut(*i),
+                    _ => None,
+                };
+                if let Some(Ok(item)) = described {
+                    item.description = Some(text.to_string());
+                }
+                for el in child
+                    .child_elements()
+                    .filter(|el| el.value().name() == "dl")
+                {
+                    match &previous {
+                        Some(Previous::Folder(folder)) => read_list(el, folder, doc),
+                        _ => read_list(el, path, doc),
+                    }
+                }
+            }
+            "dl" => read_list(child, path, doc),
+            _ => {}
+        }
+    }
+}
+
+/// Parse the bookmark file, folders become paths like `/Bookmarks bar/Rust`.
+pub fn parse(html: &str) -> Document {
+    let html = Html::parse_document(html);
+    let mut doc = Document::default();
+    // the outermost lists, the nested ones are read through them
+    for list in html.select(&Selector::parse("dl").unwrap()).filter(|el| {
+        !el.ancestors()
+            .filter_map(ElementRef::wrap)
+            .any(|a| a.value().name() == "dl")
+    }) {
+        read_list(list, "", &mut doc);
+    }
+    doc
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(parent, _)| parent)
+}
+
+fn write_link(out: &mut String, indent: &str, item: &Item) {
+    out.push_str(&format!(
+        "{indent}<DT><A HREF=\"{}\"",
+        escape_html(&item.url)
+    ));
+    if let Some(created_at) = item.created_at {
+        out.push_str(&format!(" ADD_DATE=\"{}\"", created_at.unix_timestamp()));
+    }
+    if !item.tags.is_empty() {
+        out.push_str(&format!(" TAGS=\"{}\"", escape_html(&item.tags.join(","))));
+    }
+    if item.reading_state == Some(ReadingState::Unread) {
+        out.push_str(" TOREAD=\"1\"");
+    }
+    out.push_str(&format!(">{}</A>\n", escape_html(&item.title)));
+    if let Some(description) = item.description.as_deref().filter(|d| !d.is_empty()) {
+        out.push_str(&format!("{indent}<DD>{}\n", escape_html(description)));
+    }
+}
+
+fn write_list(
+    out: &mut String,
+    path: &str,
+    depth: usize,
+    folders: &BTreeSet<String>,
+    items: &HashMap<&str, Vec<&Item>>,
+) {
+    let indent = "    ".repeat(depth);
+    out.push_str(&format!("{indent}<DL><p>\n"));
+    for folder in folders.iter().filter(|f| parent(f) == path) {
+        out.push_str(&format!(
+            "{indent}    <DT><H3>{}</H3>\n",
+            escape_html(folder.rsplit('/').next().unwrap_or_default())
+        ));
+        write_list(out, folder, depth + 1, folders, items);
+    }
+    for item in items.get(path).into_iter().flatten() {
+        write_link(out, &format!("{indent}    "), item);
+    }
+    out.push_str(&format!("{indent}</DL><p>\n"));
+}
+
+/// Render the folders and bookmarks as a bookmark file, subfolders go before bookmarks.
+pub fn render(doc: &Document) -> String {
+    let paths = doc
+        .folders
+        .iter()
+        .map(String::as_str)
+        .chain(
+            doc.entries
+                .iter()
+                .flatten()
+                .filter_map(|i| i.folder.as_deref()),
+        )
+        .filter_map(normalize_path)
+        .flat_map(|p| super::with_ancestors(&p).collect::<Vec<_>>())
+        .collect::<BTreeSet<_>>();
+    let mut items = HashMap::<&str, Vec<&Item>>::new();
+    for item in doc.entries.iter().flatten() {
+        let folder = match item.folder.as_deref().and_then(normalize_path) {
+            Some(path) => paths.get(&path).map_or("", String::as_str),
+            None => "",
+        };
+        items.entry(folder).or_default().push(item);
+    }
+
+    let mut out = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+        <!-- This is an automatically generated file.\n     \
+        It will be read and overwritten.\n     \
+        DO NOT EDIT! -->\n\
+        <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+        <TITLE>Bookmarks</TITLE>\n\
+        <H1>Bookmarks</H1>\n",
+    );
+    write_list(&mut out, "", 0, &paths, &items);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    const EXPORTED: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file. -->
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1700000000" PERSONAL_TOOLBAR_FOLDER="true">Bookmarks bar</H3>
+    <DL><p>
+        <DT><A HREF="https://www.rust-lang.org/" ADD_DATE="1577934245" TAGS="rust, lang">Rust &amp; Cargo</A>
+        <DD>A language empowering everyone
+        <DT><H3>Docs/Refs</H3>
+        <DD>Folder description
+        <DL><p>
+            <DT><A HREF="https://doc.rust-lang.org/std/" TOREAD="1">std</A>
+        </DL><p>
+        <DT><H3>Empty</H3>
+        <DL><p>
+        </DL><p>
+    </DL><p>
+    <DT><A>No link</A>
+    <DT><A HREF="https://example.com/" ADD_DATE="bad">
+        Example
+    </A>
+</DL><p>
+"#;
+
+    #[test]
+    fn test_parse() {
+        let doc = parse(EXPORTED);
+        assert_eq!(
+            doc.folders,
+            vec![
+                "/Bookmarks bar",
+                "/Bookmarks bar/Docs-Refs",
+                "/Bookmarks bar/Empty"
+            ]
+        );
+        assert_eq!(
+            doc.entries,
+            vec![
+                Ok(Item {
+                    url: "https://www.rust-lang.org/".to_string(),
+                    title: "Rust & Cargo".to_string(),
+                    description: Some("A language empowering everyone".to_string()),
+                    notes: None,
+                    folder: Some("/Bookmarks bar".to_string()),
+                    tags: vec!["rust".to_string(), "lang".to_string()],
+                    created_at: Some(datetime!(2020-01-02 03:04:05 UTC)),
+                    reading_state: None,
+                }),
+                Ok(Item {
+                    url: "https://doc.rust-lang.org/std/".to_string(),
+                    title: "std".to_string(),
+                    folder: Some("/Bookmarks bar/Docs-Refs".to_string()),
+                    reading_state: Some(ReadingState::Unread),
+                    ..Default::default()
+                }),
+                Err("Missing HREF of \"No link\"".to_string()),
+                Ok(Item {
+                    url: "https://example.com/".to_string(),
+                    title: "Example".to_string(),
+                    ..Default::default()
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let doc = Document {
+            folders: vec!["/a/empty".to_string()],
+            entries: vec![
+                Ok(Item {
+                    url: "https://example.com/?a=1&b=2".to_string(),
+                    title: "<Example>".to_string(),
+                    description: Some("An \"example\"".to_string()),
+                    notes: None,
+                    folder: Some("/a/b".to_string()),
+                    tags: vec!["x".to_string(), "y".to_string()],
+                    created_at: Some(datetime!(2020-01-02 03:04:05 UTC)),
+                    reading_state: Some(ReadingState::Unread),
+                }),
+                Ok(Item {
+                    url: "https://example.org/".to_string(),
+                    title: "Root".to_string(),
+                    ..Default::default()
+                }),
+            ],
+        };
+        let rendered = render(&doc);
+        assert!(rendered.starts_with("<!DOCTYPE NETSCAPE-Bookmark-file-1>\n"));
+        assert!(rendered.ends_with(
+            "<DL><p>
+    <DT><H3>a</H3>
+    <DL><p>
+        <DT><H3>b</H3>
+        <DL><p>
+            <DT><A HREF=\"https://example.com/?a=1&amp;b=2\" ADD_DATE=\"1577934245\" TAGS=\"x,y\" TOREAD=\"1\">&lt;Example&gt;</A>
+            <DD>An &quot;example&quot;
+        </DL><p>
+        <DT><H3>empty</H3>
+        <DL><p>
+        </DL><p>
+    </DL><p>
+    <DT><A HREF=\"https://example.org/\">Root</A>
+</DL><p>
+"
+        ));
+
+        // read back as it was
+        let parsed = parse(&rendered);
+        assert_eq!(parsed.folders, vec!["/a", "/a/b", "/a/empty"]);
+        assert_eq!(parsed.entries, doc.entries);
+    }
+}
@@ -0,0 +1,106 @@
+//! The JSON exported by Pinboard, a list of posts like `/v1/posts/all?format=json` of its API.
+use rocket::serde::Deserialize;
+
+use super::{Document, Item, parse_rfc3339, split_tags};
+use crate::db::reading::ReadingState;
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct Post {
+    href: Option<String>,
+    /// The title
+    #[serde(default)]
+    description: String,
+    /// The description
+    #[serde(default)]
+    extended: String,
+    /// Separated by spaces
+    #[serde(default)]
+    tags: String,
+    time: Option<String>,
+    /// `yes` or `no`
+    #[serde(default)]
+    toread: String,
+}
+
+fn read_post(post: serde_json::Value) -> Result<Item, String> {
+    let post = serde_json::from_value::<Post>(post).map_err(|e| format!("Invalid post: {e}"))?;
+    let title = post.description.trim().to_string();
+    let Some(url) = post.href.filter(|href| !href.trim().is_empty()) else {
+        return Err(format!("Missing href of {title:?}"));
+    };
+    let extended = post.extended.trim();
+
+    Ok(Item {
+        url,
+        title,
+        description: (!extended.is_empty()).then(|| extended.to_string()),
+        notes: None,
+        folder: None,
+        tags: split_tags(&post.tags, ' '),
+        created_at: post.time.as_deref().map(parse_rfc3339).transpose()?,
+        reading_state: (post.toread == "yes").then_some(ReadingState::Unread),
+    })
+}
+
+pub fn parse(content: &str) -> Result<Document, String> {
+    let posts = serde_json::from_str::<Vec<serde_json::Value>>(content)
+        .map_err(|e| format!("Invalid JSON: {e}"))?;
+    Ok(Document {
+        folders: vec![],
+        entries: posts.into_iter().map(read_post).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_parse() {
+        let doc = parse(
+            r#"[
+                {"href":"https://www.rust-lang.org/","description":"Rust","extended":"A language","meta":"x","hash":"y","time":"2020-01-02T03:04:05Z","shared":"yes","toread":"yes","tags":"rust  lang"},
+                {"href":"https://example.com/","description":"","extended":"","tags":"","time":"2020-01-02T03:04:05Z","toread":"no"},
+                {"description":"No link"},
+                {"href":"https://example.org/","time":"yesterday"},
+                "not a post"
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(doc.entries.len(), 5);
+        assert_eq!(
+            doc.entries[0],
+            Ok(Item {
+                url: "https://www.rust-lang.org/".to_string(),
+                title: "Rust".to_string(),
+                description: Some("A language".to_string()),
+                notes: None,
+                folder: None,
+                tags: vec!["rust".to_string(), "lang".to_string()],
+                created_at: Some(datetime!(2020-01-02 03:04:05 UTC)),
+                reading_state: Some(ReadingState::Unread),
+            })
+        );
+        assert_eq!(
+            doc.entries[1],
+            Ok(Item {
+                url: "https://example.com/".to_string(),
+                created_at: Some(datetime!(2020-01-02 03:04:05 UTC)),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            doc.entries[2],
+            Err("Missing href of \"No link\"".to_string())
+        );
+        assert_eq!(
+            doc.entries[3],
+            Err("Invalid timestamp: yesterday".to_string())
+        );
+        assert!(doc.entries[4].is_err());
+
+        assert!(parse("<html>").is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! The export of Pocket, either the HTML of the earlier one or the CSV of the later one.
+//!
+//! The HTML lists bookmarks under the `Unread` and `Read Archive` headings, the CSV has the
+//! columns `title,url,time_added,tags,status`, whose tags are separated by `|`.
+use scraper::{Html, Selector};
+
+use super::{Document, Item, Row, parse_unix_timestamp, read_csv, split_tags};
+use crate::db::reading::ReadingState;
+
+fn parse_html(content: &str) -> Document {
+    let html = Html::parse_document(content);
+    let mut state = ReadingState::Unread;
+    let mut entries = vec![];
+    for el in html.select(&Selector::parse("h1, a").unwrap()) {
+        let title = el.text().collect::<String>();
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+        if el.value().name() == "h1" {
+            state = if title.to_lowercase().contains("archive") {
+                ReadingState::Done
+            } else {
+                ReadingState::Unread
+            };
+            continue;
+        }
+
+        let attr = |name: &str| el.value().attr(name).map(str::trim);
+        entries.push(match attr("href").filter(|href| !href.is_empty()) {
+            None => Err(format!("Missing href of {title:?}")),
+            Some(url) => attr("time_added")
+                .map(parse_unix_timestamp)
+                .transpose()
+                .map(|created_at| Item {
+                    url: url.to_string(),
+                    title,
+                    description: None,
+                    notes: None,
+                    folder: None,
+                    tags: split_tags(attr("tags").unwrap_or_default(), ','),
+                    created_at,
+                    reading_state: Some(state),
+                }),
+        });
+    }
+    Document {
+        folders: vec![],
+        entries,
+    }
+}
+
+fn read_row(row: &Row) -> Result<Item, String> {
+    let title = row.get("title").unwrap_or_default().to_string();
+    let Some(url) = row.get("url") else {
+        return Err(format!("Missing url of {title:?}"));
+    };
+
+    Ok(Item {
+        url: url.to_string(),
+        title,
+        description: None,
+        notes: None,
+        folder: None,
+        tags: split_tags(row.get("tags").unwrap_or_default(), '|'),
+        created_at: row
+            .get("time_added")
+            .map(parse_unix_timestamp)
+            .transpose()?,
+        reading_state: Some(match row.get("status") {
+            Some("archive") => ReadingState::Done,
+            _ => ReadingState::Unread,
+        }),
+    })
+}
+
+pub fn parse(content: &str) -> Result<Document, String> {
+    if content.trim_start().starts_with('<') {
+        Ok(parse_html(content))
+    } else {
+        read_csv(content, &["url"], read_row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_parse_html() {
+        let doc = parse(
+            r#"<!DOCTYPE html>
+<html>
+<head><title>Pocket Export</title></head>
+<body>
+<h1>Unread</h1>
+<ul>
+<li><a href="https://www.rust-lang.org/" time_added="1577934245" tags="rust,lang">Rust</a></li>
+<li><a time_added="1577934245">No link</a></li>
+</ul>
+<h1>Read Archive</h1>
+<ul>
+<li><a href="https://example.com/" time_added="soon" tags="">Example</a></li>
+<li><a href="https://example.org/" time_added="1577934245" tags="">Example</a></li>
+</ul>
+</body>
+</html>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            doc.entries,
+            vec![
+                Ok(Item {
+                    url: "https://www.rust-lang.org/".to_string(),
+                    title: "Rust".to_string(),
+                    tags: vec!["rust".to_string(), "lang".to_string()],
+                    created_at: Some(datetime!(2020-01-02 03:04:05 UTC)),
+                    reading_state: Some(ReadingState::Unread),
+                    ..Default::default()
+                }),
+                Err("Missing href of \"No link\"".to_string()),
+                Err("Invalid timestamp: soon".to_string()),
+                Ok(Item {
+                    url: "https://example.org/".to_string(),
+                    title: "Example".to_string(),
+                    created_at: Some(datetime!(2020-01-02 03:04:05 UTC)),
+                    reading_state: Some(ReadingState::Done),
+                    ..Default::default()
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let doc = parse(
+            "title,url,time_added,tags,status\n\
+            \"Rust, the language\",https://www.rust-lang.org/,1577934245,rust|lang,unread\n\
+            Example,https://example.com/,1577934245,,archive\n\
+            No link,,1577934245,,unread\n",
+        )
+        .unwrap();
+        assert_eq!(
+            doc.entries,
+            vec![
+                Ok(Item {
+                    url: "https://www.rust-lang.org/".to_string(),
+                    title: "Rust, the language".to_string(),
+                    tags: vec!["rust".to_string(), "lang".to_string()],
+                    created_at: Some(datetime!(2020-01-02 03:04:05 UTC)),
+                    reading_state: Some(ReadingState::Unread),
+                    ..Default::default()
+                }),
+                Ok(Item {
+                    url: "https://example.com/".to_string(),
+                    title: "Example".to_string(),
+                    created_at: Some(datetime!(2020-01-02 03:04:05 UTC)),
+                    reading_state: Some(ReadingState::Done),
+                    ..Default::default()
+                }),
+                Err("Missing url of \"No link\"".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            parse("title,time_added\n").unwrap_err(),
+            "Missing column: url"
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! The CSV exported by Raindrop.io, with the columns
+//! `id,title,note,excerpt,url,folder,tags,created,cover,highlights,favorite`.
+use super::{Document, Item, Row, parse_rfc3339, read_csv, split_tags};
+
+/// The collection of bookmarks not collected.
+const UNSORTED: &str = "Unsorted";
+
+/// The path of folder from the collection, nested collections are separated by `/`.
+fn folder_path(collection: &str) -> Option<String> {
+    let segments = collection
+        .split('/')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    (!segments.is_empty() && collection != UNSORTED).then(|| format!("/{}", segments.join("/")))
+}
+
+fn read_row(row: &Row) -> Result<Item, String> {
+    let title = row.get("title").unwrap_or_default().to_string();
+    let Some(url) = row.get("url") else {
+        return Err(format!("Missing url of {title:?}"));
+    };
+
+    Ok(Item {
+        url: url.to_string(),
+        title,
+        description: row.get("excerpt").map(str::to_string),
+        notes: row.get("note").map(str::to_string),
+        folder: row.get("folder").and_then(folder_path),
+        tags: split_tags(row.get("tags").unwrap_or_default(), ','),
+        created_at: row.get("created").map(parse_rfc3339).transpose()?,
+        reading_state: None,
+    })
+}
+
+pub fn parse(content: &str) -> Result<Document, String> {
+    read_csv(content, &["url"], read_row)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_parse() {
+        let doc = parse(
+            "\u{feff}id,title,note,excerpt,url,folder,tags,created,cover,highlights,favorite\n\
+            1,Rust,My note,A language,https://www.rust-lang.org/,Dev / Rust,\"rust, lang\",2020-01-02T03:04:05.000Z,,,true\n\
+            2,Example,,,https://example.com/,Unsorted,,2020-01-02T03:04:05.000Z,,,false\n\
+            3,No link,,,,Dev,,2020-01-02T03:04:05.000Z,,,false\n\
+            4,Bad time,,,https://example.org/,Dev,,02/01/2020,,,false\n\
+            5,Short row\n",
+        )
+        .unwrap();
+        assert_eq!(
+            doc.entries,
+            vec![
+                Ok(Item {
+                    url: "https://www.rust-lang.org/".to_string(),
+                    title: "Rust".to_string(),
+                    description: Some("A language".to_string()),
+                    notes: Some("My note".to_string()),
+                    folder: Some("/Dev/Rust".to_string()),
+                    tags: vec!["rust".to_string(), "lang".to_string()],
+                    created_at: Some(datetime!(2020-01-02 03:04:05 UTC)),
+                    reading_state: None,
+                }),
+                Ok(Item {
+                    url: "https://example.com/".to_string(),
+                    title: "Example".to_string(),
+                    created_at: Some(datetime!(2020-01-02 03:04:05 UTC)),
+                    ..Default::default()
+                }),
+                Err("Missing url of \"No link\"".to_string()),
+                Err("Invalid timestamp: 02/01/2020".to_string()),
+                Err("Missing url of \"Short row\"".to_string()),
+            ]
+        );
+    }
+}
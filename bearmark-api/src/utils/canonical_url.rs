@@ -0,0 +1,136 @@
+use url::Url;
+
+/// Query parameters stripped by default, a trailing `*` matches any suffix.
+pub const DEFAULT_TRACKING_PARAMS: &[&str] = &[
+    "utm_*", "fbclid", "gclid", "dclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid",
+    "_hsenc", "_hsmkt",
+];
+
+fn is_tracking_param(key: &str, patterns: &[String]) -> bool {
+    let key = key.to_lowercase();
+    patterns.iter().any(|p| {
+        let p = p.to_lowercase();
+        match p.strip_suffix('*') {
+            Some(prefix) => key.starts_with(prefix),
+            None => key == p,
+        }
+    })
+}
+
+/// Canonicalize the URL with the given tracking parameters patterns.
+///
+/// The scheme and host are lowercased, IDN are converted into punycode,
+/// the default port and the trailing slash of path are removed,
+/// tracking parameters are stripped and the rest of query parameters are sorted.
+/// Returns the trimmed input as it is, if it is not a valid URL.
+pub fn canonicalize_with(raw: &str, tracking_params: &[String]) -> String {
+    let raw = raw.trim();
+    let Ok(mut url) = Url::parse(raw) else {
+        return raw.to_string();
+    };
+    if url.cannot_be_a_base() {
+        return url.to_string();
+    }
+
+    let mut pairs = url
+        .query_pairs()
+        .into_owned()
+        .filter(|(k, _)| !is_tracking_param(k, tracking_params))
+        .collect::<Vec<_>>();
+    pairs.sort();
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+
+    if url.fragment() == Some("") {
+        url.set_fragment(None);
+    }
+
+    let path = url.path();
+    if path.len() > 1 && path.ends_with('/') {
+        let path = path.trim_end_matches('/').to_string();
+        url.set_path(&path);
+    }
+
+    url.to_string()
+}
+
+/// The key for finding near-equal URLs, which ignores the scheme, the `www.` prefix
+/// and the trailing slash of the canonical URL.
+pub fn near_equal_key(canonical: &str) -> &str {
+    let key = canonical
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(canonical);
+    let key = key.strip_prefix("www.").unwrap_or(key);
+    key.trim_end_matches('/')
+}
+
+/// Canonicalize the URL with the configured tracking parameters patterns.
+pub fn canonicalize(raw: &str) -> String {
+    canonicalize_with(raw, crate::api::configs::get_tracking_params())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonicalize() {
+        let patterns = DEFAULT_TRACKING_PARAMS
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>();
+        for (raw, expect) in [
+            ("https://example.com", "https://example.com/"),
+            ("  https://example.com/  ", "https://example.com/"),
+            ("HTTPS://EXAMPLE.com/Path", "https://example.com/Path"),
+            ("https://example.com:443/", "https://example.com/"),
+            ("http://example.com:80/", "http://example.com/"),
+            ("http://example.com:8080/", "http://example.com:8080/"),
+            ("https://example.com/a/b/", "https://example.com/a/b"),
+            ("https://例子.测试/", "https://xn--fsqu00a.xn--0zwm56d/"),
+            (
+                "https://example.com/?b=2&a=1",
+                "https://example.com/?a=1&b=2",
+            ),
+            (
+                "https://example.com/?utm_source=x&UTM_Medium=y&id=1&fbclid=z",
+                "https://example.com/?id=1",
+            ),
+            ("https://example.com/?utm_source=x", "https://example.com/"),
+            ("https://example.com/#", "https://example.com/"),
+            ("https://example.com/#top", "https://example.com/#top"),
+            ("not a url", "not a url"),
+            ("mailto:someone@example.com", "mailto:someone@example.com"),
+        ] {
+            assert_eq!(canonicalize_with(raw, &patterns), expect, "{raw}");
+        }
+    }
+
+    #[test]
+    fn test_near_equal_key() {
+        for (canonical, expect) in [
+            ("https://example.com/", "example.com"),
+            ("http://www.example.com/", "example.com"),
+            ("https://example.com/a?b=1", "example.com/a?b=1"),
+            ("mailto:someone@example.com", "mailto:someone@example.com"),
+        ] {
+            assert_eq!(near_equal_key(canonical), expect, "{canonical}");
+        }
+    }
+
+    #[test]
+    fn test_custom_tracking_params() {
+        let patterns = vec!["ref".to_string(), "src_*".to_string()];
+        assert_eq!(
+            canonicalize_with(
+                "https://example.com/?ref=hn&src_a=1&utm_source=x",
+                &patterns
+            ),
+            "https://example.com/?utm_source=x"
+        );
+    }
+}
@@ -20,6 +20,36 @@ pub enum BearQLError {
     EmptyTag,
     #[error("Empty keyword error")]
     EmptyKeyword,
+    #[error("Empty value of qualifier {0} error")]
+    EmptyQualifier(String),
+    #[error("Unknown qualifier {0} error")]
+    UnknownQualifier(String),
+}
+
+#[derive(Error, Debug)]
+pub enum FetchError {
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("Refused to request a private address: {0}")]
+    PrivateAddress(String),
+    #[error("Request failed: {0}")]
+    Request(#[from] reqwest::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("Archiving is disabled along with the fetcher")]
+    Disabled,
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+    #[error("Unexpected HTTP status {0}")]
+    Status(u16),
+    #[error("Page is larger than {0} bytes")]
+    TooLarge(usize),
+    #[error("Archive content is missing in the {0} storage")]
+    Missing(String),
+    #[error("Storage error: {0}")]
+    Storage(#[from] std::io::Error),
 }
 
 #[derive(Error, Debug)]
@@ -29,4 +59,7 @@ pub enum CommonError {
 
     #[error(transparent)]
     BearQL(#[from] BearQLError),
+
+    #[error("Cannot change reading state from {from} to {to}")]
+    InvalidTransition { from: String, to: String },
 }
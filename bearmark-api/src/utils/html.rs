@@ -0,0 +1,81 @@
+/// Escape the text to be put in HTML, either as content or a quoted attribute value.
+pub fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A link to the URL, or the text alone if the URL is not HTTP(S), so a `javascript:` URL
+/// never becomes a link.
+pub fn render_link(url: &str, text: &str) -> String {
+    let linkable = url::Url::parse(url).is_ok_and(|u| matches!(u.scheme(), "http" | "https"));
+    if linkable {
+        format!("<a href=\"{}\">{}</a>", escape_html(url), escape_html(text))
+    } else {
+        escape_html(text)
+    }
+}
+
+/// The `Content-Security-Policy` of a page which loads nothing, and runs nothing but the given
+/// inline scripts.
+pub fn content_security_policy(scripts: &[&str]) -> String {
+    use base64::Engine;
+    use base64::prelude::BASE64_STANDARD;
+    use sha2::{Digest, Sha256};
+
+    let mut policy = "default-src 'none'; form-action 'self'".to_string();
+    if !scripts.is_empty() {
+        policy.push_str("; script-src");
+        for script in scripts {
+            let hash = BASE64_STANDARD.encode(Sha256::digest(script));
+            policy.push_str(&format!(" 'sha256-{hash}'"));
+        }
+    }
+    policy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+    }
+    #[test]
+    fn test_render_link() {
+        assert_eq!(
+            render_link("https://example.com/?a=1&b=2", "<Example>"),
+            r#"<a href="https://example.com/?a=1&amp;b=2">&lt;Example&gt;</a>"#
+        );
+        assert_eq!(render_link(" JavaScript:alert(1)", "x"), "x");
+        assert_eq!(render_link("mailto:someone@example.com", "mail"), "mail");
+        assert_eq!(render_link("not a url", "<none>"), "&lt;none&gt;");
+    }
+
+    #[test]
+    fn test_content_security_policy() {
+        assert_eq!(
+            content_security_policy(&[]),
+            "default-src 'none'; form-action 'self'"
+        );
+        // the hash of `alert(1)`
+        assert_eq!(
+            content_security_policy(&["alert(1)"]),
+            "default-src 'none'; form-action 'self'; \
+            script-src 'sha256-bhHHL3z2vDgxUt0W3dWQOrprscmda2Y5pLsLg4GF+pI='"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! A minimal writer of iCalendar (RFC 5545) feeds.
+
+use time::OffsetDateTime;
+use time::macros::format_description;
+
+/// Content lines longer than this in octets are folded.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// An event of a calendar.
+#[derive(Debug, Clone)]
+pub struct Event {
+    /// Globally unique and persistent across updates
+    pub uid: String,
+    /// When the event was updated last time
+    pub stamp: OffsetDateTime,
+    pub start: OffsetDateTime,
+    pub summary: String,
+    pub description: Option<String>,
+    pub url: Option<String>,
+}
+
+fn format_datetime(dt: OffsetDateTime) -> String {
+    dt.to_offset(time::UtcOffset::UTC)
+        .format(format_description!(
+            "[year][month][day]T[hour][minute][second]Z"
+        ))
+        .expect("Error formatting date time")
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Write a content line, folding it without splitting a character.
+fn write_line(out: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            // the leading space is counted
+            octets = 1;
+        }
+        out.push(c);
+        octets += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// Render a calendar of the events.
+pub fn render_calendar(name: &str, events: &[Event]) -> String {
+    let mut out = String::new();
+    write_line(&mut out, "BEGIN:VCALENDAR");
+    write_line(&mut out, "VERSION:2.0");
+    write_line(&mut out, "PRODID:-//bearmark//reminders//EN");
+    write_line(&mut out, "CALSCALE:GREGORIAN");
+    write_line(&mut out, &format!("X-WR-CALNAME:{}", escape_text(name)));
+    for event in events {
+        write_line(&mut out, "BEGIN:VEVENT");
+        write_line(&mut out, &format!("UID:{}", event.uid));
+        write_line(
+            &mut out,
+            &format!("DTSTAMP:{}", format_datetime(event.stamp)),
+        );
+        write_line(
+            &mut out,
+            &format!("DTSTART:{}", format_datetime(event.start)),
+        );
+        write_line(&mut out, "DURATION:PT30M");
+        write_line(
+            &mut out,
+            &format!("SUMMARY:{}", escape_text(&event.summary)),
+        );
+        if let Some(description) = &event.description {
+            write_line(
+                &mut out,
+                &format!("DESCRIPTION:{}", escape_text(description)),
+            );
+        }
+        if let Some(url) = &event.url {
+            write_line(&mut out, &format!("URL:{url}"));
+        }
+        write_line(&mut out, "END:VEVENT");
+    }
+    write_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_render_calendar() {
+        let rendered = render_calendar(
+            "Reminders",
+            &[Event {
+                uid: "bookmark-1@bearmark".to_string(),
+                stamp: datetime!(2024-01-01 08:00:00 +08:00),
+                start: datetime!(2024-02-01 09:30:00 UTC),
+                summary: "Docs; read, before release\\".to_string(),
+                description: Some("line 1\nline 2".to_string()),
+                url: Some("https://example.com/docs".to_string()),
+            }],
+        );
+        assert_eq!(
+            rendered,
+            "BEGIN:VCALENDAR\r\n\
+            VERSION:2.0\r\n\
+            PRODID:-//bearmark//reminders//EN\r\n\
+            CALSCALE:GREGORIAN\r\n\
+            X-WR-CALNAME:Reminders\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:bookmark-1@bearmark\r\n\
+            DTSTAMP:20240101T000000Z\r\n\
+            DTSTART:20240201T093000Z\r\n\
+            DURATION:PT30M\r\n\
+            SUMMARY:Docs\\; read\\, before release\\\\\r\n\
+            DESCRIPTION:line 1\\nline 2\r\n\
+            URL:https://example.com/docs\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n"
+        );
+    }
+
+    #[test]
+    fn test_fold_lines() {
+        let mut out = String::new();
+        write_line(&mut out, &format!("SUMMARY:{}", "é".repeat(40)));
+        let lines = out.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|l| l.len() <= MAX_LINE_OCTETS));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(lines[2], "");
+        assert_eq!(
+            out.replace("\r\n ", ""),
+            format!("SUMMARY:{}\r\n", "é".repeat(40))
+        );
+    }
+}
@@ -1,6 +1,10 @@
+pub mod canonical_url;
+pub mod html;
+pub mod icalendar;
 pub mod logging;
+pub mod nullable;
 #[cfg(test)]
 pub mod rand;
 
 mod errors;
-pub use errors::{BearQLError, CommonError, DatabaseError};
+pub use errors::{ArchiveError, BearQLError, CommonError, DatabaseError, FetchError};
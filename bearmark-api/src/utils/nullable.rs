@@ -0,0 +1,32 @@
+use rocket::serde::{Deserialize, Deserializer, Serializer};
+
+/// Deserialize a present field into `Some`, so that an explicit `null` becomes `Some(None)`
+/// while a missing field stays `None` with `#[serde(default)]`.
+pub fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Deserialize::deserialize(deserializer).map(Some)
+}
+
+/// The same as [`deserialize_some`], for a date time in RFC 3339.
+pub fn deserialize_some_rfc3339<'de, D>(
+    deserializer: D,
+) -> Result<Option<Option<time::OffsetDateTime>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    time::serde::rfc3339::option::deserialize(deserializer).map(Some)
+}
+
+/// Serialize a field deserialized by [`deserialize_some_rfc3339`], which must be skipped if `None`.
+pub fn serialize_some_rfc3339<S>(
+    value: &Option<Option<time::OffsetDateTime>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    time::serde::rfc3339::option::serialize(&value.flatten(), serializer)
+}
@@ -15,6 +15,8 @@ pub enum Query<'a> {
     Parenthesized(BBox<'a, Query<'a>>),
     #[rule(r#"#{0:`\w*`}"#, group = 2)]
     Tag(BString<'a>),
+    #[rule(r#"{0:`[\w.]+`}:{1:`[^\s()|]*`}"#, group = 2)]
+    Qualifier(BString<'a>, BString<'a>),
     #[rule(r#"{0:`\w+`}"#, group = 2)]
     Keyword(BString<'a>),
     #[rule(r#"{0:`(\.)?(/\w+)*/{0,2}`}"#, group = 3)]
@@ -101,6 +103,28 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_primitive_qualifier() {
+        let bump = bumpalo::Bump::new();
+        for (src, key, value) in [
+            ("notes:foo", "notes", "foo"),       // qualifier notes with value foo
+            ("notes:", "notes", ""),             // empty value
+            ("site:docs.rs", "site", "docs.rs"), // value with dots
+            ("attr.owner:*", "attr.owner", "*"), // key with dots
+        ] {
+            let rv = Parser::<Query>::parse_with(src, &bump);
+            info!(?rv, src, "parse result");
+            assert!(rv.is_ok());
+            assert_eq!(
+                rv.unwrap(),
+                Qualifier(
+                    BString::from_str_in(key, &bump),
+                    BString::from_str_in(value, &bump)
+                )
+            );
+        }
+    }
+
     #[test]
     fn test_query_and() {
         let src = r#"#title | trust rust"#;
@@ -138,6 +162,8 @@ mod test {
             r#"title ( #rust  #langs )"#,
             r#"title ( #rust | #langs )"#,
             r#"/blog/"#,
+            r#"notes:rust #langs"#,
+            r#"title ( notes:rust | #langs )"#,
         ] {
             let rv = Parser::<Query>::parse_with(src, &bump);
             info!(?rv, ?src, "parsed");
@@ -0,0 +1,6 @@
+DROP TABLE canonical_url_params;
+
+DROP INDEX bookmarks_canonical_url_idx;
+
+ALTER TABLE bookmarks
+    DROP COLUMN canonical_url;
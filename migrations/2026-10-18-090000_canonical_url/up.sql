@@ -0,0 +1,17 @@
+ALTER TABLE bookmarks
+    ADD COLUMN canonical_url VARCHAR;
+
+UPDATE bookmarks
+SET canonical_url = url;
+
+ALTER TABLE bookmarks
+    ALTER COLUMN canonical_url SET NOT NULL;
+
+CREATE INDEX bookmarks_canonical_url_idx ON bookmarks (canonical_url);
+
+-- The tracking parameters stripped from the canonical URLs, no row until they're canonicalized
+CREATE TABLE canonical_url_params(
+    id boolean PRIMARY KEY DEFAULT TRUE CHECK (id),
+    tracking_params text[] NOT NULL,
+    updated_at timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1 @@
+DROP INDEX bookmarks_url_idx;
@@ -0,0 +1 @@
+CREATE INDEX bookmarks_url_idx ON bookmarks (url);
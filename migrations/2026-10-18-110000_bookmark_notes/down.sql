@@ -0,0 +1,3 @@
+ALTER TABLE bookmarks
+    DROP COLUMN notes,
+    DROP COLUMN description;
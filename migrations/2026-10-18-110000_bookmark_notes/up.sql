@@ -0,0 +1,3 @@
+ALTER TABLE bookmarks
+    ADD COLUMN notes TEXT,
+    ADD COLUMN description VARCHAR;
@@ -0,0 +1,16 @@
+CREATE TABLE bookmark_metadata(
+    bookmark_id integer PRIMARY KEY REFERENCES bookmarks(id) ON DELETE CASCADE,
+    status varchar NOT NULL DEFAULT 'pending',
+    http_status integer,
+    error varchar,
+    title varchar,
+    description varchar,
+    image_url varchar,
+    site_name varchar,
+    favicon_url varchar,
+    fetched_at timestamp(6) with time zone,
+    updated_at timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX bookmark_metadata_status_idx ON bookmark_metadata (status);
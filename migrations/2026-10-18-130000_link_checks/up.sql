@@ -0,0 +1,10 @@
+CREATE TABLE link_checks(
+    bookmark_id integer PRIMARY KEY REFERENCES bookmarks(id) ON DELETE CASCADE,
+    status_code integer,
+    redirect_url varchar,
+    error varchar,
+    consecutive_failures integer NOT NULL DEFAULT 0,
+    checked_at timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX link_checks_checked_at_idx ON link_checks (checked_at);
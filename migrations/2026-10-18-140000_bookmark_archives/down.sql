@@ -0,0 +1,3 @@
+DROP TABLE archive_blobs;
+
+DROP TABLE bookmark_archives;
@@ -0,0 +1,18 @@
+CREATE TABLE bookmark_archives(
+    id serial PRIMARY KEY,
+    bookmark_id integer NOT NULL REFERENCES bookmarks(id) ON DELETE CASCADE,
+    content_hash varchar NOT NULL,
+    content_type varchar NOT NULL,
+    size integer NOT NULL,
+    storage varchar NOT NULL,
+    created_at timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX bookmark_archives_bookmark_id_idx ON bookmark_archives (bookmark_id);
+
+-- contents of archives in the postgres storage, addressed by their hashes
+CREATE TABLE archive_blobs(
+    content_hash varchar PRIMARY KEY,
+    content bytea NOT NULL,
+    created_at timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
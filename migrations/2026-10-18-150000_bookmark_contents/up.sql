@@ -0,0 +1,8 @@
+CREATE TABLE bookmark_contents(
+    bookmark_id integer PRIMARY KEY REFERENCES bookmarks(id) ON DELETE CASCADE,
+    content text NOT NULL,
+    updated_at timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- The expression must be the same as the one in searching, to make the index usable
+CREATE INDEX bookmark_contents_content_idx ON bookmark_contents USING GIN (to_tsvector('english', content));
@@ -0,0 +1,3 @@
+ALTER TABLE bookmarks
+    DROP COLUMN visit_count,
+    DROP COLUMN last_visited_at;
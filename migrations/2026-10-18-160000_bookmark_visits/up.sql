@@ -0,0 +1,3 @@
+ALTER TABLE bookmarks
+    ADD COLUMN visit_count integer NOT NULL DEFAULT 0,
+    ADD COLUMN last_visited_at timestamp(6) with time zone;
@@ -0,0 +1,5 @@
+DROP INDEX bookmarks_pinned_idx;
+
+ALTER TABLE bookmarks
+    DROP COLUMN pinned,
+    DROP COLUMN pin_order;
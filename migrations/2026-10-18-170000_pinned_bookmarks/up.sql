@@ -0,0 +1,5 @@
+ALTER TABLE bookmarks
+    ADD COLUMN pinned boolean NOT NULL DEFAULT false,
+    ADD COLUMN pin_order integer;
+
+CREATE INDEX bookmarks_pinned_idx ON bookmarks (pin_order) WHERE pinned;
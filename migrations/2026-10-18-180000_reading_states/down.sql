@@ -0,0 +1,7 @@
+DROP INDEX bookmarks_reading_queue_idx;
+
+ALTER TABLE bookmarks
+    DROP COLUMN reading_state,
+    DROP COLUMN reading_state_changed_at,
+    DROP COLUMN queued_at,
+    DROP COLUMN read_at;
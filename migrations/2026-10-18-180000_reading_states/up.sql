@@ -0,0 +1,7 @@
+ALTER TABLE bookmarks
+    ADD COLUMN reading_state varchar,
+    ADD COLUMN reading_state_changed_at timestamp(6) with time zone,
+    ADD COLUMN queued_at timestamp(6) with time zone,
+    ADD COLUMN read_at timestamp(6) with time zone;
+
+CREATE INDEX bookmarks_reading_queue_idx ON bookmarks (queued_at) WHERE reading_state = 'unread';
@@ -0,0 +1,15 @@
+CREATE TABLE bookmark_revisions(
+    id serial PRIMARY KEY,
+    bookmark_id integer NOT NULL REFERENCES bookmarks(id) ON DELETE CASCADE,
+    old_title varchar NOT NULL,
+    new_title varchar NOT NULL,
+    old_url varchar NOT NULL,
+    new_url varchar NOT NULL,
+    old_folder varchar,
+    new_folder varchar,
+    old_tags text[] NOT NULL,
+    new_tags text[] NOT NULL,
+    created_at timestamp(6) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX bookmark_revisions_bookmark_id_idx ON bookmark_revisions (bookmark_id);
@@ -0,0 +1,3 @@
+DROP INDEX bookmarks_remind_at_idx;
+
+ALTER TABLE bookmarks DROP COLUMN remind_at;
@@ -0,0 +1,3 @@
+ALTER TABLE bookmarks ADD COLUMN remind_at timestamp(6) with time zone;
+
+CREATE INDEX bookmarks_remind_at_idx ON bookmarks (remind_at) WHERE remind_at IS NOT NULL;
@@ -0,0 +1,3 @@
+DROP INDEX bookmarks_attributes_idx;
+
+ALTER TABLE bookmarks DROP COLUMN attributes;
@@ -0,0 +1,3 @@
+ALTER TABLE bookmarks ADD COLUMN attributes jsonb NOT NULL DEFAULT '{}';
+
+CREATE INDEX bookmarks_attributes_idx ON bookmarks USING GIN (attributes);
@@ -0,0 +1,3 @@
+DROP INDEX bookmarks_shortcut_idx;
+
+ALTER TABLE bookmarks DROP COLUMN shortcut;
@@ -0,0 +1,4 @@
+ALTER TABLE bookmarks ADD COLUMN shortcut varchar;
+
+-- Shortcuts of bookmarks in the trash don't hold the keyword
+CREATE UNIQUE INDEX bookmarks_shortcut_idx ON bookmarks (shortcut) WHERE deleted_at IS NULL;